@@ -1,9 +1,9 @@
 mod cli;
 
 #[cfg(feature = "experimental")]
-mod http;
+pub(crate) mod http;
 
 pub use self::cli::Cli;
 
 #[cfg(feature = "experimental")]
-pub use self::http::Http;
+pub use self::http::{ConnectionError, Http, PullSummary};
@@ -1,16 +1,22 @@
+#[cfg(feature = "grpc")]
+use crate::core::grpc;
 use crate::{
     core::{
         env::{self, GetEnvValue},
-        logs::LogStream,
+        health::{self, supports_start_interval, HealthReport, REPORT_LOG_LINES},
+        http_probe::{self, HttpProbe},
+        logs::{LogStream, WaitError},
         ports::Ports,
+        restarts::RestartTracker,
         ContainerState, Docker, WaitFor,
     },
-    Container, Image, ImageArgs, RunnableImage,
+    Container, Image, RunnableImage,
 };
 use bollard_stubs::models::{ContainerInspectResponse, HealthStatusEnum};
 use std::{
     collections::HashMap,
     ffi::{OsStr, OsString},
+    net::TcpStream,
     process::{Child, Command, Stdio},
     sync::{Arc, RwLock},
     thread::sleep,
@@ -30,10 +36,11 @@ pub struct Cli {
 
 impl Cli {
     pub fn run<I: Image>(&self, image: impl Into<RunnableImage<I>>) -> Container<I> {
-        let image = image.into();
+        let image = self.inner.supported_healthcheck(image.into());
+        let image = self.inner.resolve_docker_socket(image);
 
         if let Some(network) = image.network() {
-            if self.inner.create_network_if_not_exists(network) {
+            if self.inner.create_network_if_not_exists(network, &image) {
                 let mut guard = self
                     .inner
                     .created_networks
@@ -62,13 +69,18 @@ impl Cli {
             .to_string();
 
         #[cfg(feature = "watchdog")]
-        if self.inner.command == env::Command::Remove {
-            crate::watchdog::register(container_id.clone());
+        if self.inner.command != env::Command::Keep {
+            crate::watchdog::register(container_id.clone(), self.inner.docker_host.clone());
         }
 
         self.inner.register_container_started(container_id.clone());
+        self.inner.chown_mounts(&container_id, &image);
 
-        self.block_until_ready(&container_id, image.ready_conditions());
+        self.block_until_ready(
+            &container_id,
+            image.ready_conditions(),
+            image.poll_interval(),
+        );
 
         let client = Cli {
             inner: self.inner.clone(),
@@ -87,10 +99,11 @@ impl Cli {
     }
 
     pub fn run_cmd<I: Image>(&self, image: impl Into<RunnableImage<I>>) -> Command {
-        let image = image.into();
+        let image = self.inner.supported_healthcheck(image.into());
+        let image = self.inner.resolve_docker_socket(image);
 
         if let Some(network) = image.network() {
-            if self.inner.create_network_if_not_exists(network) {
+            if self.inner.create_network_if_not_exists(network, &image) {
                 let mut guard = self
                     .inner
                     .created_networks
@@ -118,11 +131,61 @@ struct Client {
     created_networks: RwLock<Vec<String>>,
     binary: OsString,
     command: env::Command,
+    /// The address of the Docker daemon, unless it is taken from the environment.
+    docker_host: Option<String>,
 }
 
 impl Client {
+    /// The address of the Docker daemon the CLI talks to.
+    fn docker_host(&self) -> Option<String> {
+        self.docker_host
+            .clone()
+            .or_else(|| env::Os::get_env_value("DOCKER_HOST"))
+    }
+
+    /// The host on which the ports of containers are published, see [`env::published_ports_host`].
+    fn host(&self) -> String {
+        env::published_ports_host(self.docker_host().as_deref())
+    }
+
+    /// Resolves the image to run, with the Docker socket of the daemon mounted if requested.
+    fn resolve_docker_socket<I: Image>(&self, image: RunnableImage<I>) -> RunnableImage<I> {
+        image.with_resolved_docker_socket(|| {
+            env::docker_socket::<env::Os>(self.docker_host().as_deref())
+        })
+    }
+
+    /// Makes the configured users the owners of their bind mounts in the container `id`, see
+    /// [`RunnableImage::with_owned_bind_mount`].
+    fn chown_mounts<I: Image>(&self, id: &str, image: &RunnableImage<I>) {
+        for (path, user) in image.mount_owners() {
+            let output = self
+                .chown_command(id, user, path)
+                .output()
+                .expect("Failed to execute docker command");
+            if !output.status.success() {
+                panic!(
+                    "failed to make {user} the owner of {path} in container {id}: {}",
+                    String::from_utf8_lossy(&output.stderr).trim_end()
+                )
+            }
+        }
+    }
+
+    fn chown_command(&self, id: &str, user: &str, path: &str) -> Command {
+        let mut command = self.command();
+        command.args(["exec", "--user", "0", id, "chown", "-R", user, path]);
+
+        command
+    }
+
     fn command(&self) -> Command {
-        Command::new(self.binary.clone())
+        let mut command = Command::new(self.binary.clone());
+        if let Some(docker_host) = &self.docker_host {
+            command.env("DOCKER_HOST", docker_host);
+        }
+
+        command
     }
 
     fn register_container_started(&self, id: String) {
@@ -175,10 +238,122 @@ impl Client {
             command.arg("--privileged");
         }
 
+        if let Some(runtime) = image.runtime() {
+            command.arg(format!("--runtime={runtime}"));
+        }
+
+        if image.tty() {
+            command.arg("--tty");
+        }
+
         if let Some(bytes) = image.shm_size() {
             command.arg(format!("--shm-size={bytes}"));
         }
 
+        for (path, options) in image.tmpfs() {
+            command.arg("--tmpfs").arg(format!("{path}:{options}"));
+        }
+
+        if let Some(bytes) = image.memory_reservation() {
+            command.arg(format!("--memory-reservation={bytes}"));
+        }
+
+        if let Some(platform) = image.platform() {
+            command.arg(format!("--platform={platform}"));
+        }
+
+        if let Some(hostname) = image.hostname() {
+            command.arg(format!("--hostname={hostname}"));
+        }
+
+        if let Some(domainname) = image.domainname() {
+            command.arg(format!("--domainname={domainname}"));
+        }
+
+        for (key, value) in image.labels() {
+            command.arg(format!("--label={key}={value}"));
+        }
+
+        for capability in image.cap_add() {
+            command.arg(format!("--cap-add={capability}"));
+        }
+
+        for capability in image.cap_drop() {
+            command.arg(format!("--cap-drop={capability}"));
+        }
+
+        if let Some(mode) = image.userns_mode() {
+            command.arg(format!("--userns={mode}"));
+        }
+
+        if let Some(spec) = image.exec_spec() {
+            if let Some(user) = &spec.user {
+                command.arg(format!("--user={user}"));
+            }
+            if let Some(working_dir) = &spec.working_dir {
+                command.arg(format!("--workdir={working_dir}"));
+            }
+        }
+
+        if let Some(healthcheck) = image.healthcheck() {
+            match healthcheck.test.split_first() {
+                Some((kind, _)) if kind == "NONE" => {
+                    command.arg("--no-healthcheck");
+                }
+                Some((kind, args)) if kind == "CMD-SHELL" => {
+                    command.arg(format!("--health-cmd={}", args.join(" ")));
+                }
+                // the CLI always runs the test in a shell, which must not split the arguments
+                Some((_, args)) => {
+                    let args: Vec<_> = args.iter().map(|arg| shell_quote(arg)).collect();
+                    command.arg(format!("--health-cmd={}", args.join(" ")));
+                }
+                None => {}
+            }
+            if let Some(interval) = healthcheck.interval {
+                command.arg(format!("--health-interval={}ms", interval.as_millis()));
+            }
+            if let Some(timeout) = healthcheck.timeout {
+                command.arg(format!("--health-timeout={}ms", timeout.as_millis()));
+            }
+            if let Some(retries) = healthcheck.retries {
+                command.arg(format!("--health-retries={retries}"));
+            }
+            if let Some(start_period) = healthcheck.start_period {
+                command.arg(format!(
+                    "--health-start-period={}ms",
+                    start_period.as_millis()
+                ));
+            }
+            if let Some(start_interval) = healthcheck.start_interval {
+                command.arg(format!(
+                    "--health-start-interval={}ms",
+                    start_interval.as_millis()
+                ));
+            }
+        }
+
+        for (key, value) in image.storage_opts() {
+            command.arg("--storage-opt").arg(format!("{key}={value}"));
+        }
+
+        let resources = image.resources();
+        if let Some(bytes) = resources.memory {
+            command.arg(format!("--memory={bytes}"));
+        }
+        if let Some(bytes) = resources.memory_swap {
+            command.arg(format!("--memory-swap={bytes}"));
+        }
+        if let Some(nano_cpus) = resources.nano_cpus {
+            command.arg(format!("--cpus={}", nano_cpus as f64 / 1_000_000_000.0));
+        }
+        if let Some(shares) = resources.cpu_shares {
+            command.arg(format!("--cpu-shares={shares}"));
+        }
+        if let Some(limit) = resources.pids_limit {
+            command.arg(format!("--pids-limit={limit}"));
+        }
+
         if let Some(network) = image.network() {
             command.arg(format!("--network={network}"));
         }
@@ -191,6 +366,10 @@ impl Client {
             command.arg("-e").arg(format!("{key}={value}"));
         }
 
+        for (key, value) in image.runtime_env_vars::<env::Os>() {
+            command.arg("-e").arg(format!("{key}={value}"));
+        }
+
         for (key, value) in image.hosts() {
             command.arg("--add-host").arg(format!("{key}:{value}"));
         }
@@ -199,6 +378,12 @@ impl Client {
             command.arg("-v").arg(format!("{orig}:{dest}"));
         }
 
+        for (container, mode) in image.volumes_from() {
+            command
+                .arg("--volumes-from")
+                .arg(format!("{container}:{mode}"));
+        }
+
         if let Some(entrypoint) = image.entrypoint() {
             command.arg("--entrypoint").arg(entrypoint);
         }
@@ -208,7 +393,9 @@ impl Client {
             .as_ref()
             .map(|network| network.starts_with("container:"))
             .unwrap_or(false);
-        if let Some(ports) = image.ports() {
+        if !image.publish_ports() {
+            // the container is only reachable from within its network
+        } else if let Some(ports) = image.ports() {
             for port in ports {
                 command
                     .arg("-p")
@@ -216,7 +403,11 @@ impl Client {
             }
         } else if !is_container_networked {
             for port in image.expose_ports() {
-                command.arg(format!("--expose={port}"));
+                if image.expose_on_localhost() {
+                    command.arg("-p").arg(format!("127.0.0.1::{port}"));
+                } else {
+                    command.arg(format!("--expose={port}"));
+                }
             }
             command.arg("-P"); // publish all exposed ports
         }
@@ -231,7 +422,7 @@ impl Client {
 
         command
             .arg(image.descriptor())
-            .args(image.args().clone().into_iterator())
+            .args(image.cmd())
             .stdout(Stdio::piped());
 
         command
@@ -241,13 +432,23 @@ impl Client {
         Client::run_command(image, command, true)
     }
 
-    fn create_network_if_not_exists(&self, name: &str) -> bool {
+    fn create_network_if_not_exists<I: Image>(&self, name: &str, image: &RunnableImage<I>) -> bool {
         if self.network_exists(name) {
             return false;
         }
 
         let mut docker = self.command();
-        docker.args(["network", "create", name]);
+        docker.args(["network", "create"]);
+
+        if let Some(driver) = image.network_driver() {
+            docker.arg(format!("--driver={driver}"));
+        }
+
+        for (key, value) in image.network_driver_opts() {
+            docker.arg("--opt").arg(format!("{key}={value}"));
+        }
+
+        docker.arg(name);
 
         let output = docker.output().expect("failed to create docker network");
         assert!(output.status.success(), "failed to create docker network");
@@ -255,6 +456,32 @@ impl Client {
         true
     }
 
+    /// Removes the start interval of the healthcheck of `image` if the Docker daemon is too old to
+    /// support it.
+    fn supported_healthcheck<I: Image>(&self, image: RunnableImage<I>) -> RunnableImage<I> {
+        let has_start_interval = image
+            .healthcheck()
+            .as_ref()
+            .map_or(false, |healthcheck| healthcheck.start_interval.is_some());
+        if !has_start_interval || supports_start_interval(&self.api_version()) {
+            return image;
+        }
+
+        log::warn!(
+            "Ignoring the start interval of the healthcheck, it requires Docker 25 or newer"
+        );
+        image.without_health_start_interval()
+    }
+
+    /// Returns the API version of the Docker daemon, e.g. `1.43`.
+    fn api_version(&self) -> String {
+        let mut docker = self.command();
+        docker.args(["version", "--format", "{{.Server.APIVersion}}"]);
+
+        let output = docker.output().expect("failed to query docker version");
+        String::from_utf8(output.stdout).expect("output is not valid utf-8")
+    }
+
     fn network_exists(&self, name: &str) -> bool {
         let mut docker = self.command();
         docker.args(["network", "ls", "--format", "{{.Name}}"]);
@@ -265,6 +492,45 @@ impl Client {
         output.lines().any(|network| network == name)
     }
 
+    /// Returns the last `lines` lines the container has written to stdout and stderr so far.
+    fn recent_logs(&self, id: &str, lines: usize) -> (String, String) {
+        let output = self
+            .command()
+            .arg("logs")
+            .arg(format!("--tail={lines}"))
+            .arg(id)
+            .output()
+            .expect("Failed to execute docker command");
+
+        (
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        )
+    }
+
+    /// Force-removes a container, along with its anonymous volumes if `remove_volumes` is set.
+    fn rm(&self, id: &str, remove_volumes: bool) {
+        let mut command = self.command();
+        command.arg("rm").arg("-f");
+        if remove_volumes {
+            command.arg("-v");
+        }
+        let output = command
+            .arg(id)
+            .output()
+            .expect("Failed to execute docker command");
+        let error_msg = "Failed to remove docker container";
+        assert!(output.status.success(), "{}", error_msg);
+        // The container's id is printed on stdout if it was removed successfully.
+        assert!(
+            String::from_utf8(output.stdout)
+                .expect("Could not decode daemon's response.")
+                .contains(id),
+            "{}",
+            error_msg
+        );
+    }
+
     fn delete_networks<I, S>(&self, networks: I)
     where
         I: IntoIterator<Item = S>,
@@ -284,6 +550,16 @@ impl Client {
     }
 }
 
+/// Quotes `arg` for a POSIX shell unless it consists of characters without special meaning only.
+fn shell_quote(arg: &str) -> String {
+    let is_plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_plain) {
+        return arg.to_owned();
+    }
+
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
 impl Default for Cli {
     fn default() -> Self {
         Self::new::<env::Os>()
@@ -301,6 +577,28 @@ impl Cli {
                 created_networks: Default::default(),
                 binary: "docker".into(),
                 command: env::command::<E>().unwrap_or_default(),
+                docker_host: None,
+            }),
+        }
+    }
+
+    /// Creates a client for the Docker daemon at the given address, or the one configured in the
+    /// environment if `docker_host` is `None`.
+    #[cfg(feature = "watchdog")]
+    pub(crate) fn for_docker_host(docker_host: Option<String>) -> Self {
+        Self::with_binary("docker", docker_host)
+    }
+
+    /// Like [`Cli::for_docker_host`], running `binary` instead of `docker`.
+    #[cfg(feature = "watchdog")]
+    pub(crate) fn with_binary(binary: impl Into<OsString>, docker_host: Option<String>) -> Self {
+        Self {
+            inner: Arc::new(Client {
+                container_startup_timestamps: Default::default(),
+                created_networks: Default::default(),
+                binary: binary.into(),
+                command: env::command::<env::Os>().unwrap_or_default(),
+                docker_host,
             }),
         }
     }
@@ -343,6 +641,32 @@ impl Docker for Cli {
         LogStream::new(child.stderr.expect("stderr to be captured"))
     }
 
+    fn combined_logs(&self, id: &str) -> LogStream {
+        self.inner
+            .wait_at_least_one_second_after_container_was_started(id);
+
+        let mut child = self
+            .inner
+            .command()
+            .arg("logs")
+            .arg("-f")
+            .arg(id)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Failed to execute docker command");
+
+        let stdout = child.stdout.take().expect("stdout to be captured");
+        let stderr = child.stderr.take().expect("stderr to be captured");
+        LogStream::interleaved(stdout, stderr, move || {
+            let _ = child.wait();
+        })
+    }
+
+    fn host(&self) -> String {
+        self.inner.host()
+    }
+
     fn ports(&self, id: &str) -> Ports {
         self.inspect(id)
             .network_settings
@@ -377,25 +701,11 @@ impl Docker for Cli {
     }
 
     fn rm(&self, id: &str) {
-        let output = self
-            .inner
-            .command()
-            .arg("rm")
-            .arg("-f")
-            .arg("-v") // Also remove volumes
-            .arg(id)
-            .output()
-            .expect("Failed to execute docker command");
-        let error_msg = "Failed to remove docker container";
-        assert!(output.status.success(), "{}", error_msg);
-        // The container's id is printed on stdout if it was removed successfully.
-        assert!(
-            String::from_utf8(output.stdout)
-                .expect("Could not decode daemon's response.")
-                .contains(id),
-            "{}",
-            error_msg
-        );
+        self.inner.rm(id, true)
+    }
+
+    fn rm_keep_volumes(&self, id: &str) {
+        self.inner.rm(id, false)
     }
 
     fn stop(&self, id: &str) {
@@ -441,7 +751,7 @@ impl Docker for Cli {
         exec_output
     }
 
-    fn block_until_ready(&self, id: &str, ready_conditions: Vec<WaitFor>) {
+    fn block_until_ready(&self, id: &str, ready_conditions: Vec<WaitFor>, poll_interval: Duration) {
         log::debug!("Waiting for container {} to be ready", id);
 
         for condition in ready_conditions {
@@ -452,6 +762,32 @@ impl Docker for Cli {
                 WaitFor::StdErrMessage { message } => {
                     self.stderr_logs(id).wait_for_message(&message).unwrap()
                 }
+                WaitFor::AnyStreamMessage { message } => {
+                    self.combined_logs(id).wait_for_message(&message).unwrap()
+                }
+                WaitFor::StdOutMessageWithinLines { message, max_lines } => {
+                    match self
+                        .stdout_logs(id)
+                        .wait_for_message_within_lines(&message, max_lines)
+                    {
+                        Ok(()) => {}
+                        Err(WaitError::LineLimitExceeded(lines)) => panic!(
+                            "failed to find message `{message}` in {} lines of the logs of container {id}",
+                            lines.len()
+                        ),
+                        Err(e) => panic!("{e:?}"),
+                    }
+                }
+                WaitFor::StdOutRegex { pattern } => {
+                    self.stdout_logs(id).wait_for_match(&pattern).unwrap()
+                }
+                WaitFor::StdErrRegex { pattern } => {
+                    self.stderr_logs(id).wait_for_match(&pattern).unwrap()
+                }
+                WaitFor::StdOutMessageSequence { messages } => self
+                    .stdout_logs(id)
+                    .wait_for_messages_in_order(&messages)
+                    .unwrap(),
                 WaitFor::Duration { length } => {
                     std::thread::sleep(length);
                 }
@@ -472,9 +808,151 @@ impl Docker for Cli {
                             panic!("Healthcheck not configured for container")
                         }
                         Some(UNHEALTHY) => panic!("Healthcheck reports unhealthy"),
-                        Some(STARTING) => sleep(Duration::from_millis(100)),
+                        Some(STARTING) => sleep(poll_interval),
                     }
                 },
+                WaitFor::HealthcheckVerbose { timeout } => {
+                    use HealthStatusEnum::*;
+
+                    let started = Instant::now();
+
+                    loop {
+                        let details = self.inspect(id);
+                        let health_status = details
+                            .state
+                            .as_ref()
+                            .unwrap_or_else(|| panic!("Container state not available"))
+                            .health
+                            .as_ref()
+                            .unwrap_or_else(|| panic!("Health state not available"))
+                            .status;
+
+                        let reason = match health_status {
+                            Some(HEALTHY) => break,
+                            None | Some(EMPTY) | Some(NONE) => {
+                                panic!("Healthcheck not configured for container")
+                            }
+                            Some(UNHEALTHY) => "reports unhealthy".to_owned(),
+                            Some(STARTING) if started.elapsed() >= timeout => {
+                                format!("did not become healthy within {timeout:?}")
+                            }
+                            Some(STARTING) => {
+                                sleep(poll_interval);
+                                continue;
+                            }
+                        };
+
+                        let (stdout, stderr) = self.inner.recent_logs(id, REPORT_LOG_LINES);
+                        let report = HealthReport {
+                            container_id: id,
+                            reason,
+                            stdout,
+                            stderr,
+                            health_log: health::health_log(details),
+                        };
+
+                        panic!("{}", report.render())
+                    }
+                }
+                WaitFor::TcpPort { port, timeout } => {
+                    let host_port = self
+                        .ports(id)
+                        .map_to_host_port_ipv4(port)
+                        .unwrap_or_else(|| panic!("container {id} does not expose port {port}"));
+                    let host = self.host();
+
+                    let started = Instant::now();
+                    while let Err(e) = TcpStream::connect((host.as_str(), host_port)) {
+                        if started.elapsed() >= timeout {
+                            panic!(
+                                "port {port} of container {id} did not accept connections on {host}:{host_port} within {timeout:?}, last attempt failed: {e}"
+                            );
+                        }
+
+                        sleep(poll_interval);
+                    }
+                }
+                WaitFor::StableAfterRestarts { max } => {
+                    let mut tracker = RestartTracker::new(max);
+
+                    while !tracker.observe(&self.inspect(id)) {
+                        sleep(poll_interval);
+                    }
+                }
+                WaitFor::PortsPublished { count, timeout } => {
+                    let started = Instant::now();
+
+                    loop {
+                        let published = self.ports(id).published_count();
+                        if published >= count {
+                            break;
+                        }
+                        if started.elapsed() >= timeout {
+                            panic!(
+                                "container {id} published {published} of {count} ports within {timeout:?}"
+                            );
+                        }
+
+                        sleep(poll_interval);
+                    }
+                }
+                WaitFor::Http {
+                    port,
+                    path,
+                    expected_status,
+                    body_contains,
+                    timeout,
+                } => {
+                    let host_port = self
+                        .ports(id)
+                        .map_to_host_port_ipv4(port)
+                        .unwrap_or_else(|| panic!("container {id} does not expose port {port}"));
+                    let probe = HttpProbe {
+                        path: &path,
+                        expected_status,
+                        body_contains: body_contains.as_deref(),
+                    };
+
+                    let started = Instant::now();
+                    let mut delay = poll_interval;
+                    loop {
+                        let remaining = timeout.saturating_sub(started.elapsed());
+                        match probe.send(&self.inner.host(), host_port, remaining) {
+                            Ok(()) => break,
+                            Err(reason) if started.elapsed() >= timeout => {
+                                panic!("{}", probe.failure(id, timeout, &reason))
+                            }
+                            Err(_) => {
+                                sleep(delay);
+                                delay = http_probe::backoff(delay, poll_interval);
+                            }
+                        }
+                    }
+                }
+                #[cfg(feature = "grpc")]
+                WaitFor::Grpc {
+                    port,
+                    service,
+                    timeout,
+                } => {
+                    let host_port = self
+                        .ports(id)
+                        .map_to_host_port_ipv4(port)
+                        .unwrap_or_else(|| panic!("container {id} does not expose port {port}"));
+
+                    grpc::block_until_serving(
+                        id,
+                        &self.inner.host(),
+                        host_port,
+                        service.as_deref().unwrap_or_default(),
+                        timeout,
+                        poll_interval,
+                    );
+                }
+                #[cfg(not(feature = "grpc"))]
+                WaitFor::Grpc { .. } => {
+                    panic!("waiting for a gRPC service requires the `grpc` feature")
+                }
                 WaitFor::Nothing => {}
             }
         }
@@ -488,14 +966,14 @@ impl Drop for Client {
         let networks = self.created_networks.read().expect("failed to lock RwLock");
         let created_networks = networks.len() > 0;
 
-        match self.command {
-            env::Command::Remove if created_networks => {
+        match self.command.should_remove() {
+            true if created_networks => {
                 self.delete_networks(networks.iter());
             }
-            env::Command::Remove => {
+            true => {
                 // nothing to do
             }
-            env::Command::Keep => {
+            false => {
                 let networks = networks.join(",");
 
                 log::warn!(
@@ -510,7 +988,11 @@ impl Drop for Client {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{core::WaitFor, images::generic::GenericImage, Image};
+    use crate::{
+        core::{BindMode, Capability, ExecSpec, Healthcheck, Resources, SelinuxRelabel, WaitFor},
+        images::generic::GenericImage,
+        Image,
+    };
     use std::collections::BTreeMap;
 
     #[derive(Default)]
@@ -594,6 +1076,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cli_run_command_should_include_memory_reservation() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_memory_reservation(64 * 1024 * 1024);
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--memory-reservation=67108864" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_include_platform() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_platform("linux/arm64");
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--platform=linux/arm64" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_include_hostname() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_hostname("node1");
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--hostname=node1" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_include_domainname() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_domainname("cluster.local");
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--domainname=cluster.local" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_include_capabilities() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image)
+            .with_cap_add(Capability::NetAdmin)
+            .with_cap_add("bpf")
+            .with_cap_drop("CAP_KILL");
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--cap-add=CAP_NET_ADMIN" "--cap-add=CAP_BPF" "--cap-drop=CAP_KILL" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_include_resources() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_resources(Resources {
+            memory: Some(128 * 1024 * 1024),
+            memory_swap: Some(256 * 1024 * 1024),
+            nano_cpus: Some(1_500_000_000),
+            cpu_shares: Some(512),
+            pids_limit: Some(100),
+        });
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--memory=134217728" "--memory-swap=268435456" "--cpus=1.5" "--cpu-shares=512" "--pids-limit=100" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_should_chown_owned_bind_mounts_as_root() {
+        let docker = Cli::default();
+        let command = docker.inner.chown_command("abc", "1000:1000", "/data");
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "exec" "--user" "0" "abc" "chown" "-R" "1000:1000" "/data""#
+        );
+    }
+
     #[test]
     #[should_panic(expected = "Failed to remove docker container")]
     fn cli_rm_command_should_panic_on_invalid_container() {
@@ -627,6 +1201,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cli_run_command_with_no_published_ports_should_not_publish_ports() {
+        let image = GenericImage::new("hello", "0.0").with_exposed_port(80);
+        let image = RunnableImage::from(image)
+            .with_mapped_port((8080, 80))
+            .with_no_published_ports();
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(format!("{command:?}"), r#""docker" "run" "-d" "hello:0.0""#);
+    }
+
     #[test]
     fn cli_run_command_with_container_network_should_not_expose_ports() {
         let image = GenericImage::new("hello", "0.0");
@@ -641,6 +1226,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cli_run_command_should_include_runtime() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_runtime("sysbox-runc");
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--runtime=sysbox-runc" "-P" "-d" "hello:0.0""#
+        );
+    }
+
     #[test]
     fn cli_run_command_should_include_privileged() {
         let image = GenericImage::new("hello", "0.0");
@@ -653,6 +1250,184 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cli_run_command_should_include_volumes_from() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image)
+            .with_volumes_from("seeder", BindMode::ReadOnly)
+            .with_volumes_from("other", BindMode::ReadWrite);
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--volumes-from" "other:rw" "--volumes-from" "seeder:ro" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_include_selinux_relabeling_of_bind_mounts() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image)
+            .with_relabeled_bind_mount(
+                ("/shared", "/data"),
+                BindMode::ReadOnly,
+                SelinuxRelabel::Shared,
+            )
+            .with_relabeled_bind_mount(
+                ("/private", "/secrets"),
+                BindMode::ReadWrite,
+                SelinuxRelabel::Private,
+            );
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "-v" "/private:/secrets:rw,Z" "-v" "/shared:/data:ro,z" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_expose_ports_on_localhost_if_requested() {
+        let image = GenericImage::new("hello", "0.0")
+            .with_exposed_port(80)
+            .with_exposed_port(443);
+        let image = RunnableImage::from(image).with_expose_on_localhost(true);
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "-p" "127.0.0.1::80" "-p" "127.0.0.1::443" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_include_storage_opts() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_storage_opt(("size", "10G"));
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--storage-opt" "size=10G" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_include_healthcheck() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_healthcheck(Healthcheck {
+            test: vec!["CMD-SHELL".to_owned(), "curl -f localhost".to_owned()],
+            interval: Some(Duration::from_secs(5)),
+            retries: Some(3),
+            start_interval: Some(Duration::from_millis(500)),
+            ..Default::default()
+        });
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--health-cmd=curl -f localhost" "--health-interval=5000ms" "--health-retries=3" "--health-start-interval=500ms" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_quote_arguments_of_exec_form_healthcheck() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_healthcheck(Healthcheck {
+            test: vec![
+                "CMD".to_owned(),
+                "pg_isready".to_owned(),
+                "-d".to_owned(),
+                "my db".to_owned(),
+                "it's".to_owned(),
+            ],
+            ..Default::default()
+        });
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--health-cmd=pg_isready -d 'my db' 'it'\\''s'" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_include_labels() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_label("team", "storage");
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--label=team=storage" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_include_exec_spec() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_exec_spec(ExecSpec {
+            cmd: vec!["ls".to_owned(), "-la".to_owned()],
+            user: Some("1000:1000".to_owned()),
+            working_dir: Some("/data".to_owned()),
+        });
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--user=1000:1000" "--workdir=/data" "-P" "-d" "hello:0.0" "ls" "-la""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_with_default_cmd_should_omit_args() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from((image, vec!["sleep".to_owned(), "60".to_owned()]))
+            .with_default_cmd();
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_include_userns_mode() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_userns_mode("host");
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--userns=host" "-P" "-d" "hello:0.0""#
+        );
+    }
+
+    #[cfg(feature = "watchdog")]
+    #[test]
+    fn cli_for_docker_host_should_talk_to_that_host() {
+        let docker = Cli::for_docker_host(Some("tcp://remote:2375".to_owned()));
+        let command = docker.inner.command();
+
+        assert!(
+            format!("{command:?}").contains(r#"DOCKER_HOST="tcp://remote:2375""#),
+            "{command:?}"
+        );
+    }
+
+    #[test]
+    fn cli_run_command_should_include_tty() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_tty(true);
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--tty" "-P" "-d" "hello:0.0""#
+        );
+    }
+
     #[test]
     fn cli_run_command_should_include_shm_size() {
         let image = GenericImage::new("hello", "0.0");
@@ -665,6 +1440,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cli_run_command_should_include_dev_shm_tmpfs() {
+        let image = GenericImage::new("hello", "0.0");
+        let image = RunnableImage::from(image).with_dev_shm_tmpfs(67_108_864);
+        let command = Client::build_run_command(&image, Command::new("docker"));
+
+        assert_eq!(
+            format!("{command:?}"),
+            r#""docker" "run" "--tmpfs" "/dev/shm:size=67108864" "-P" "-d" "hello:0.0""#
+        );
+    }
+
     #[test]
     fn should_create_network_if_image_needs_it_and_drop_it_in_the_end() {
         {
@@ -1,41 +1,119 @@
 use crate::{
-    core::{env, logs::LogStreamAsync, ports::Ports, DockerAsync, Port},
-    ContainerAsync, Image, ImageArgs, RunnableImage,
+    core::{
+        env::{self, GetEnvValue},
+        lifecycle::Step,
+        logs::{LogSource, LogStreamAsync, RawLogStreamAsync},
+        ports::Ports,
+        registry_auth, ChangeKind, CopyFromError, CopyFromOptions, CreatedContainer, DockerAsync,
+        ExecResult, ExecSpec, FilesystemChange, Port,
+    },
+    ContainerAsync, Image, RunnableImage,
 };
 use async_trait::async_trait;
 use bollard::{
-    container::{Config, CreateContainerOptions, LogsOptions, RemoveContainerOptions},
-    image::CreateImageOptions,
-    models::{ContainerCreateResponse, ContainerInspectResponse, HostConfig, PortBinding},
-    network::CreateNetworkOptions,
-    Docker,
+    container::{
+        Config, CreateContainerOptions, DownloadFromContainerOptions, LogOutput, LogsOptions,
+        RemoveContainerOptions, UploadToContainerOptions, WaitContainerOptions,
+    },
+    exec::{CreateExecOptions, StartExecResults},
+    image::{CreateImageOptions, TagImageOptions},
+    models::{
+        ContainerInspectResponse, CreateImageInfo, EndpointSettings, HealthConfig, HostConfig,
+        ImageInspect, PortBinding,
+    },
+    network::{ConnectNetworkOptions, CreateNetworkOptions},
+    ClientVersion, Docker, API_DEFAULT_VERSION,
 };
-use futures::{executor::block_on, stream::StreamExt, TryStreamExt};
+use futures::{
+    executor::block_on,
+    stream::{self, BoxStream, StreamExt},
+    TryStreamExt,
+};
+use hyper::Body;
 use std::{
-    collections::HashMap,
-    fmt, io,
-    sync::{Arc, RwLock},
+    collections::{BTreeMap, HashMap},
+    fmt,
+    future::Future,
+    io, mem,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// The timeout of requests to the Docker daemon, in seconds.
+const CONNECT_TIMEOUT_SECS: u64 = 120;
+/// How often a finished command is inspected until the daemon reports its exit code.
+const EXEC_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How often a finished command is inspected before giving up on its exit code.
+const EXEC_POLL_ATTEMPTS: u32 = 100;
+
+/// The Docker API version that added pulling images for a specific platform.
+const PLATFORM_API_VERSION: ClientVersion = ClientVersion {
+    major_version: 1,
+    minor_version: 32,
 };
 
+/// The number of containers [`Http::run_all`] starts at the same time.
+const DEFAULT_RUN_ALL_CONCURRENCY: usize = 4;
+
 /// A testcontainers client that uses HTTP to communicate with the docker daemon.
 ///
 /// This client provides an async-based interface.
 pub struct Http {
     inner: Arc<Client>,
+    default_network: Option<String>,
+    registry_mirror: Option<String>,
+    /// Limits the number of containers that are alive at the same time, see
+    /// [`Http::with_max_concurrent`].
+    concurrency_limit: Option<Arc<Semaphore>>,
+    /// Limits how long a single operation on a container may take, see
+    /// [`Http::with_operation_timeout`].
+    operation_timeout: Option<Duration>,
+    /// How often a pull that failed because of the registry is retried, and the pause before the
+    /// first retry, see [`Http::with_pull_retries`].
+    pull_retries: (u32, Duration),
+    /// The slot the container owning this client occupies.
+    _permit: Option<OwnedSemaphorePermit>,
+    /// How the container owning this client was created, kept to recreate it, see
+    /// [`RunnableImage::with_startup_retries`].
+    create_spec: Option<CreateSpec>,
 }
 
+type CreateSpec = (
+    Option<CreateContainerOptions<String>>,
+    Config<String>,
+    BTreeMap<String, Vec<String>>,
+);
+
 /// The internal client.
 ///
 /// This exists so we don't have to make the outer client clonable and still can have only a single instance around which is important for `Drop` behaviour.
 struct Client {
     command: env::Command,
     bollard: Docker,
+    /// The address of the Docker daemon `bollard` is connected to, which also tells the transport.
+    /// It is unknown for clients passed to [`Http::with_docker`].
+    docker_host: Option<String>,
     created_networks: RwLock<Vec<String>>,
+    /// The containers started by this client that haven't been removed yet, see
+    /// [`Http::cleanup`].
+    created_containers: RwLock<Vec<String>>,
+    /// Whether the API version has been negotiated with the Docker daemon.
+    version_negotiated: AtomicBool,
 }
 
 impl fmt::Debug for Http {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Http").finish()
+        let mut debug = f.debug_struct("Http");
+        match &self.inner.docker_host {
+            Some(docker_host) => debug.field("docker_host", docker_host),
+            None => debug.field("docker", &self.inner.bollard),
+        };
+        debug.finish()
     }
 }
 
@@ -47,11 +125,203 @@ impl Default for Http {
 
 // public API
 impl Http {
+    /// Creates a client for the Docker daemon found in the environment, like [`Http::default`],
+    /// but returns an error instead of panicking if the environment is invalid or the client
+    /// cannot be set up.
+    pub fn try_new() -> Result<Self, ConnectionError> {
+        Self::connect_from_env()
+    }
+
+    /// Creates a client for the Docker daemon configured in the environment the way the Docker
+    /// CLI reads it, returning an error if the client cannot be set up.
+    ///
+    /// `DOCKER_HOST` names the daemon. If `DOCKER_TLS_VERIFY` is set, the client connects to it
+    /// over TLS, verifying the daemon against `ca.pem` and authenticating with `cert.pem` and
+    /// `key.pem`, all taken from `DOCKER_CERT_PATH` or `~/.docker`.
+    pub fn connect_from_env() -> Result<Self, ConnectionError> {
+        Self::from_env::<env::Os>()
+    }
+
+    /// Checks that the Docker daemon answers requests, e.g. to fail early with a clear error on
+    /// a CI runner whose daemon isn't up yet.
+    pub async fn ping(&self) -> Result<(), ConnectionError> {
+        match self.inner.bollard.ping().await {
+            Ok(_) => Ok(()),
+            Err(source) => Err(ConnectionError::Unreachable {
+                host: self.inner.docker_host.clone(),
+                source,
+            }),
+        }
+    }
+
+    /// Creates a client that connects to the Docker daemon listening on the Unix socket at `path`,
+    /// e.g. `/var/run/docker.sock`, regardless of `DOCKER_HOST`.
+    ///
+    /// Returns an error if there is no socket at `path` or the client cannot be set up.
+    pub fn with_unix_socket(path: impl AsRef<Path>) -> Result<Self, ConnectionError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(ConnectionError::SocketNotFound(path.to_owned()));
+        }
+
+        let docker_host = format!("unix://{}", path.display());
+        let docker = try_connect_to(&docker_host)?;
+        let command = env::command::<env::Os>().unwrap_or_default();
+        Ok(Self::with_client(command, docker, Some(docker_host)))
+    }
+
+    /// Creates a client that talks to the Docker daemon through `docker`, e.g. to reuse a client
+    /// with custom TLS settings or timeouts instead of connecting a second time.
+    ///
+    /// The command is still taken from the `TESTCONTAINERS` env variable, see
+    /// [`Http::with_command`] to override it. Since the address of the daemon is unknown, the
+    /// watchdog stops containers on the daemon configured in the environment.
+    pub fn with_docker(docker: Docker) -> Self {
+        let command = env::command::<env::Os>().unwrap_or_default();
+        Self::with_client(command, docker, None)
+    }
+
+    /// Overrides the command taken from the `TESTCONTAINERS` env variable, e.g. to keep the
+    /// containers of this client around regardless of the environment.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if containers started by this client are still alive.
+    pub fn with_command(mut self, command: env::Command) -> Self {
+        match Arc::get_mut(&mut self.inner) {
+            Some(inner) => inner.command = command,
+            None => {
+                panic!("the command of a client can't be changed while its containers are alive")
+            }
+        }
+        self
+    }
+
+    /// Pins the API version used to talk to the Docker daemon, e.g. `1.41`, instead of
+    /// negotiating it with the daemon.
+    ///
+    /// A client passed to [`Http::with_docker`] keeps the version it was configured with.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `version` is not a valid API version or if containers started by
+    /// this client are still alive.
+    pub fn with_api_version(mut self, version: &str) -> Self {
+        let version = parse_api_version(version)
+            .unwrap_or_else(|| panic!("`{version}` is not a valid Docker API version"));
+        let inner = Arc::get_mut(&mut self.inner).unwrap_or_else(|| {
+            panic!("the API version of a client can't be changed while its containers are alive")
+        });
+
+        if let Some(docker_host) = &inner.docker_host {
+            inner.bollard = try_connect_with_version::<env::Os>(docker_host, &version)
+                .unwrap_or_else(|e| panic!("{e}"));
+        }
+        *inner.version_negotiated.get_mut() = true;
+        self
+    }
+
+    /// Makes every container started by this client join the given network, unless the image
+    /// specifies a network of its own.
+    ///
+    /// The network is created on first use and removed together with the client, just like
+    /// networks requested through [`RunnableImage::with_network`].
+    pub fn with_default_network(self, network: impl Into<String>) -> Self {
+        Self {
+            default_network: Some(network.into()),
+            ..self
+        }
+    }
+
+    /// Pulls images from Docker Hub through the given registry mirror, e.g. `mirror.corp:5000`.
+    ///
+    /// Images without a registry are rewritten to refer to the mirror before they are pulled and
+    /// run, expanding official images to the `library` namespace, so `redis:7` becomes
+    /// `mirror.corp:5000/library/redis:7`. Images from other registries are left untouched.
+    pub fn with_registry_mirror(self, mirror: impl Into<String>) -> Self {
+        Self {
+            registry_mirror: Some(mirror.into()),
+            ..self
+        }
+    }
+
+    /// Limits the number of containers of this client that are alive at the same time to `limit`,
+    /// to avoid overwhelming the machine running the tests.
+    ///
+    /// [`Http::run`] waits until a container started before is dropped once the limit is reached.
+    /// [`Http::run_all`] panics instead if it is given more images than containers may still be
+    /// started, as it keeps all of its containers alive until they are all ready.
+    ///
+    /// Panics if `limit` is zero.
+    pub fn with_max_concurrent(self, limit: usize) -> Self {
+        assert!(limit > 0, "concurrency limit must be greater than zero");
+
+        Self {
+            concurrency_limit: Some(Arc::new(Semaphore::new(limit))),
+            ..self
+        }
+    }
+
+    /// Fails operations on containers of this client that take longer than `timeout`, e.g. when
+    /// the Docker daemon is wedged, instead of waiting for them indefinitely.
+    ///
+    /// Applies to single requests like inspecting, starting, stopping or removing a container.
+    /// Reading logs is not covered: logs are streamed rather than fetched in one request, and
+    /// following them, e.g. while waiting for a message, lasts as long as the container takes to
+    /// log it, so a deadline per request would fail slow containers instead of detecting a wedged
+    /// daemon. Waiting for a container to exit is not covered for the same reason.
+    ///
+    /// # Panics
+    ///
+    /// Operations of containers of this client panic if they don't complete within `timeout`.
+    pub fn with_operation_timeout(self, timeout: Duration) -> Self {
+        Self {
+            operation_timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Retries pulling an image up to `retries` times if the registry fails transiently, e.g.
+    /// with a TLS handshake timeout or a reset connection, waiting `backoff` before the first
+    /// retry and twice as long before every further one.
+    ///
+    /// Every retry pulls the image from scratch. Failures that won't go away by retrying, e.g.
+    /// an image or tag that doesn't exist, fail right away, and so do other requests to the
+    /// Docker daemon.
+    pub fn with_pull_retries(self, retries: u32, backoff: Duration) -> Self {
+        Self {
+            pull_retries: (retries, backoff),
+            ..self
+        }
+    }
+
     pub async fn run<I: Image>(&self, image: impl Into<RunnableImage<I>>) -> ContainerAsync<I> {
-        let image = image.into();
+        let mut image = image.into();
+        // the hook consumes itself, so it could not prepare the recreated containers
+        assert!(
+            image.startup_retries() == 0 || !image.has_pre_start_hook(),
+            "a pre-start hook cannot be combined with startup retries"
+        );
+
+        let permit = match &self.concurrency_limit {
+            Some(limit) => Some(
+                limit
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("the semaphore is never closed"),
+            ),
+            None => None,
+        };
+        self.negotiate_version().await;
+        if let (None, Some(network)) = (image.network(), &self.default_network) {
+            image = image.with_network(network.clone());
+        }
+        let mut image = self.resolve_docker_socket(image);
+        let descriptor = self.descriptor(&image);
         let mut create_options: Option<CreateContainerOptions<String>> = None;
         let mut config: Config<String> = Config {
-            image: Some(image.descriptor()),
+            image: Some(descriptor.clone()),
             host_config: Some(HostConfig::default()),
             ..Default::default()
         };
@@ -64,13 +334,153 @@ impl Http {
             });
         }
 
-        // create network and add it to container creation
+        // tmpfs mounts
+        let tmpfs: HashMap<String, String> = image
+            .tmpfs()
+            .map(|(path, options)| (path.clone(), options.clone()))
+            .collect();
+        if !tmpfs.is_empty() {
+            config.host_config = config.host_config.map(|mut host_config| {
+                host_config.tmpfs = Some(tmpfs);
+                host_config
+            });
+        }
+
+        // privileges and container runtime
+        if image.privileged() {
+            config.host_config = config.host_config.map(|mut host_config| {
+                host_config.privileged = Some(true);
+                host_config
+            });
+        }
+        if let Some(runtime) = image.runtime() {
+            let info = self.inner.bollard.info().await.unwrap();
+            if let Err(message) = check_runtime(runtime, info.runtimes.unwrap_or_default().keys()) {
+                panic!("{message}")
+            }
+            config.host_config = config.host_config.map(|mut host_config| {
+                host_config.runtime = Some(runtime.clone());
+                host_config
+            });
+        }
+
+        // pseudo-TTY
+        if image.tty() {
+            config.tty = Some(true);
+        }
+        if let Some((height, width)) = image.console_size() {
+            config.host_config = config.host_config.map(|mut host_config| {
+                host_config.console_size = Some(vec![height.into(), width.into()]);
+                host_config
+            });
+        }
+
+        // memory reservation
+        if let Some(bytes) = image.memory_reservation() {
+            config.host_config = config.host_config.map(|mut host_config| {
+                host_config.memory_reservation = Some(bytes);
+                host_config
+            });
+        }
+
+        // labels
+        let labels: HashMap<String, String> = image
+            .labels()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        if !labels.is_empty() {
+            config.labels = Some(labels);
+        }
+
+        // hostname
+        if let Some(hostname) = image.hostname() {
+            config.hostname = Some(hostname.clone());
+        }
+
+        // domain name
+        if let Some(domainname) = image.domainname() {
+            config.domainname = Some(domainname.clone());
+        }
+
+        // capabilities
+        let cap_add: Vec<String> = image.cap_add().map(ToString::to_string).collect();
+        if !cap_add.is_empty() {
+            config.host_config = config.host_config.map(|mut host_config| {
+                host_config.cap_add = Some(cap_add);
+                host_config
+            });
+        }
+        let cap_drop: Vec<String> = image.cap_drop().map(ToString::to_string).collect();
+        if !cap_drop.is_empty() {
+            config.host_config = config.host_config.map(|mut host_config| {
+                host_config.cap_drop = Some(cap_drop);
+                host_config
+            });
+        }
+
+        // user namespace
+        if let Some(mode) = image.userns_mode() {
+            config.host_config = config.host_config.map(|mut host_config| {
+                host_config.userns_mode = Some(mode.clone());
+                host_config
+            });
+        }
+
+        // healthcheck
+        if let Some(healthcheck) = image.healthcheck() {
+            if healthcheck.start_interval.is_some() {
+                log::warn!(
+                    "Ignoring the start interval of the healthcheck, it is not supported by the `Http` client"
+                );
+            }
+
+            config.healthcheck = Some(HealthConfig {
+                test: Some(healthcheck.test.clone()),
+                interval: healthcheck
+                    .interval
+                    .map(|interval| interval.as_nanos() as i64),
+                timeout: healthcheck.timeout.map(|timeout| timeout.as_nanos() as i64),
+                retries: healthcheck.retries,
+                start_period: healthcheck
+                    .start_period
+                    .map(|start_period| start_period.as_nanos() as i64),
+            });
+        }
+
+        // storage driver options
+        let storage_opts: HashMap<String, String> = image
+            .storage_opts()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        if !storage_opts.is_empty() {
+            config.host_config = config.host_config.map(|mut host_config| {
+                host_config.storage_opt = Some(storage_opts);
+                host_config
+            });
+        }
+
+        // resource limits
+        let resources = image.resources().clone();
+        config.host_config = config.host_config.map(|mut host_config| {
+            host_config.memory = resources.memory;
+            host_config.memory_swap = resources.memory_swap;
+            host_config.nano_cpus = resources.nano_cpus;
+            host_config.cpu_shares = resources.cpu_shares;
+            host_config.pids_limit = resources.pids_limit;
+            host_config
+        });
+
+        // add network to container creation
         if let Some(network) = image.network() {
             config.host_config = config.host_config.map(|mut host_config| {
                 host_config.network_mode = Some(network.to_string());
                 host_config
             });
-            if self.create_network_if_not_exists(network).await {
+        }
+
+        // create networks, including the ones the container is connected to once it is running
+        for network in image.network().iter().chain(image.network_aliases().keys()) {
+            if self.create_network_if_not_exists(network, &image).await {
                 let mut guard = self
                     .inner
                     .created_networks
@@ -92,6 +502,12 @@ impl Http {
             .env_vars()
             .into_iter()
             .map(|(k, v)| format!("{k}={v}"))
+            .chain(
+                image
+                    .runtime_env_vars::<env::Os>()
+                    .into_iter()
+                    .map(|(k, v)| format!("{k}={v}")),
+            )
             .collect();
         config.env = Some(envs);
 
@@ -103,6 +519,18 @@ impl Http {
             .collect();
         config.volumes = Some(vols);
 
+        // volumes from other containers
+        let volumes_from: Vec<String> = image
+            .volumes_from()
+            .map(|(container, mode)| format!("{container}:{mode}"))
+            .collect();
+        if !volumes_from.is_empty() {
+            config.host_config = config.host_config.map(|mut host_config| {
+                host_config.volumes_from = Some(volumes_from);
+                host_config
+            });
+        }
+
         // entrypoint
         if let Some(entrypoint) = image.entrypoint() {
             config.entrypoint = Some(vec![entrypoint]);
@@ -118,7 +546,12 @@ impl Http {
         );
 
         // ports
-        if image.ports().is_some() || image.expose_ports().len() > 0 {
+        if !image.publish_ports() {
+            // the container is only reachable from within its network
+        } else if image.ports().is_some() || image.expose_ports().len() > 0 {
+            let exposed_host_ip = image
+                .expose_on_localhost()
+                .then(|| String::from("127.0.0.1"));
             let empty: Vec<Port> = Vec::new();
             let bindings = image
                 .ports()
@@ -134,12 +567,15 @@ impl Http {
                         }]),
                     )
                 })
-                .chain(
-                    image
-                        .expose_ports()
-                        .into_iter()
-                        .map(|p| (format!("{}/tcp", p), Some(vec![PortBinding::default()]))),
-                );
+                .chain(image.expose_ports().into_iter().map(|p| {
+                    (
+                        format!("{}/tcp", p),
+                        Some(vec![PortBinding {
+                            host_ip: exposed_host_ip.clone(),
+                            host_port: None,
+                        }]),
+                    )
+                }));
 
             config.host_config = config.host_config.map(|mut host_config| {
                 host_config.port_bindings = Some(bindings.collect());
@@ -152,82 +588,432 @@ impl Http {
             });
         }
 
-        let args = image
-            .args()
-            .clone()
-            .into_iterator()
-            .collect::<Vec<String>>();
+        let args = image.cmd();
         if !args.is_empty() {
             config.cmd = Some(args);
         }
 
+        // the daemon drops the default command of the image if the entrypoint is overridden
+        if image.default_cmd() && image.entrypoint().is_some() {
+            config.cmd = self.image_cmd(&descriptor, image.platform().as_ref()).await;
+        }
+
+        // user and working directory
+        if let Some(spec) = image.exec_spec() {
+            config.user = spec.user.clone();
+            config.working_dir = spec.working_dir.clone();
+        }
+
+        // an image for a specific platform is always pulled, since a local image with the same
+        // name may have been built for a different platform
+        if let Some(platform) = image.platform() {
+            self.pull_image(descriptor.clone(), Some(platform)).await;
+        }
+
+        if let Some(hook) = image.take_config_hook() {
+            hook.call(&mut config);
+        }
+
+        let create_spec = (image.startup_retries() > 0).then(|| {
+            (
+                create_options.clone(),
+                config.clone(),
+                image.network_aliases().clone(),
+            )
+        });
+
         // create the container with options
-        let create_result = self
-            .create_container(create_options.clone(), config.clone())
-            .await;
-        let container_id = {
-            match create_result {
-                Ok(container) => container.id,
-                Err(bollard::errors::Error::DockerResponseServerError {
-                    status_code: 404, ..
-                }) => {
-                    {
-                        let pull_options = Some(CreateImageOptions {
-                            from_image: image.descriptor(),
-                            ..Default::default()
-                        });
-                        let mut pulling = self.inner.bollard.create_image(pull_options, None, None);
-                        while let Some(result) = pulling.next().await {
-                            if result.is_err() {
-                                result.unwrap();
-                            }
-                        }
+        let create = Step::new("create", &descriptor, None);
+        let container_id = create
+            .run(async {
+                let create_result = self
+                    .inner
+                    .bollard
+                    .create_container(create_options.clone(), config.clone())
+                    .await;
+                match create_result {
+                    Ok(container) => container.id,
+                    Err(bollard::errors::Error::DockerResponseServerError {
+                        status_code: 404,
+                        ..
+                    }) => {
+                        self.pull_image(descriptor.clone(), image.platform().as_ref())
+                            .await;
+                        self.inner
+                            .bollard
+                            .create_container(create_options, config)
+                            .await
+                            .unwrap()
+                            .id
                     }
-                    self.create_container(create_options, config)
-                        .await
-                        .unwrap()
-                        .id
+                    Err(err) => panic!("{}", err),
                 }
-                Err(err) => panic!("{}", err),
-            }
-        };
+            })
+            .await;
+        create.record_id(&container_id);
+        self.track_container(&container_id);
 
         #[cfg(feature = "watchdog")]
-        if self.inner.command == env::Command::Remove {
-            crate::watchdog::register(container_id.clone());
+        if self.inner.command != env::Command::Keep {
+            crate::watchdog::register(container_id.clone(), self.inner.docker_host.clone());
         }
 
-        self.inner
-            .bollard
-            .start_container::<String>(&container_id, None)
+        if let Some(hook) = image.take_pre_start_hook() {
+            let client = Http {
+                inner: self.inner.clone(),
+                default_network: self.default_network.clone(),
+                registry_mirror: self.registry_mirror.clone(),
+                concurrency_limit: None,
+                operation_timeout: self.operation_timeout,
+                pull_retries: self.pull_retries,
+                _permit: None,
+                create_spec: None,
+            };
+            hook.call(CreatedContainer::new(container_id.clone(), client))
+                .await;
+        }
+
+        Step::new("start", &descriptor, Some(&container_id))
+            .run(
+                self.inner
+                    .bollard
+                    .start_container::<String>(&container_id, None),
+            )
             .await
             .unwrap();
+        self.connect_network_aliases(&container_id, image.network_aliases())
+            .await;
 
         let client = Http {
             inner: self.inner.clone(),
+            default_network: self.default_network.clone(),
+            registry_mirror: self.registry_mirror.clone(),
+            concurrency_limit: self.concurrency_limit.clone(),
+            operation_timeout: self.operation_timeout,
+            pull_retries: self.pull_retries,
+            _permit: permit,
+            create_spec,
         };
 
         ContainerAsync::new(container_id, client, image, self.inner.command).await
     }
+
+    /// Runs all given images concurrently and returns the containers once all of them are ready.
+    ///
+    /// At most four containers are started at the same time, see [`Http::run_all_with_concurrency`]
+    /// to change this limit.
+    /// The containers are returned in the same order as the images.
+    pub async fn run_all<I: Image>(
+        &self,
+        images: Vec<impl Into<RunnableImage<I>>>,
+    ) -> Vec<ContainerAsync<I>> {
+        self.run_all_with_concurrency(images, DEFAULT_RUN_ALL_CONCURRENCY)
+            .await
+    }
+
+    /// Runs all given images concurrently, starting at most `limit` containers at the same time.
+    ///
+    /// Panics if `limit` is zero, if any of the containers fails to start or if this client may
+    /// not start as many containers at once, see [`Http::with_max_concurrent`].
+    pub async fn run_all_with_concurrency<I: Image>(
+        &self,
+        images: Vec<impl Into<RunnableImage<I>>>,
+        limit: usize,
+    ) -> Vec<ContainerAsync<I>> {
+        assert!(limit > 0, "concurrency limit must be greater than zero");
+        if let Some(concurrency_limit) = &self.concurrency_limit {
+            // the containers hold on to their permits until they are dropped
+            let available = concurrency_limit.available_permits();
+            assert!(
+                images.len() <= available,
+                "cannot run {} images at once, only {available} more containers may be alive at the same time",
+                images.len()
+            );
+        }
+
+        stream::iter(images)
+            .map(|image| self.run(image))
+            .buffered(limit)
+            .collect()
+            .await
+    }
+
+    /// Runs the image, captures its stdout and stderr until `marker` is logged and removes the
+    /// container again.
+    ///
+    /// Returns the captured output up to and including the marker.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the container exits before logging the marker.
+    pub async fn run_until_log<I: Image>(
+        &self,
+        image: impl Into<RunnableImage<I>>,
+        marker: &str,
+    ) -> String {
+        let container = self.run(image).await;
+        let output = self
+            .combined_logs(container.id())
+            .read_until_message(marker)
+            .await
+            .unwrap_or_else(|e| {
+                panic!("container {} did not log '{marker}': {e:?}", container.id())
+            });
+        container.rm().await;
+
+        output
+    }
+
+    /// Pulls the image `descriptor`, e.g. `redis:7-alpine`, and reports which of its layers had to
+    /// be downloaded and which existed locally already.
+    ///
+    /// Useful for tests that depend on the layer cache of the Docker daemon.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the image can't be pulled.
+    pub async fn pull(&self, descriptor: &str) -> PullSummary {
+        self.negotiate_version().await;
+        self.pull_image(descriptor.to_owned(), None).await
+    }
+
+    /// Returns the metadata of the image `descriptor`, e.g. its exposed ports, entrypoint and
+    /// labels, pulling the image if it isn't present locally.
+    ///
+    /// Useful for tests that adapt to the image they run, e.g. to discover which ports to map.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the image can't be pulled or inspected.
+    pub async fn inspect_image(&self, descriptor: &str) -> ImageInspect {
+        self.negotiate_version().await;
+        self.inspect_or_pull_image(descriptor, None).await
+    }
+
+    /// Tags the local image `source`, e.g. `redis:7-alpine`, as `target_repo:target_tag`.
+    ///
+    /// Useful to make a pulled image available under the name other tooling expects.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the image `source` doesn't exist locally.
+    pub async fn tag_image(&self, source: &str, target_repo: &str, target_tag: &str) {
+        self.negotiate_version().await;
+        self.inner
+            .bollard
+            .tag_image(
+                source,
+                Some(TagImageOptions {
+                    repo: target_repo,
+                    tag: target_tag,
+                }),
+            )
+            .await
+            .unwrap_or_else(|e| {
+                panic!("failed to tag image `{source}` as `{target_repo}:{target_tag}`: {e}")
+            });
+    }
+
+    /// Removes all containers started by this client and then the networks it created, e.g. in
+    /// the teardown of a test suite.
+    ///
+    /// Unlike relying on `Drop`, this doesn't block the runtime and removes the networks right
+    /// away instead of once the client and all of its containers are gone. Containers removed
+    /// this way can still be dropped as usual. Nothing is removed if containers are kept, see
+    /// [`env::Command`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if a container or network can't be removed.
+    pub async fn cleanup(&self) {
+        if !self.inner.command.should_remove() {
+            return;
+        }
+
+        let containers = mem::take(
+            &mut *self
+                .inner
+                .created_containers
+                .write()
+                .expect("failed to lock RwLock"),
+        );
+        for id in containers {
+            self.remove_container(&id, true).await;
+        }
+
+        let networks = mem::take(
+            &mut *self
+                .inner
+                .created_networks
+                .write()
+                .expect("failed to lock RwLock"),
+        );
+        for network in networks {
+            self.inner
+                .bollard
+                .remove_network(&network)
+                .await
+                .unwrap_or_else(|e| panic!("failed to remove network `{network}`: {e}"));
+        }
+    }
+}
+
+/// Parts of the errors of a registry that might go away when a pull is retried, see
+/// [`Http::with_pull_retries`].
+const TRANSIENT_PULL_ERRORS: &[&str] = &[
+    "tls handshake timeout",
+    "connection reset",
+    "connection refused",
+    "i/o timeout",
+    "unexpected eof",
+    "timeout exceeded",
+    "bad gateway",
+    "service unavailable",
+    "gateway timeout",
+    "toomanyrequests",
+];
+
+/// Why pulling an image failed.
+#[derive(Debug)]
+enum PullError {
+    /// The Docker daemon rejected the pull, e.g. because the image doesn't exist.
+    Daemon(bollard::errors::Error),
+    /// The Docker daemon reported an error while pulling, e.g. because the connection to the
+    /// registry was reset.
+    Registry(String),
+}
+
+impl PullError {
+    /// Whether the registry failed in a way that might go away if the pull is retried.
+    fn is_transient(&self) -> bool {
+        let message = match self {
+            PullError::Daemon(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404,
+                ..
+            }) => return false,
+            PullError::Daemon(bollard::errors::Error::DockerResponseServerError {
+                message,
+                ..
+            }) => message,
+            PullError::Daemon(_) => return false,
+            PullError::Registry(message) => message,
+        }
+        .to_lowercase();
+
+        TRANSIENT_PULL_ERRORS
+            .iter()
+            .any(|transient| message.contains(transient))
+    }
+}
+
+impl fmt::Display for PullError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PullError::Daemon(err) => write!(f, "{err}"),
+            PullError::Registry(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+/// How the layers of an image were obtained by a pull, see [`Http::pull`].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PullSummary {
+    /// Number of layers that were downloaded from the registry.
+    pub layers_pulled: usize,
+    /// Number of layers that existed locally already.
+    pub layers_cached: usize,
+}
+
+impl PullSummary {
+    fn record(&mut self, info: &CreateImageInfo) {
+        match info.status.as_deref() {
+            Some("Already exists") => self.layers_cached += 1,
+            Some("Pull complete") => self.layers_pulled += 1,
+            _ => {}
+        }
+    }
 }
 
 impl Http {
     fn new() -> Self {
+        Self::try_new().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    fn from_env<E: GetEnvValue>() -> Result<Self, ConnectionError> {
+        let command = env::try_command::<E>()
+            .map_err(ConnectionError::UnknownCommand)?
+            .unwrap_or_default();
+        let docker_host = env::docker_host::<E>().map_err(ConnectionError::NoDaemon)?;
+        let bollard = try_connect_with_version::<E>(&docker_host, API_DEFAULT_VERSION)?;
+
+        Ok(Self::with_client(command, bollard, Some(docker_host)))
+    }
+
+    #[cfg(test)]
+    fn connected_to(docker_host: String) -> Self {
+        let command = env::command::<env::Os>().unwrap_or_default();
+        Self::with_client(command, connect_to(&docker_host), Some(docker_host))
+    }
+
+    /// The address of the Docker daemon, assuming the one configured in the environment for
+    /// clients passed to [`Http::with_docker`].
+    fn docker_host(&self) -> Option<String> {
+        self.inner
+            .docker_host
+            .clone()
+            .or_else(|| env::Os::get_env_value("DOCKER_HOST"))
+    }
+
+    /// The descriptor of the image to run, pulled through the registry mirror if one is set.
+    fn descriptor<I: Image>(&self, image: &RunnableImage<I>) -> String {
+        match &self.registry_mirror {
+            Some(mirror) => mirrored_descriptor(mirror, &image.descriptor()),
+            None => image.descriptor(),
+        }
+    }
+
+    /// Resolves the image to run, with the Docker socket of the daemon mounted if requested.
+    fn resolve_docker_socket<I: Image>(&self, image: RunnableImage<I>) -> RunnableImage<I> {
+        image.with_resolved_docker_socket(|| {
+            env::docker_socket::<env::Os>(self.docker_host().as_deref())
+        })
+    }
+
+    fn with_client(command: env::Command, bollard: Docker, docker_host: Option<String>) -> Self {
         Http {
             inner: Arc::new(Client {
-                command: env::command::<env::Os>().unwrap_or_default(),
-                bollard: Docker::connect_with_http_defaults().unwrap(),
+                command,
+                bollard,
+                docker_host,
                 created_networks: RwLock::new(Vec::new()),
+                created_containers: RwLock::new(Vec::new()),
+                version_negotiated: AtomicBool::new(false),
             }),
+            default_network: None,
+            registry_mirror: None,
+            concurrency_limit: None,
+            operation_timeout: None,
+            pull_retries: (0, Duration::ZERO),
+            _permit: None,
+            create_spec: None,
         }
     }
 
-    async fn create_network_if_not_exists(&self, network: &str) -> bool {
+    async fn create_network_if_not_exists<I: Image>(
+        &self,
+        network: &str,
+        image: &RunnableImage<I>,
+    ) -> bool {
         if !network_exists(&self.inner.bollard, network).await {
             self.inner
                 .bollard
                 .create_network(CreateNetworkOptions {
                     name: network.to_owned(),
+                    driver: image.network_driver().clone().unwrap_or_default(),
+                    options: image
+                        .network_driver_opts()
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect(),
                     ..Default::default()
                 })
                 .await
@@ -239,57 +1025,482 @@ impl Http {
         false
     }
 
-    async fn create_container(
-        &self,
-        options: Option<CreateContainerOptions<String>>,
-        config: Config<String>,
-    ) -> Result<ContainerCreateResponse, bollard::errors::Error> {
-        self.inner.bollard.create_container(options, config).await
+    /// Runs the `operation` on the container `id` within the operation timeout, see
+    /// [`Http::with_operation_timeout`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the operation doesn't complete within the operation timeout.
+    async fn timed<T>(&self, operation: &str, id: &str, future: impl Future<Output = T>) -> T {
+        match self.operation_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, future)
+                .await
+                .unwrap_or_else(|_| {
+                    panic!("failed to {operation} container {id} within {timeout:?}")
+                }),
+            None => future.await,
+        }
     }
 
-    fn logs(&self, container_id: String, options: LogsOptions<String>) -> LogStreamAsync<'_> {
-        let stream = self
-            .inner
-            .bollard
-            .logs(&container_id, Some(options))
-            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
-            .map(|chunk| {
-                let bytes = chunk?.into_bytes();
-                let str = std::str::from_utf8(bytes.as_ref())
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    /// Connects a running container to additional networks under the given aliases, see
+    /// [`RunnableImage::with_network_alias`].
+    async fn connect_network_aliases(
+        &self,
+        id: &str,
+        network_aliases: &BTreeMap<String, Vec<String>>,
+    ) {
+        for (network, aliases) in network_aliases {
+            self.inner
+                .bollard
+                .connect_network(
+                    network,
+                    ConnectNetworkOptions {
+                        container: id,
+                        endpoint_config: EndpointSettings {
+                            aliases: Some(aliases.clone()),
+                            ..Default::default()
+                        },
+                    },
+                )
+                .await
+                .unwrap_or_else(|e| {
+                    panic!("failed to connect container {id} to network {network}: {e}")
+                });
+        }
+    }
 
-                Ok(String::from(str))
-            })
-            .boxed();
+    /// Downgrades the API version used to talk to the Docker daemon to the newest one it supports,
+    /// so that a daemon older than `bollard` still understands the requests.
+    async fn negotiate_version(&self) {
+        if self.inner.version_negotiated.load(Ordering::Acquire) {
+            return;
+        }
 
-        LogStreamAsync::new(stream)
+        negotiate_version(&self.inner.bollard).await;
+        self.inner.version_negotiated.store(true, Ordering::Release);
     }
-}
 
-async fn network_exists(client: &Docker, network: &str) -> bool {
-    let networks = client.list_networks::<String>(None).await.unwrap();
-    networks
-        .iter()
-        .any(|i| matches!(&i.name, Some(name) if name == network))
-}
+    async fn pull_image(&self, descriptor: String, platform: Option<&String>) -> PullSummary {
+        if platform.is_some() {
+            require_api_version(
+                &self.inner.bollard,
+                "pulling an image for a platform",
+                &PLATFORM_API_VERSION,
+            );
+        }
+        let pull_options = Some(CreateImageOptions {
+            from_image: descriptor.clone(),
+            platform: platform.cloned().unwrap_or_default(),
+            ..Default::default()
+        });
+        let (retries, backoff) = self.pull_retries;
+        let mut attempt = 0;
+        let (mut summary, up_to_date) = loop {
+            match self.try_pull_image(&descriptor, pull_options.clone()).await {
+                Ok(pulled) => break pulled,
+                Err(err) if attempt < retries && err.is_transient() => {
+                    let delay = backoff.saturating_mul(1 << attempt.min(16));
+                    log::warn!("Failed to pull image `{descriptor}`, retrying in {delay:?}: {err}");
 
-impl Drop for Client {
-    fn drop(&mut self) {
-        match self.command {
-            env::Command::Remove => {
-                let guard = self.created_networks.read().expect("failed to lock RwLock");
-                for network in guard.iter() {
-                    block_on(async { self.bollard.remove_network(network).await.unwrap() });
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
                 }
+                Err(err) => match platform {
+                    Some(platform) => panic!(
+                        "failed to pull image `{descriptor}` for platform `{platform}`: {err}"
+                    ),
+                    None => panic!("failed to pull image `{descriptor}`: {err}"),
+                },
+            }
+        };
+
+        // the daemon doesn't report the layers of an image that is present already
+        if up_to_date && summary == PullSummary::default() {
+            summary.layers_cached = self
+                .inner
+                .bollard
+                .inspect_image(&descriptor)
+                .await
+                .ok()
+                .and_then(|image| image.root_fs)
+                .and_then(|root_fs| root_fs.layers)
+                .map_or(0, |layers| layers.len());
+        }
+
+        summary
+    }
+
+    /// Pulls an image once, returning what was pulled and whether the image was up to date.
+    async fn try_pull_image(
+        &self,
+        descriptor: &str,
+        pull_options: Option<CreateImageOptions<String>>,
+    ) -> Result<(PullSummary, bool), PullError> {
+        let credentials = registry_auth::credentials_for::<env::Os>(descriptor);
+        let mut pulling = self
+            .inner
+            .bollard
+            .create_image(pull_options, None, credentials);
+        let mut summary = PullSummary::default();
+        let mut up_to_date = false;
+        while let Some(info) = pulling.next().await {
+            let info = info.map_err(PullError::Daemon)?;
+            if let Some(error) = info.error {
+                return Err(PullError::Registry(error));
+            }
+
+            summary.record(&info);
+            up_to_date |= info
+                .status
+                .map_or(false, |status| status.contains("Image is up to date"));
+        }
+
+        Ok((summary, up_to_date))
+    }
+
+    /// Returns the default command of an image, pulling the image if it isn't present locally.
+    async fn image_cmd(&self, descriptor: &str, platform: Option<&String>) -> Option<Vec<String>> {
+        self.inspect_or_pull_image(descriptor, platform)
+            .await
+            .config
+            .and_then(|config| config.cmd)
+    }
+
+    /// Inspects an image, pulling it if it isn't present locally.
+    async fn inspect_or_pull_image(
+        &self,
+        descriptor: &str,
+        platform: Option<&String>,
+    ) -> ImageInspect {
+        let inspected = match self.inner.bollard.inspect_image(descriptor).await {
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => {
+                self.pull_image(descriptor.to_owned(), platform).await;
+                self.inner.bollard.inspect_image(descriptor).await
+            }
+            result => result,
+        };
+
+        inspected.unwrap_or_else(|e| panic!("failed to inspect image `{descriptor}`: {e}"))
+    }
+
+    /// Waits until the daemon reports the exit code of the finished exec `exec_id` in container
+    /// `id`, which it may record slightly after the output of the command ended.
+    async fn exec_exit_code(&self, id: &str, exec_id: &str) -> i64 {
+        for _ in 0..EXEC_POLL_ATTEMPTS {
+            let inspected = self
+                .inner
+                .bollard
+                .inspect_exec(exec_id)
+                .await
+                .unwrap_or_else(|e| panic!("failed to inspect exec in container {id}: {e}"));
+            if let (Some(false), Some(exit_code)) = (inspected.running, inspected.exit_code) {
+                return exit_code;
+            }
+
+            tokio::time::sleep(EXEC_POLL_INTERVAL).await;
+        }
+
+        panic!(
+            "exec in container {id} did not report an exit code within {:?}",
+            EXEC_POLL_INTERVAL * EXEC_POLL_ATTEMPTS
+        )
+    }
+
+    /// Records that the container `id` has been started by this client, see [`Http::cleanup`].
+    fn track_container(&self, id: &str) {
+        self.inner
+            .created_containers
+            .write()
+            .expect("failed to lock RwLock")
+            .push(id.to_owned());
+    }
+
+    async fn remove_container(&self, id: &str, remove_volumes: bool) {
+        self.inner
+            .created_containers
+            .write()
+            .expect("failed to lock RwLock")
+            .retain(|created| created != id);
+
+        let result = self
+            .inner
+            .bollard
+            .remove_container(
+                id,
+                Some(RemoveContainerOptions {
+                    force: true,
+                    v: remove_volumes,
+                    ..Default::default()
+                }),
+            )
+            .await;
+
+        match result {
+            Ok(()) => {}
+            // the container has already been removed, e.g. by a `TestContext`
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => log::debug!("Container {id} has already been removed"),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    async fn upload(&self, id: &str, tar: Body, dest_dir: &str) {
+        self.inner
+            .bollard
+            .upload_to_container(
+                id,
+                Some(UploadToContainerOptions {
+                    path: dest_dir,
+                    ..Default::default()
+                }),
+                tar,
+            )
+            .await
+            .unwrap_or_else(|err| match err {
+                // the daemon answers 404 for missing containers as well
+                bollard::errors::Error::DockerResponseServerError {
+                    status_code: 404,
+                    message,
+                } if !message.starts_with("No such container") => {
+                    panic!("directory `{dest_dir}` does not exist in container {id}")
+                }
+                err => panic!("failed to copy into `{dest_dir}` of container {id}: {err}"),
+            })
+    }
+
+    fn logs(&self, container_id: String, options: LogsOptions<String>) -> LogStreamAsync<'static> {
+        self.raw_logs(container_id, options).into_lossy()
+    }
+
+    fn raw_logs(
+        &self,
+        container_id: String,
+        options: LogsOptions<String>,
+    ) -> RawLogStreamAsync<'static> {
+        let stream = self
+            .inner
+            .bollard
+            .logs(&container_id, Some(options))
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+            .map(|chunk| Ok(chunk?.into_bytes().to_vec()))
+            .boxed();
+
+        RawLogStreamAsync::new(stream)
+    }
+}
+
+/// Connects to the Docker daemon found by [`env::docker_host`].
+#[cfg(test)]
+pub(crate) fn connect() -> Docker {
+    connect_to(&docker_host())
+}
+
+/// Resolves the address of the Docker daemon, see [`env::docker_host`].
+#[cfg(test)]
+fn docker_host() -> String {
+    env::docker_host::<env::Os>()
+        .unwrap_or_else(|attempted| panic!("{}", ConnectionError::NoDaemon(attempted)))
+}
+
+#[cfg(test)]
+fn connect_to(host: &str) -> Docker {
+    try_connect_to(host).unwrap_or_else(|e| panic!("{e}"))
+}
+
+fn try_connect_to(host: &str) -> Result<Docker, ConnectionError> {
+    try_connect_with_version::<env::Os>(host, API_DEFAULT_VERSION)
+}
+
+/// Connects to the daemon at `host`, over TLS if the environment asks for it, see
+/// [`env::docker_tls_cert_path`].
+fn try_connect_with_version<E: GetEnvValue>(
+    host: &str,
+    version: &ClientVersion,
+) -> Result<Docker, ConnectionError> {
+    log::debug!("Connecting to Docker daemon at {host} using API version {version}");
+
+    let connection = match tls_cert_path::<E>(host) {
+        Some(cert_path) => Docker::connect_with_ssl(
+            host,
+            &cert_path.join("key.pem"),
+            &cert_path.join("cert.pem"),
+            &cert_path.join("ca.pem"),
+            CONNECT_TIMEOUT_SECS,
+            version,
+        ),
+        None => match host.split_once("://").map(|(scheme, _)| scheme) {
+            #[cfg(unix)]
+            Some("unix") => Docker::connect_with_unix(host, CONNECT_TIMEOUT_SECS, version),
+            #[cfg(windows)]
+            Some("npipe") => Docker::connect_with_named_pipe(host, CONNECT_TIMEOUT_SECS, version),
+            _ => Docker::connect_with_http(host, CONNECT_TIMEOUT_SECS, version),
+        },
+    };
+    connection.map_err(|source| ConnectionError::Connect {
+        host: host.to_owned(),
+        source,
+    })
+}
+
+/// The directory with the certificates to connect to the daemon at `host` with, if it is reached
+/// over TCP and the environment asks for TLS.
+fn tls_cert_path<E: GetEnvValue>(host: &str) -> Option<PathBuf> {
+    if host.starts_with("unix://") || host.starts_with("npipe://") {
+        return None;
+    }
+
+    env::docker_tls_cert_path::<E>()
+}
+
+/// Defines error cases when creating an [`Http`] client, see [`Http::try_new`].
+#[derive(Debug)]
+pub enum ConnectionError {
+    /// The `TESTCONTAINERS` env variable holds an unknown command.
+    UnknownCommand(String),
+    /// `DOCKER_HOST` is not set and no Docker daemon was found at any of the attempted addresses.
+    NoDaemon(Vec<String>),
+    /// There is no socket at the given path, see [`Http::with_unix_socket`].
+    SocketNotFound(PathBuf),
+    /// The client for the daemon at `host` could not be set up.
+    Connect {
+        host: String,
+        source: bollard::errors::Error,
+    },
+    /// The daemon did not answer a ping, see [`Http::ping`]. The host is unknown for clients
+    /// passed to [`Http::with_docker`].
+    Unreachable {
+        host: Option<String>,
+        source: bollard::errors::Error,
+    },
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionError::UnknownCommand(command) => {
+                write!(f, "{}", env::unknown_command(command))
+            }
+            ConnectionError::NoDaemon(attempted) => write!(
+                f,
+                "failed to find a Docker daemon, tried {}; set DOCKER_HOST to the address of the daemon",
+                attempted.join(", ")
+            ),
+            ConnectionError::SocketNotFound(path) => {
+                write!(f, "Docker socket `{}` does not exist", path.display())
+            }
+            ConnectionError::Connect { host, source } => {
+                write!(f, "failed to connect to Docker daemon at {host}: {source}")
+            }
+            ConnectionError::Unreachable {
+                host: Some(host),
+                source,
+            } => write!(f, "could not reach Docker daemon at {host}: {source}"),
+            ConnectionError::Unreachable { host: None, source } => {
+                write!(f, "could not reach Docker daemon: {source}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConnectionError::Connect { source, .. }
+            | ConnectionError::Unreachable { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Parses an API version of the Docker daemon, e.g. `1.41`.
+fn parse_api_version(version: &str) -> Option<ClientVersion> {
+    let (major, minor) = version.split_once('.')?;
+
+    Some(ClientVersion {
+        major_version: major.parse().ok()?,
+        minor_version: minor.parse().ok()?,
+    })
+}
+
+/// Negotiates the API version with the daemon `client` is connected to.
+///
+/// The version is shared between all clones of `client`, so this downgrades them all.
+async fn negotiate_version(client: &Docker) {
+    if let Err(e) = client.clone().negotiate_version().await {
+        panic!("failed to negotiate the API version with the Docker daemon: {e}")
+    }
+    log::debug!("Using Docker API version {}", client.client_version());
+}
+
+/// Panics with a clear message if the API version negotiated with the daemon predates `feature`.
+fn require_api_version(client: &Docker, feature: &str, required: &ClientVersion) {
+    let version = client.client_version();
+    if version < *required {
+        panic!(
+            "{feature} requires Docker API version {required}, but the Docker daemon only supports {version}"
+        )
+    }
+}
+
+/// Checks that `runtime` is one of the container runtimes the Docker daemon supports.
+fn check_runtime<'a>(
+    runtime: &str,
+    available: impl Iterator<Item = &'a String>,
+) -> Result<(), String> {
+    let mut available = available.map(String::as_str).collect::<Vec<_>>();
+    if available.contains(&runtime) {
+        return Ok(());
+    }
+
+    available.sort_unstable();
+    Err(format!(
+        "unknown container runtime `{runtime}`, the Docker daemon supports: {}",
+        available.join(", ")
+    ))
+}
+
+/// Rewrites the descriptor of an image hosted on Docker Hub to refer to the given mirror.
+fn mirrored_descriptor(mirror: &str, descriptor: &str) -> String {
+    let path = match descriptor.split_once('/') {
+        Some(("docker.io" | "index.docker.io", path)) => path,
+        // the first component names a registry if it looks like a host
+        Some((host, _)) if host.contains(['.', ':']) || host == "localhost" => {
+            return descriptor.to_owned();
+        }
+        _ => descriptor,
+    };
+    let mirror = mirror.trim_end_matches('/');
+    if path.contains('/') {
+        format!("{mirror}/{path}")
+    } else {
+        format!("{mirror}/library/{path}")
+    }
+}
+
+async fn network_exists(client: &Docker, network: &str) -> bool {
+    let networks = client.list_networks::<String>(None).await.unwrap();
+    networks
+        .iter()
+        .any(|i| matches!(&i.name, Some(name) if name == network))
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        if self.command.should_remove() {
+            let guard = self.created_networks.read().expect("failed to lock RwLock");
+            for network in guard.iter() {
+                block_on(async { self.bollard.remove_network(network).await.unwrap() });
             }
-            env::Command::Keep => {}
         }
     }
 }
 
 #[async_trait]
 impl DockerAsync for Http {
-    fn stdout_logs(&self, id: &str) -> LogStreamAsync<'_> {
+    fn stdout_logs(&self, id: &str) -> LogStreamAsync<'static> {
         self.logs(
             id.to_owned(),
             LogsOptions {
@@ -301,11 +1512,60 @@ impl DockerAsync for Http {
         )
     }
 
-    fn stderr_logs(&self, id: &str) -> LogStreamAsync<'_> {
+    fn stderr_logs(&self, id: &str) -> LogStreamAsync<'static> {
+        self.logs(
+            id.to_owned(),
+            LogsOptions {
+                follow: true,
+                stderr: true,
+                tail: "all".to_owned(),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn combined_logs(&self, id: &str) -> LogStreamAsync<'static> {
         self.logs(
             id.to_owned(),
             LogsOptions {
                 follow: true,
+                stdout: true,
+                stderr: true,
+                tail: "all".to_owned(),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn recent_logs(&self, id: &str, source: LogSource, lines: usize) -> LogStreamAsync<'static> {
+        self.logs(
+            id.to_owned(),
+            LogsOptions {
+                stdout: source == LogSource::StdOut,
+                stderr: source == LogSource::StdErr,
+                tail: lines.to_string(),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn current_raw_logs(&self, id: &str) -> RawLogStreamAsync<'static> {
+        self.raw_logs(
+            id.to_owned(),
+            LogsOptions {
+                stdout: true,
+                stderr: true,
+                tail: "all".to_owned(),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn current_logs(&self, id: &str) -> LogStreamAsync<'static> {
+        self.logs(
+            id.to_owned(),
+            LogsOptions {
+                stdout: true,
                 stderr: true,
                 tail: "all".to_owned(),
                 ..Default::default()
@@ -313,161 +1573,1555 @@ impl DockerAsync for Http {
         )
     }
 
-    async fn ports(&self, id: &str) -> Ports {
-        self.inspect(id)
-            .await
-            .network_settings
-            .unwrap_or_default()
-            .ports
-            .map(Ports::from)
-            .unwrap_or_default()
+    fn host(&self) -> String {
+        env::published_ports_host(self.docker_host().as_deref())
+    }
+
+    async fn ports(&self, id: &str) -> Ports {
+        self.inspect(id)
+            .await
+            .network_settings
+            .unwrap_or_default()
+            .ports
+            .map(Ports::from)
+            .unwrap_or_default()
+    }
+
+    async fn inspect(&self, id: &str) -> ContainerInspectResponse {
+        self.timed("inspect", id, async {
+            self.inner
+                .bollard
+                .inspect_container(id, None)
+                .await
+                .unwrap()
+        })
+        .await
+    }
+
+    async fn rm(&self, id: &str) {
+        self.timed("remove", id, self.remove_container(id, true))
+            .await
+    }
+
+    async fn rm_keep_volumes(&self, id: &str) {
+        self.timed("remove", id, self.remove_container(id, false))
+            .await
+    }
+
+    async fn stop(&self, id: &str) {
+        self.timed("stop", id, async {
+            self.inner.bollard.stop_container(id, None).await.unwrap();
+        })
+        .await
+    }
+
+    async fn start(&self, id: &str) {
+        self.timed("start", id, async {
+            self.inner
+                .bollard
+                .start_container::<String>(id, None)
+                .await
+                .unwrap();
+        })
+        .await
+    }
+
+    async fn recreate(&self, id: &str) -> String {
+        self.timed("recreate", id, async {
+            let (options, config, network_aliases) = self
+                .create_spec
+                .clone()
+                .unwrap_or_else(|| panic!("container {id} cannot be recreated"));
+            let container_id = self
+                .inner
+                .bollard
+                .create_container(options, config)
+                .await
+                .unwrap()
+                .id;
+            self.track_container(&container_id);
+
+            #[cfg(feature = "watchdog")]
+            if self.inner.command != env::Command::Keep {
+                crate::watchdog::register(container_id.clone(), self.inner.docker_host.clone());
+            }
+
+            self.start(&container_id).await;
+            self.connect_network_aliases(&container_id, &network_aliases)
+                .await;
+            container_id
+        })
+        .await
+    }
+
+    async fn restart(&self, id: &str) {
+        self.timed("restart", id, async {
+            self.inner
+                .bollard
+                .restart_container(id, None)
+                .await
+                .unwrap();
+        })
+        .await
+    }
+
+    async fn wait(&self, id: &str) -> i64 {
+        self.inner
+            .bollard
+            .wait_container(
+                id,
+                Some(WaitContainerOptions {
+                    condition: "not-running",
+                }),
+            )
+            .next()
+            .await
+            .expect("wait stream ended without a response")
+            .unwrap()
+            .status_code
+    }
+
+    async fn copy_to(&self, id: &str, tar_bytes: Vec<u8>, dest_dir: &str) {
+        self.timed(
+            "copy files to",
+            id,
+            self.upload(id, tar_bytes.into(), dest_dir),
+        )
+        .await
+    }
+
+    async fn copy_stream_to(
+        &self,
+        id: &str,
+        tar: BoxStream<'static, Result<Vec<u8>, io::Error>>,
+        dest_dir: &str,
+    ) {
+        self.timed(
+            "copy files to",
+            id,
+            self.upload(id, Body::wrap_stream(tar), dest_dir),
+        )
+        .await
+    }
+
+    async fn copy_from(
+        &self,
+        id: &str,
+        path: &str,
+        options: CopyFromOptions,
+    ) -> Result<Vec<u8>, CopyFromError> {
+        self.timed("copy files from", id, async {
+            self.inner
+                .bollard
+                .download_from_container(id, Some(DownloadFromContainerOptions { path }))
+                .try_fold(options.archive(), |mut archive, chunk| async move {
+                    archive.write(&chunk);
+                    Ok(archive)
+                })
+                .await
+                .map(|archive| archive.finish())
+                .map_err(|err| match err {
+                    bollard::errors::Error::DockerResponseServerError {
+                        status_code: 404, ..
+                    } => CopyFromError::NotFound(path.to_owned()),
+                    err => CopyFromError::Docker {
+                        path: path.to_owned(),
+                        message: err.to_string(),
+                    },
+                })
+        })
+        .await
+    }
+
+    async fn changes(&self, id: &str) -> Vec<FilesystemChange> {
+        self.timed("list the changes of", id, async {
+            self.inner
+                .bollard
+                .container_changes(id)
+                .await
+                .unwrap()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|change| FilesystemChange {
+                    path: change.path,
+                    kind: match change.kind {
+                        0 => ChangeKind::Modified,
+                        1 => ChangeKind::Added,
+                        2 => ChangeKind::Deleted,
+                        other => panic!("unknown filesystem change kind {other}"),
+                    },
+                })
+                .collect()
+        })
+        .await
+    }
+
+    async fn exec(&self, id: &str, spec: ExecSpec) -> ExecResult {
+        let exec = self
+            .timed(
+                "exec in",
+                id,
+                self.inner.bollard.create_exec(
+                    id,
+                    CreateExecOptions {
+                        attach_stdout: Some(true),
+                        attach_stderr: Some(true),
+                        cmd: Some(spec.cmd),
+                        user: spec.user,
+                        working_dir: spec.working_dir,
+                        ..Default::default()
+                    },
+                ),
+            )
+            .await
+            .unwrap_or_else(|e| panic!("failed to create exec in container {id}: {e}"));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        // the output is read for as long as the command runs, so only starting it is timed
+        let started = self
+            .timed("exec in", id, self.inner.bollard.start_exec(&exec.id, None))
+            .await;
+        match started {
+            Ok(StartExecResults::Attached { mut output, .. }) => {
+                while let Some(chunk) = output.next().await {
+                    match chunk.unwrap() {
+                        LogOutput::StdOut { message } => stdout.extend_from_slice(&message),
+                        LogOutput::StdErr { message } => stderr.extend_from_slice(&message),
+                        _ => {}
+                    }
+                }
+            }
+            Ok(StartExecResults::Detached) => unreachable!("exec is started attached"),
+            Err(e) => panic!("failed to start exec in container {id}: {e}"),
+        }
+
+        let exit_code = self
+            .timed("exec in", id, self.exec_exit_code(id, &exec.id))
+            .await;
+
+        ExecResult {
+            stdout,
+            stderr,
+            exit_code,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{BindMode, Capability, Healthcheck, Resources, WaitFor},
+        images::generic::GenericImage,
+    };
+    use futures::FutureExt;
+    use std::{panic::AssertUnwindSafe, sync::atomic::AtomicUsize};
+
+    async fn inspect(client: &bollard::Docker, id: &str) -> ContainerInspectResponse {
+        client.inspect_container(id, None).await.unwrap()
+    }
+
+    /// Serves a single `/version` request like a Docker daemon supporting `api_version`.
+    async fn fake_daemon(api_version: &'static str) -> String {
+        let body = format!(r#"{{"ApiVersion":"{api_version}","MinAPIVersion":"1.12"}}"#);
+        fake_daemon_responding("200 OK", body).await
+    }
+
+    /// Starts a fake Docker daemon that answers a single request with the given status and body.
+    async fn fake_daemon_responding(status: &'static str, body: String) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = socket.read(&mut request).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        format!("tcp://{address}")
+    }
+
+    /// Starts a fake Docker daemon that answers one request after another with the given statuses
+    /// and bodies. Returns its address and the number of requests it received.
+    async fn fake_daemon_answering(
+        responses: Vec<(&'static str, &'static str)>,
+    ) -> (String, Arc<AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let received = requests.clone();
+        tokio::spawn(async move {
+            let mut responses = responses.into_iter();
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0; 1024];
+                while socket.read(&mut request).await.unwrap_or(0) > 0 {
+                    received.fetch_add(1, Ordering::SeqCst);
+                    let (status, body) = responses.next().expect("unexpected request");
+                    let response = format!(
+                        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+                        body.len()
+                    );
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                }
+            }
+        });
+
+        (format!("tcp://{address}"), requests)
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "cannot run 2 images at once, only 1 more containers may be alive at the same time"
+    )]
+    async fn run_all_should_reject_more_images_than_max_concurrent_containers() {
+        let docker = Http::connected_to("tcp://127.0.0.1:1".to_owned()).with_max_concurrent(1);
+        let images = vec![
+            GenericImage::new("hello-world", "latest"),
+            GenericImage::new("hello-world", "latest"),
+        ];
+
+        // fails instead of waiting for a container to be dropped
+        tokio::time::timeout(Duration::from_secs(5), docker.run_all(images))
+            .await
+            .expect("run_all should fail fast");
+    }
+
+    #[tokio::test]
+    async fn negotiate_version_should_downgrade_to_version_of_older_daemon() {
+        let client = connect_to(&fake_daemon("1.30").await);
+
+        negotiate_version(&client).await;
+
+        assert_eq!(client.client_version().to_string(), "1.30");
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "pulling an image for a platform requires Docker API version 1.32, but the Docker daemon only supports 1.30"
+    )]
+    async fn require_api_version_should_panic_if_daemon_is_too_old() {
+        let client = connect_to(&fake_daemon("1.30").await);
+        negotiate_version(&client).await;
+
+        require_api_version(
+            &client,
+            "pulling an image for a platform",
+            &PLATFORM_API_VERSION,
+        );
+    }
+
+    fn layer_status(status: &str) -> CreateImageInfo {
+        CreateImageInfo {
+            id: Some("a1b2c3".to_owned()),
+            status: Some(status.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pull_summary_should_count_pulled_and_cached_layers() {
+        let mut summary = PullSummary::default();
+
+        for status in [
+            "Pulling fs layer",
+            "Already exists",
+            "Downloading",
+            "Pull complete",
+            "Pull complete",
+        ] {
+            summary.record(&layer_status(status));
+        }
+
+        assert_eq!(
+            summary,
+            PullSummary {
+                layers_pulled: 2,
+                layers_cached: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_runtime_should_be_reported_with_available_ones() {
+        let available = ["runc".to_owned(), "io.containerd.runc.v2".to_owned()];
+
+        assert_eq!(check_runtime("runc", available.iter()), Ok(()));
+        assert_eq!(
+            check_runtime("sysbox-runc", available.iter()),
+            Err(
+                "unknown container runtime `sysbox-runc`, the Docker daemon supports: io.containerd.runc.v2, runc"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[should_panic(expected = "unknown container runtime `nonexistent-runtime`")]
+    async fn http_run_should_reject_unknown_runtime() {
+        let docker = Http::new();
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_runtime("nonexistent-runtime");
+
+        docker.run(image).await;
+    }
+
+    #[test]
+    fn connection_error_should_describe_failure() {
+        let error: Box<dyn std::error::Error> = Box::new(ConnectionError::NoDaemon(vec![
+            "unix:///var/run/docker.sock".to_owned(),
+            "tcp://localhost:2375".to_owned(),
+        ]));
+
+        assert_eq!(
+            error.to_string(),
+            "failed to find a Docker daemon, tried unix:///var/run/docker.sock, tcp://localhost:2375; set DOCKER_HOST to the address of the daemon"
+        );
+        assert!(error.source().is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_inspect_image_should_return_exposed_ports() {
+        let docker = Http::new();
+
+        let image = docker.inspect_image("redis:7-alpine").await;
+
+        let exposed_ports = image.config.unwrap().exposed_ports.unwrap();
+        assert!(exposed_ports.contains_key("6379/tcp"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_pull_should_report_layers_of_present_image_as_cached() {
+        let docker = Http::new();
+        docker.pull("redis:7-alpine").await;
+
+        let summary = docker.pull("redis:7-alpine").await;
+
+        assert_eq!(summary.layers_pulled, 0);
+        assert!(summary.layers_cached > 0);
+    }
+
+    #[test]
+    fn with_unix_socket_should_report_missing_socket() {
+        let error = Http::with_unix_socket("/nonexistent/docker.sock").unwrap_err();
+
+        assert!(matches!(
+            &error,
+            ConnectionError::SocketNotFound(path) if path == Path::new("/nonexistent/docker.sock")
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Docker socket `/nonexistent/docker.sock` does not exist"
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "failed to exec in container fake within 100ms")]
+    async fn operation_timeout_should_fail_slow_exec() {
+        // a daemon that accepts connections but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _socket = listener.accept().await.unwrap();
+            futures::future::pending::<()>().await
+        });
+        let docker = Http::connected_to(format!("tcp://{address}"))
+            .with_operation_timeout(Duration::from_millis(100));
+        let spec = ExecSpec {
+            cmd: vec!["true".to_owned()],
+            ..Default::default()
+        };
+
+        tokio::time::timeout(Duration::from_secs(5), docker.exec("fake", spec))
+            .await
+            .expect("exec should time out before the test does");
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "failed to inspect container fake within 100ms")]
+    async fn operation_timeout_should_fail_slow_inspect() {
+        // a daemon that accepts connections but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _socket = listener.accept().await.unwrap();
+            futures::future::pending::<()>().await
+        });
+        let docker = Http::connected_to(format!("tcp://{address}"))
+            .with_operation_timeout(Duration::from_millis(100));
+
+        tokio::time::timeout(Duration::from_secs(5), docker.inspect("fake"))
+            .await
+            .expect("inspect should time out before the test does");
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "directory `/missing` does not exist in container fake")]
+    async fn copy_to_should_report_missing_directory() {
+        let body = r#"{"message":"Could not find the file /missing in container fake"}"#;
+        let docker =
+            Http::connected_to(fake_daemon_responding("404 Not Found", body.to_owned()).await);
+
+        docker
+            .copy_to(
+                "fake",
+                crate::core::tar::single_file("app.conf", b"", 0o644),
+                "/missing",
+            )
+            .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "failed to copy into `/app` of container fake: Docker responded with status code 404: No such container: fake"
+    )]
+    async fn copy_to_should_report_missing_container() {
+        let body = r#"{"message":"No such container: fake"}"#;
+        let docker =
+            Http::connected_to(fake_daemon_responding("404 Not Found", body.to_owned()).await);
+
+        docker
+            .copy_to(
+                "fake",
+                crate::core::tar::single_file("app.conf", b"", 0o644),
+                "/app",
+            )
+            .await;
+    }
+
+    #[tokio::test]
+    async fn copy_from_should_report_missing_path() {
+        let body = r#"{"message":"Could not find the file /missing in container fake"}"#;
+        let docker =
+            Http::connected_to(fake_daemon_responding("404 Not Found", body.to_owned()).await);
+
+        let error = docker
+            .copy_from("fake", "/missing", CopyFromOptions::default())
+            .await;
+
+        assert_eq!(error, Err(CopyFromError::NotFound("/missing".to_owned())));
+    }
+
+    #[tokio::test]
+    async fn copy_from_with_gzip_should_compress_downloaded_archive() {
+        let archive = crate::core::tar::single_file("app.log", b"started", 0o644);
+        let body = String::from_utf8(archive.clone()).unwrap();
+        let docker = Http::connected_to(fake_daemon_responding("200 OK", body).await);
+
+        let compressed = docker
+            .copy_from(
+                "fake",
+                "/var/log/app.log",
+                CopyFromOptions::default().with_gzip(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(&compressed[..2], [0x1f, 0x8b]);
+        assert_eq!(crate::core::gzip::tests::gunzip(&compressed), archive);
+    }
+
+    #[tokio::test]
+    async fn pull_retries_should_retry_transient_registry_failures() {
+        let (address, requests) = fake_daemon_answering(vec![
+            (
+                "500 Internal Server Error",
+                r#"{"message":"Get \"https://registry-1.docker.io/v2/\": net/http: TLS handshake timeout"}"#,
+            ),
+            (
+                "200 OK",
+                r#"{"errorDetail":{"message":"read tcp: connection reset by peer"},"error":"read tcp: connection reset by peer"}"#,
+            ),
+            ("200 OK", r#"{"status":"Pull complete","id":"abc"}"#),
+        ])
+        .await;
+        let docker = Http::connected_to(address)
+            .with_api_version("1.41")
+            .with_pull_retries(2, Duration::from_millis(10));
+
+        let summary = docker.pull("fake:latest").await;
+
+        assert_eq!(summary.layers_pulled, 1);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn pull_retries_should_not_retry_missing_manifest() {
+        let (address, requests) = fake_daemon_answering(vec![(
+            "404 Not Found",
+            r#"{"message":"manifest for fake:latest not found: manifest unknown: manifest unknown"}"#,
+        )])
+        .await;
+        let docker = Http::connected_to(address)
+            .with_api_version("1.41")
+            .with_pull_retries(3, Duration::from_millis(10));
+
+        let pulled = AssertUnwindSafe(docker.pull("fake:latest"))
+            .catch_unwind()
+            .await;
+
+        assert!(pulled.is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn ping_should_succeed_if_daemon_answers() {
+        let docker = Http::connected_to(fake_daemon("1.41").await);
+
+        docker.ping().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn ping_should_report_unreachable_daemon() {
+        // nothing listens on the port once the listener is dropped
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let docker = Http::connected_to(format!("tcp://{address}"));
+
+        let error = docker.ping().await.unwrap_err();
+
+        assert!(
+            error.to_string().starts_with(&format!(
+                "could not reach Docker daemon at tcp://{address}: "
+            )),
+            "unexpected error: {error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn with_api_version_should_pin_version_instead_of_negotiating() {
+        let docker = Http::connected_to(fake_daemon("1.30").await).with_api_version("1.25");
+
+        docker.negotiate_version().await;
+
+        assert_eq!(docker.inner.bollard.client_version().to_string(), "1.25");
+    }
+
+    #[test]
+    fn api_version_should_be_parsed() {
+        assert_eq!(
+            parse_api_version("1.41").map(|version| version.to_string()),
+            Some("1.41".to_owned())
+        );
+        assert!(parse_api_version("latest").is_none());
+        assert!(parse_api_version("1").is_none());
+    }
+
+    #[tokio::test]
+    async fn with_docker_should_reuse_given_client() {
+        let bollard = connect_to(&fake_daemon("1.30").await);
+
+        let docker = Http::with_docker(bollard).with_command(env::Command::Keep);
+
+        assert_eq!(docker.inner.command, env::Command::Keep);
+        assert!(docker.inner.docker_host.is_none());
+        docker.negotiate_version().await;
+        assert_eq!(docker.inner.bollard.client_version().to_string(), "1.30");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn with_unix_socket_should_connect_through_socket() {
+        let path = std::env::temp_dir().join(format!("testcontainers-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let _listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+
+        let docker = Http::with_unix_socket(&path).unwrap();
+
+        assert_eq!(
+            format!("{docker:?}"),
+            format!(r#"Http {{ docker_host: "unix://{}" }}"#, path.display())
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn docker_socket_should_be_the_one_the_client_is_connected_to() {
+        let path = std::env::temp_dir().join(format!(
+            "testcontainers-mounted-{}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let _listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let docker = Http::with_unix_socket(&path).unwrap();
+
+        let image = docker.resolve_docker_socket(
+            RunnableImage::from(GenericImage::new("docker", "cli")).with_docker_socket(),
+        );
+
+        let path = path.display().to_string();
+        assert_eq!(
+            image.volumes().collect::<Vec<_>>(),
+            vec![(&path, &"/var/run/docker.sock".to_owned())]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "a pre-start hook cannot be combined with startup retries")]
+    async fn http_run_should_reject_pre_start_hook_with_startup_retries() {
+        let docker = Http::connected_to("tcp://localhost:2375".to_owned());
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_pre_start_hook(|_| async {}.boxed())
+            .with_startup_retries(1);
+
+        docker.run(image).await;
+    }
+
+    #[test]
+    fn mirrored_descriptor_should_expand_official_images() {
+        assert_eq!(
+            mirrored_descriptor("mirror.corp:5000", "redis:7"),
+            "mirror.corp:5000/library/redis:7"
+        );
+        assert_eq!(
+            mirrored_descriptor("mirror.corp:5000/", "docker.io/redis:7"),
+            "mirror.corp:5000/library/redis:7"
+        );
+    }
+
+    #[test]
+    fn mirrored_descriptor_should_keep_namespace_of_docker_hub_images() {
+        assert_eq!(
+            mirrored_descriptor("mirror.corp:5000", "bitnami/kafka:3.5"),
+            "mirror.corp:5000/bitnami/kafka:3.5"
+        );
+    }
+
+    #[test]
+    fn mirrored_descriptor_should_not_rewrite_images_of_other_registries() {
+        assert_eq!(
+            mirrored_descriptor("mirror.corp:5000", "ghcr.io/org/app:1"),
+            "ghcr.io/org/app:1"
+        );
+        assert_eq!(
+            mirrored_descriptor("mirror.corp:5000", "localhost/app:1"),
+            "localhost/app:1"
+        );
+    }
+
+    #[test]
+    fn mirrored_descriptor_should_keep_explicit_library_namespace() {
+        assert_eq!(
+            mirrored_descriptor("mirror.corp:5000", "index.docker.io/library/redis:7"),
+            "mirror.corp:5000/library/redis:7"
+        );
+        assert_eq!(
+            mirrored_descriptor("mirror.corp:5000", "library/redis@sha256:abc"),
+            "mirror.corp:5000/library/redis@sha256:abc"
+        );
+    }
+
+    #[test]
+    fn http_with_registry_mirror_should_run_images_of_mirror() {
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let docker = Http::connected_to("tcp://localhost:2375".to_owned());
+        assert_eq!(docker.descriptor(&image), "hello-world:latest");
+
+        let docker = docker.with_registry_mirror("mirror.corp:5000");
+
+        assert_eq!(
+            docker.descriptor(&image),
+            "mirror.corp:5000/library/hello-world:latest"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_should_wait_for_a_free_slot_if_max_concurrent_is_reached() {
+        let docker = Http::new().with_max_concurrent(1);
+        let first = docker.run(GenericImage::new("hello-world", "latest")).await;
+
+        let second = docker.run(GenericImage::new("hello-world", "latest"));
+        futures::pin_mut!(second);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(500), &mut second)
+                .await
+                .is_err(),
+            "second container must not start while the first one is alive"
+        );
+
+        drop(first);
+        second.await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_expose_all_ports_if_no_explicit_mapping_requested() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let container = docker.run(image).await;
+
+        // inspect volume and env
+        let container_details = inspect(&docker.inner.bollard, container.id()).await;
+        let publish_ports = container_details
+            .host_config
+            .unwrap()
+            .publish_all_ports
+            .unwrap();
+        assert_eq!(publish_ports, true, "publish_all_ports must be `true`");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_not_publish_ports_if_requested() {
+        let docker = Http::new();
+        let network = "no-published-ports";
+        let image = GenericImage::new("simple_web_server", "latest")
+            .with_exposed_port(80)
+            .with_wait_for(WaitFor::message_on_stdout("server is ready"));
+        let server = RunnableImage::from(image)
+            .with_network(network)
+            .with_container_name("unpublished_server")
+            .with_no_published_ports();
+        let server = docker.run(server).await;
+
+        let container_details = inspect(&docker.inner.bollard, server.id()).await;
+        let host_config = container_details.host_config.unwrap();
+        assert_ne!(host_config.publish_all_ports, Some(true));
+        assert!(host_config.port_bindings.unwrap_or_default().is_empty());
+        let ports = container_details.network_settings.unwrap().ports;
+        assert!(ports
+            .unwrap_or_default()
+            .values()
+            .all(|bindings| bindings.is_none()));
+
+        // the server is still reachable from within its network
+        let client = GenericImage::new("redis", "7-alpine")
+            .with_entrypoint("sh")
+            .with_wait_for(WaitFor::message_on_stdout("simple_web_server"));
+        let client = RunnableImage::from((
+            client,
+            vec![
+                "-c".to_owned(),
+                "wget -qO- http://unpublished_server; echo; sleep 60".to_owned(),
+            ],
+        ))
+        .with_network(network);
+        docker.run(client).await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_owned_bind_mount_should_be_writable_by_non_root_user() {
+        use std::os::unix::fs::MetadataExt;
+
+        let fixtures = tempfile::tempdir().unwrap();
+        std::fs::write(fixtures.path().join("fixture.txt"), "fixture\n").unwrap();
+        let host_owner = std::fs::metadata(fixtures.path()).unwrap();
+        let docker = Http::new();
+        let image = GenericImage::new("redis", "7-alpine")
+            .with_wait_for(WaitFor::message_on_stdout("Ready to accept connections"));
+        let image = RunnableImage::from(image)
+            .with_owned_bind_mount((fixtures.path().to_str().unwrap(), "/fixtures"), "redis");
+        let container = docker.run(image).await;
+
+        let written = docker
+            .exec(
+                container.id(),
+                ExecSpec {
+                    cmd: vec![
+                        "sh".to_owned(),
+                        "-c".to_owned(),
+                        "echo more >> /fixtures/fixture.txt".to_owned(),
+                    ],
+                    user: Some("redis".to_owned()),
+                    working_dir: None,
+                },
+            )
+            .await;
+        // hands the files back, so that the temporary directory can be removed
+        docker
+            .exec(
+                container.id(),
+                ExecSpec {
+                    cmd: vec![
+                        "chown".to_owned(),
+                        "-R".to_owned(),
+                        format!("{}:{}", host_owner.uid(), host_owner.gid()),
+                        "/fixtures".to_owned(),
+                    ],
+                    user: Some("0".to_owned()),
+                    working_dir: None,
+                },
+            )
+            .await;
+
+        assert_eq!(
+            written.exit_code,
+            0,
+            "{}",
+            String::from_utf8_lossy(&written.stderr)
+        );
+        assert_eq!(
+            std::fs::read_to_string(fixtures.path().join("fixture.txt")).unwrap(),
+            "fixture\nmore\n"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_exec_should_capture_output_and_exit_code() {
+        let docker = Http::new();
+        let image = GenericImage::new("redis", "7-alpine")
+            .with_wait_for(WaitFor::message_on_stdout("Ready to accept connections"));
+        let container = docker.run(image).await;
+
+        let result = container
+            .exec(vec![
+                "sh".to_owned(),
+                "-c".to_owned(),
+                "echo out; echo err >&2; exit 3".to_owned(),
+            ])
+            .await;
+
+        assert_eq!(result.stdout, b"out\n");
+        assert_eq!(result.stderr, b"err\n");
+        assert_eq!(result.exit_code, 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_should_connect_aliased_networks_after_start() {
+        let docker = Http::new();
+        let network = "post-start-aliases";
+        let image = GenericImage::new("simple_web_server", "latest")
+            .with_wait_for(WaitFor::message_on_stdout("server is ready"));
+        let server = RunnableImage::from(image).with_network_alias(network, "aliased_server");
+        let server = docker.run(server).await;
+
+        let endpoint = inspect(&docker.inner.bollard, server.id())
+            .await
+            .network_settings
+            .and_then(|settings| settings.networks)
+            .and_then(|mut networks| networks.remove(network))
+            .unwrap();
+        assert!(endpoint
+            .aliases
+            .unwrap_or_default()
+            .contains(&"aliased_server".to_owned()));
+
+        // a peer on the network resolves the alias
+        let client = GenericImage::new("redis", "7-alpine")
+            .with_entrypoint("sh")
+            .with_wait_for(WaitFor::message_on_stdout("simple_web_server"));
+        let client = RunnableImage::from((
+            client,
+            vec![
+                "-c".to_owned(),
+                "wget -qO- http://aliased_server; echo; sleep 60".to_owned(),
+            ],
+        ))
+        .with_network(network);
+        docker.run(client).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_map_exposed_port() {
+        let docker = Http::new();
+        let image = GenericImage::new("simple_web_server", "latest").with_exposed_port(5000);
+        let container = docker.run(image).await;
+        container.get_host_port_ipv4(5000).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_expose_only_requested_ports() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let image = RunnableImage::from(image)
+            .with_mapped_port((123, 456))
+            .with_mapped_port((555, 888));
+        let container = docker.run(image).await;
+
+        let container_details = inspect(&docker.inner.bollard, container.id()).await;
+
+        let port_bindings = container_details
+            .host_config
+            .unwrap()
+            .port_bindings
+            .unwrap();
+        assert!(port_bindings.contains_key("456/tcp"));
+        assert!(port_bindings.contains_key("888/tcp"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_expose_ports_on_localhost_if_requested() {
+        let docker = Http::new();
+        let image = GenericImage::new("simple_web_server", "latest")
+            .with_exposed_port(80)
+            .with_wait_for(WaitFor::message_on_stdout("server is ready"));
+        let image = RunnableImage::from(image).with_expose_on_localhost(true);
+        let container = docker.run(image).await;
+
+        let container_details = inspect(&docker.inner.bollard, container.id()).await;
+        let port_bindings = container_details
+            .host_config
+            .unwrap()
+            .port_bindings
+            .unwrap()
+            .remove("80/tcp")
+            .flatten()
+            .unwrap();
+        assert!(port_bindings
+            .iter()
+            .all(|binding| binding.host_ip.as_deref() == Some("127.0.0.1")));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_include_network() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let image = RunnableImage::from(image).with_network("awesome-net-1");
+        let container = docker.run(image).await;
+
+        let container_details = inspect(&docker.inner.bollard, container.id()).await;
+        let networks = container_details
+            .network_settings
+            .unwrap()
+            .networks
+            .unwrap();
+
+        assert!(
+            networks.contains_key("awesome-net-1"),
+            "Networks is {networks:?}"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_include_name() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let image = RunnableImage::from(image).with_container_name("hello_container");
+        let container = docker.run(image).await;
+
+        let container_details = inspect(&docker.inner.bollard, container.id()).await;
+        let container_name = container_details.name.unwrap();
+        assert!(container_name.ends_with("hello_container"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_should_create_network_if_image_needs_it_and_drop_it_in_the_end() {
+        let client = connect();
+        let hello_world = GenericImage::new("hello-world", "latest");
+
+        {
+            let docker = Http::new();
+            assert!(!network_exists(&client, "awesome-net-2").await);
+
+            // creating the first container creates the network
+            let _container1 = docker
+                .run(RunnableImage::from(hello_world.clone()).with_network("awesome-net-2"))
+                .await;
+
+            // creating a 2nd container doesn't fail because check if the network exists already
+            let _container2 = docker
+                .run(RunnableImage::from(hello_world).with_network("awesome-net-2"))
+                .await;
+
+            assert!(network_exists(&client, "awesome-net-2").await);
+        }
+
+        // client has been dropped, should clean up networks
+        assert!(!network_exists(&client, "awesome-net-2").await)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_should_run_containers_on_the_default_network() {
+        let client = connect();
+        let hello_world = GenericImage::new("hello-world", "latest");
+
+        {
+            let docker = Http::new().with_default_network("awesome-net-default");
+
+            let container1 = docker.run(hello_world.clone()).await;
+            let container2 = docker.run(hello_world).await;
+
+            for container in [container1.id(), container2.id()] {
+                let container_details = inspect(&client, container).await;
+                let networks = container_details
+                    .network_settings
+                    .and_then(|settings| settings.networks)
+                    .unwrap();
+                assert!(
+                    networks.contains_key("awesome-net-default"),
+                    "container {container} should be attached to the default network"
+                );
+            }
+        }
+
+        // client has been dropped, should clean up the default network as well
+        assert!(!network_exists(&client, "awesome-net-default").await)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_should_create_network_with_custom_driver() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let image = RunnableImage::from(image)
+            .with_network("awesome-net-3")
+            .with_network_driver("macvlan")
+            .with_network_driver_opt(("macvlan_mode", "bridge"));
+        let _container = docker.run(image).await;
+
+        let network = docker
+            .inner
+            .bollard
+            .inspect_network::<String>("awesome-net-3", None)
+            .await
+            .unwrap();
+
+        assert_eq!(network.driver.as_deref(), Some("macvlan"));
+        assert_eq!(
+            network
+                .options
+                .unwrap_or_default()
+                .get("macvlan_mode")
+                .map(String::as_str),
+            Some("bridge")
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_mount_volumes_from_other_container() {
+        let docker = Http::new();
+
+        // the redis image declares `/data` as a volume
+        let seeder = GenericImage::new("redis", "7-alpine")
+            .with_entrypoint("sh")
+            .with_wait_for(WaitFor::message_on_stdout("seeded"));
+        let seeder = RunnableImage::from((
+            seeder,
+            vec![
+                "-c".to_owned(),
+                "echo shared > /data/seed.txt && echo seeded && sleep 60".to_owned(),
+            ],
+        ));
+        let seeder = docker.run(seeder).await;
+
+        let reader = GenericImage::new("redis", "7-alpine")
+            .with_entrypoint("cat")
+            .with_wait_for(WaitFor::message_on_stdout("shared"));
+        let reader = RunnableImage::from((reader, vec!["/data/seed.txt".to_owned()]))
+            .with_volumes_from(seeder.id(), BindMode::ReadOnly);
+        let reader = docker.run(reader).await;
+
+        let container_details = inspect(&docker.inner.bollard, reader.id()).await;
+        let volumes_from = container_details.host_config.unwrap().volumes_from.unwrap();
+        assert_eq!(volumes_from, vec![format!("{}:ro", seeder.id())]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_allocate_tty() {
+        let docker = Http::new();
+        let image = GenericImage::new("simple_web_server", "latest")
+            .with_wait_for(WaitFor::message_on_stdout("server is ready"));
+        let image = RunnableImage::from(image).with_tty(true);
+        let container = docker.run(image).await;
+
+        let container_details = inspect(&docker.inner.bollard, container.id()).await;
+        let tty = container_details.config.unwrap().tty.unwrap();
+
+        assert!(tty, "tty must be `true`");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_logs_should_tolerate_invalid_utf8() {
+        let docker = Http::new();
+        let image = GenericImage::new("redis", "7-alpine")
+            .with_entrypoint("sh")
+            .with_wait_for(WaitFor::message_on_stdout("ready"));
+        let image = RunnableImage::from((
+            image,
+            vec![
+                "-c".to_owned(),
+                r"printf 'binary \377\376\nready\n'; sleep 60".to_owned(),
+            ],
+        ));
+        let container = docker.run(image).await;
+
+        assert_eq!(container.log_bytes().await, b"binary \xff\xfe\nready\n");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_until_log_should_return_output_up_to_marker() {
+        let docker = Http::new();
+        let image = GenericImage::new("redis", "7-alpine").with_entrypoint("sh");
+        let image = RunnableImage::from((
+            image,
+            vec![
+                "-c".to_owned(),
+                "echo one; echo two >&2; echo three; echo DONE; echo after; sleep 60".to_owned(),
+            ],
+        ));
+
+        let output = docker.run_until_log(image, "DONE").await;
+
+        assert_eq!(output, "one\ntwo\nthree\nDONE");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_should_run_image_under_new_tag() {
+        let docker = Http::new();
+        // make sure the source image exists locally
+        docker.run(GenericImage::new("hello-world", "latest")).await;
+
+        docker
+            .tag_image("hello-world:latest", "testcontainers-hello-world", "tagged")
+            .await;
+        let container = docker
+            .run(GenericImage::new("testcontainers-hello-world", "tagged"))
+            .await;
+
+        let container_details = inspect(&docker.inner.bollard, container.id()).await;
+        assert_eq!(
+            container_details.config.unwrap().image.as_deref(),
+            Some("testcontainers-hello-world:tagged")
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_should_wait_for_message_sequence() {
+        let docker = Http::new();
+        let image = GenericImage::new("redis", "7-alpine")
+            .with_entrypoint("sh")
+            .with_wait_for(WaitFor::message_sequence_on_stdout([
+                "loading",
+                "migrating",
+                "ready",
+            ]));
+        let image = RunnableImage::from((
+            image,
+            vec![
+                "-c".to_owned(),
+                "echo loading; sleep 1; echo migrating; sleep 1; echo ready; sleep 60".to_owned(),
+            ],
+        ));
+
+        let container = docker.run(image).await;
+
+        assert_eq!(container.log_line_count().await, 3);
     }
 
-    async fn inspect(&self, id: &str) -> ContainerInspectResponse {
-        self.inner
-            .bollard
-            .inspect_container(id, None)
-            .await
-            .unwrap()
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_should_call_pre_start_hook_before_starting_container() {
+        let docker = Http::new();
+        let image = GenericImage::new("redis", "7-alpine")
+            .with_entrypoint("sh")
+            .with_wait_for(WaitFor::message_on_stdout("configured by hook"));
+        let image = RunnableImage::from((
+            image,
+            vec!["-c".to_owned(), "cat /app.conf; sleep 60".to_owned()],
+        ))
+        .with_pre_start_hook(|container| {
+            async move { container.copy_to("configured by hook\n", "/app.conf").await }.boxed()
+        });
+
+        docker.run(image).await;
     }
 
-    async fn rm(&self, id: &str) {
-        self.inner
-            .bollard
-            .remove_container(
-                id,
-                Some(RemoveContainerOptions {
-                    force: true,
-                    v: true,
-                    ..Default::default()
-                }),
-            )
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_should_apply_config_hook() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let image = RunnableImage::from(image).with_config_hook(|config| {
+            config.stop_timeout = Some(42);
+        });
+        let container = docker.run(image).await;
+
+        let config = inspect(&docker.inner.bollard, container.id())
             .await
+            .config
             .unwrap();
-    }
 
-    async fn stop(&self, id: &str) {
-        self.inner.bollard.stop_container(id, None).await.unwrap();
+        assert_eq!(config.stop_timeout, Some(42));
     }
 
-    async fn start(&self, id: &str) {
-        self.inner
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_rm_should_keep_anonymous_volumes_if_requested() {
+        let docker = Http::new();
+        // the redis image declares `/data` as a volume
+        let image = GenericImage::new("redis", "7-alpine")
+            .with_wait_for(WaitFor::message_on_stdout("Ready to accept connections"));
+        let image = RunnableImage::from(image).with_remove_volumes(false);
+        let container = docker.run(image).await;
+
+        let container_details = inspect(&docker.inner.bollard, container.id()).await;
+        let volume = container_details
+            .mounts
+            .unwrap()
+            .into_iter()
+            .find(|mount| mount.destination.as_deref() == Some("/data"))
+            .and_then(|mount| mount.name)
+            .unwrap();
+        container.rm().await;
+
+        assert!(docker.inner.bollard.inspect_volume(&volume).await.is_ok());
+        docker
+            .inner
             .bollard
-            .start_container::<String>(id, None)
+            .remove_volume(&volume, None)
             .await
             .unwrap();
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::images::generic::GenericImage;
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_set_console_size() {
+        let docker = Http::new();
+        let image = GenericImage::new("simple_web_server", "latest")
+            .with_wait_for(WaitFor::message_on_stdout("server is ready"));
+        let image = RunnableImage::from(image)
+            .with_tty(true)
+            .with_console_size(40, 120);
+        let container = docker.run(image).await;
 
-    async fn inspect(client: &bollard::Docker, id: &str) -> ContainerInspectResponse {
-        client.inspect_container(id, None).await.unwrap()
+        let container_details = inspect(&docker.inner.bollard, container.id()).await;
+        let console_size = container_details.host_config.unwrap().console_size;
+
+        assert_eq!(console_size, Some(vec![40, 120]));
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn http_run_command_should_expose_all_ports_if_no_explicit_mapping_requested() {
+    async fn http_run_command_should_set_memory_reservation() {
         let docker = Http::new();
         let image = GenericImage::new("hello-world", "latest");
+        let image = RunnableImage::from(image).with_memory_reservation(64 * 1024 * 1024);
         let container = docker.run(image).await;
 
-        // inspect volume and env
         let container_details = inspect(&docker.inner.bollard, container.id()).await;
-        let publish_ports = container_details
+        let memory_reservation = container_details
             .host_config
             .unwrap()
-            .publish_all_ports
+            .memory_reservation
             .unwrap();
-        assert_eq!(publish_ports, true, "publish_all_ports must be `true`");
+
+        assert_eq!(memory_reservation, 64 * 1024 * 1024);
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn http_run_command_should_map_exposed_port() {
+    async fn http_run_command_should_wait_for_shell_healthcheck() {
         let docker = Http::new();
-        let image = GenericImage::new("simple_web_server", "latest").with_exposed_port(5000);
+        let image =
+            GenericImage::new("simple_web_server", "latest").with_wait_for(WaitFor::Healthcheck);
+        let image = RunnableImage::from(image)
+            .with_healthcheck(Healthcheck {
+                interval: Some(Duration::from_millis(200)),
+                ..Default::default()
+            })
+            // the image ships without curl, so connect through bash instead
+            .with_healthcheck_cmd_shell("bash -c 'echo > /dev/tcp/127.0.0.1/80' || exit 1");
         let container = docker.run(image).await;
-        container.get_host_port_ipv4(5000).await;
+
+        let state = inspect(&docker.inner.bollard, container.id())
+            .await
+            .state
+            .unwrap();
+        assert_eq!(
+            state.health.unwrap().status,
+            Some(bollard::models::HealthStatusEnum::HEALTHY)
+        );
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn http_run_command_should_expose_only_requested_ports() {
+    async fn http_run_command_should_set_healthcheck() {
         let docker = Http::new();
         let image = GenericImage::new("hello-world", "latest");
-        let image = RunnableImage::from(image)
-            .with_mapped_port((123, 456))
-            .with_mapped_port((555, 888));
+        let image = RunnableImage::from(image).with_healthcheck(Healthcheck {
+            test: vec!["CMD".to_owned(), "/hello".to_owned()],
+            interval: Some(Duration::from_secs(5)),
+            retries: Some(3),
+            ..Default::default()
+        });
         let container = docker.run(image).await;
 
         let container_details = inspect(&docker.inner.bollard, container.id()).await;
+        let healthcheck = container_details.config.unwrap().healthcheck.unwrap();
 
-        let port_bindings = container_details
-            .host_config
-            .unwrap()
-            .port_bindings
-            .unwrap();
-        assert!(port_bindings.contains_key("456/tcp"));
-        assert!(port_bindings.contains_key("888/tcp"));
+        assert_eq!(
+            healthcheck.test,
+            Some(vec!["CMD".to_owned(), "/hello".to_owned()])
+        );
+        assert_eq!(healthcheck.interval, Some(5_000_000_000));
+        assert_eq!(healthcheck.retries, Some(3));
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn http_run_command_should_include_network() {
+    async fn http_run_command_should_set_userns_mode() {
         let docker = Http::new();
         let image = GenericImage::new("hello-world", "latest");
-        let image = RunnableImage::from(image).with_network("awesome-net-1");
+        let image = RunnableImage::from(image).with_userns_mode("host");
         let container = docker.run(image).await;
 
         let container_details = inspect(&docker.inner.bollard, container.id()).await;
-        let networks = container_details
-            .network_settings
+        let userns_mode = container_details.host_config.unwrap().userns_mode;
+
+        assert_eq!(userns_mode.as_deref(), Some("host"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_pull_image_for_platform() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let image = RunnableImage::from(image).with_platform("linux/arm64");
+        let container = docker.run(image).await;
+
+        let container_details = inspect(&docker.inner.bollard, container.id()).await;
+        let image_details = docker
+            .inner
+            .bollard
+            .inspect_image(&container_details.image.unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(image_details.architecture.as_deref(), Some("arm64"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[should_panic(
+        expected = "failed to pull image `hello-world:latest` for platform `linux/riscv32`"
+    )]
+    async fn http_run_command_should_panic_if_platform_is_not_in_manifest_list() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let image = RunnableImage::from(image).with_platform("linux/riscv32");
+        docker.run(image).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_set_hostname_and_domainname() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let image = RunnableImage::from(image)
+            .with_hostname("node1")
+            .with_domainname("cluster.local");
+        let container = docker.run(image).await;
+
+        let config = inspect(&docker.inner.bollard, container.id())
+            .await
+            .config
+            .unwrap();
+
+        assert_eq!(config.hostname.as_deref(), Some("node1"));
+        assert_eq!(config.domainname.as_deref(), Some("cluster.local"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_macro_should_label_container_with_call_site() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let line = line!() + 1;
+        let container = crate::run!(docker, image).await;
+
+        let labels = inspect(&docker.inner.bollard, container.id())
+            .await
+            .config
             .unwrap()
-            .networks
+            .labels
             .unwrap();
 
-        assert!(
-            networks.contains_key("awesome-net-1"),
-            "Networks is {networks:?}"
+        assert_eq!(
+            labels.get(crate::core::SOURCE_LABEL),
+            Some(&format!("{} ({}:{line})", module_path!(), file!()))
         );
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn http_run_command_should_include_name() {
+    async fn http_run_command_should_set_exec_spec() {
         let docker = Http::new();
-        let image = GenericImage::new("hello-world", "latest");
-        let image = RunnableImage::from(image).with_container_name("hello_container");
+        let image = GenericImage::new("redis", "7-alpine");
+        let image = RunnableImage::from(image).with_exec_spec(ExecSpec {
+            cmd: vec!["sleep".to_owned(), "60".to_owned()],
+            user: Some("1000:1000".to_owned()),
+            working_dir: Some("/tmp".to_owned()),
+        });
         let container = docker.run(image).await;
 
-        let container_details = inspect(&docker.inner.bollard, container.id()).await;
-        let container_name = container_details.name.unwrap();
-        assert!(container_name.ends_with("hello_container"));
+        let config = inspect(&docker.inner.bollard, container.id())
+            .await
+            .config
+            .unwrap();
+
+        assert_eq!(config.cmd, Some(vec!["sleep".to_owned(), "60".to_owned()]));
+        assert_eq!(config.user.as_deref(), Some("1000:1000"));
+        assert_eq!(config.working_dir.as_deref(), Some("/tmp"));
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn http_should_create_network_if_image_needs_it_and_drop_it_in_the_end() {
-        let client = bollard::Docker::connect_with_http_defaults().unwrap();
-        let hello_world = GenericImage::new("hello-world", "latest");
+    async fn http_run_command_should_keep_default_cmd_with_custom_entrypoint() {
+        let docker = Http::new();
+        let image = GenericImage::new("redis", "7-alpine")
+            .with_entrypoint("/usr/local/bin/docker-entrypoint.sh")
+            .with_wait_for(WaitFor::message_on_stdout("Ready to accept connections"));
+        let image = RunnableImage::from((image, vec!["sleep".to_owned(), "60".to_owned()]))
+            .with_default_cmd();
+        let container = docker.run(image).await;
 
-        {
-            let docker = Http::new();
-            assert!(!network_exists(&client, "awesome-net-2").await);
+        let config = inspect(&docker.inner.bollard, container.id())
+            .await
+            .config
+            .unwrap();
 
-            // creating the first container creates the network
-            let _container1 = docker
-                .run(RunnableImage::from(hello_world.clone()).with_network("awesome-net-2"))
-                .await;
+        assert_eq!(
+            config.entrypoint,
+            Some(vec!["/usr/local/bin/docker-entrypoint.sh".to_owned()])
+        );
+        assert_eq!(config.cmd, Some(vec!["redis-server".to_owned()]));
+    }
 
-            // creating a 2nd container doesn't fail because check if the network exists already
-            let _container2 = docker
-                .run(RunnableImage::from(hello_world).with_network("awesome-net-2"))
-                .await;
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_set_capabilities() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let image = RunnableImage::from(image)
+            .with_cap_add(Capability::NetAdmin)
+            .with_cap_add(Capability::SysPtrace)
+            .with_cap_drop(Capability::Kill);
+        let container = docker.run(image).await;
 
-            assert!(network_exists(&client, "awesome-net-2").await);
-        }
+        let host_config = inspect(&docker.inner.bollard, container.id())
+            .await
+            .host_config
+            .unwrap();
 
-        // client has been dropped, should clean up networks
-        assert!(!network_exists(&client, "awesome-net-2").await)
+        assert_eq!(
+            host_config.cap_add,
+            Some(vec![
+                "CAP_NET_ADMIN".to_owned(),
+                "CAP_SYS_PTRACE".to_owned()
+            ])
+        );
+        assert_eq!(host_config.cap_drop, Some(vec!["CAP_KILL".to_owned()]));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_set_resources() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let image = RunnableImage::from(image).with_resources(Resources {
+            memory: Some(128 * 1024 * 1024),
+            memory_swap: Some(256 * 1024 * 1024),
+            nano_cpus: Some(500_000_000),
+            cpu_shares: Some(512),
+            pids_limit: Some(100),
+        });
+        let container = docker.run(image).await;
+
+        let host_config = inspect(&docker.inner.bollard, container.id())
+            .await
+            .host_config
+            .unwrap();
+
+        assert_eq!(host_config.memory, Some(128 * 1024 * 1024));
+        assert_eq!(host_config.memory_swap, Some(256 * 1024 * 1024));
+        assert_eq!(host_config.nano_cpus, Some(500_000_000));
+        assert_eq!(host_config.cpu_shares, Some(512));
+        assert_eq!(host_config.pids_limit, Some(100));
     }
 
     #[tokio::test(flavor = "multi_thread")]
@@ -482,4 +3136,172 @@ mod tests {
 
         assert_eq!(shm_size, 1_000_000);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_run_command_should_mount_dev_shm_tmpfs() {
+        let docker = Http::new();
+        let image = GenericImage::new("hello-world", "latest");
+        let image = RunnableImage::from(image).with_dev_shm_tmpfs(67_108_864);
+        let container = docker.run(image).await;
+
+        let container_details = inspect(&docker.inner.bollard, container.id()).await;
+        let tmpfs = container_details.host_config.unwrap().tmpfs.unwrap();
+
+        assert_eq!(
+            tmpfs.get("/dev/shm").map(String::as_str),
+            Some("size=67108864")
+        );
+    }
+
+    #[cfg(feature = "grpc")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_grpc_wait_should_block_until_service_is_serving() {
+        let docker = Http::new();
+        let image = GenericImage::new("grpc_health_server", "latest").with_wait_for(
+            WaitFor::grpc_service(50051, "greeter", Duration::from_secs(10)),
+        );
+        // the server reports NOT_SERVING for its first second
+        docker.run(image).await;
+    }
+
+    #[cfg(feature = "grpc")]
+    #[tokio::test(flavor = "multi_thread")]
+    #[should_panic(expected = "did not report gRPC service `unknown` as SERVING within 2s")]
+    async fn http_grpc_wait_should_give_up_on_unknown_service() {
+        let docker = Http::new();
+        let image = GenericImage::new("grpc_health_server", "latest").with_wait_for(
+            WaitFor::grpc_service(50051, "unknown", Duration::from_secs(2)),
+        );
+        docker.run(image).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_copy_from_container_should_return_files_and_directories() {
+        let docker = Http::new();
+        let image = GenericImage::new("simple_web_server", "latest")
+            .with_wait_for(WaitFor::message_on_stdout("server is ready"));
+        let container = docker.run(image).await;
+        container.copy_to("hello", "/tmp/greeting.txt").await;
+
+        let greeting = container
+            .copy_file_from_container("/tmp/greeting.txt")
+            .await
+            .unwrap();
+        let directory = container.copy_from_container("/tmp").await.unwrap();
+
+        let entries = ::tar::Archive::new(directory.as_slice())
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let path = entry.path().unwrap().into_owned();
+                (path, entry.header().entry_type())
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(greeting, b"hello");
+        // a directory entry followed by its contents
+        assert_eq!(
+            entries[0],
+            (PathBuf::from("tmp"), ::tar::EntryType::Directory)
+        );
+        assert!(entries.contains(&(PathBuf::from("tmp/greeting.txt"), ::tar::EntryType::Regular)));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_wait_should_block_until_endpoint_answers() {
+        let docker = Http::new();
+        // the server answers with the name it was started as
+        let image = GenericImage::new("simple_web_server", "latest")
+            .with_exposed_port(80)
+            .with_wait_for(WaitFor::http_body_contains(
+                80,
+                "/",
+                200,
+                "foo",
+                Duration::from_secs(10),
+            ));
+        docker.run(image).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_cleanup_should_remove_all_containers_and_networks() {
+        use bollard::container::InspectContainerOptions;
+
+        let docker = Http::new();
+        let image = GenericImage::new("simple_web_server", "latest")
+            .with_wait_for(WaitFor::message_on_stdout("server is ready"));
+        let first = docker
+            .run(RunnableImage::from(image.clone()).with_network("cleanup-network"))
+            .await;
+        let second = docker
+            .run(RunnableImage::from(image).with_network("cleanup-network"))
+            .await;
+
+        docker.cleanup().await;
+
+        for id in [first.id(), second.id()] {
+            let result = docker
+                .inner
+                .bollard
+                .inspect_container(id, None::<InspectContainerOptions>)
+                .await;
+            assert!(
+                matches!(
+                    result,
+                    Err(bollard::errors::Error::DockerResponseServerError {
+                        status_code: 404,
+                        ..
+                    })
+                ),
+                "container {id} should have been removed"
+            );
+        }
+        assert!(!network_exists(&docker.inner.bollard, "cleanup-network").await);
+    }
+
+    /// Connects to a daemon over TLS with the certificates of a directory named after the process.
+    struct FakeEnvTls;
+
+    impl FakeEnvTls {
+        fn cert_path() -> std::path::PathBuf {
+            std::env::temp_dir().join(format!("testcontainers-certs-{}", std::process::id()))
+        }
+    }
+
+    impl GetEnvValue for FakeEnvTls {
+        fn get_env_value(key: &str) -> Option<String> {
+            match key {
+                "DOCKER_HOST" => Some("tcp://127.0.0.1:2376".to_owned()),
+                "DOCKER_TLS_VERIFY" => Some("1".to_owned()),
+                "DOCKER_CERT_PATH" => Some(Self::cert_path().to_string_lossy().into_owned()),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn http_from_env_should_connect_over_tls_when_asked_to_verify() {
+        let cert_path = FakeEnvTls::cert_path();
+        assert!(matches!(
+            Http::from_env::<FakeEnvTls>(),
+            Err(ConnectionError::Connect { host, .. }) if host == "tcp://127.0.0.1:2376"
+        ));
+
+        std::fs::create_dir_all(&cert_path).unwrap();
+        std::fs::write(
+            cert_path.join("ca.pem"),
+            "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n",
+        )
+        .unwrap();
+        let docker = Http::from_env::<FakeEnvTls>();
+        std::fs::remove_dir_all(&cert_path).unwrap();
+
+        let docker = docker.unwrap();
+        assert_eq!(
+            docker.inner.docker_host.as_deref(),
+            Some("tcp://127.0.0.1:2376")
+        );
+        assert!(format!("{:?}", docker.inner.bollard).contains("HTTPS"));
+    }
 }
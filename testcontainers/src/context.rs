@@ -0,0 +1,94 @@
+use crate::{clients::Http, core::DockerAsync, ContainerAsync, Image, RunnableImage};
+use std::sync::Mutex;
+
+/// Owns a [`Http`] client and keeps track of all containers started through it.
+///
+/// Containers remove themselves when they are dropped, which blocks the current thread.
+/// A [`TestContext`] instead removes all of its containers when calling [`TestContext::shutdown`],
+/// which gives test suites a deterministic point at which their environment is torn down.
+///
+/// ```rust,no_run
+/// use testcontainers::{GenericImage, TestContext};
+///
+/// # async fn run() {
+/// let context = TestContext::default();
+///
+/// let first = context.run(GenericImage::new("redis", "7-alpine")).await;
+/// let second = context.run(GenericImage::new("redis", "7-alpine")).await;
+///
+/// // ... use the containers ...
+///
+/// context.shutdown().await;
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct TestContext {
+    client: Http,
+    container_ids: Mutex<Vec<String>>,
+}
+
+impl TestContext {
+    /// Returns the client used to start containers.
+    ///
+    /// Containers started directly through the client are not tracked by this context.
+    pub fn client(&self) -> &Http {
+        &self.client
+    }
+
+    /// Runs the given image and registers the container to be removed on [`TestContext::shutdown`].
+    pub async fn run<I: Image>(&self, image: impl Into<RunnableImage<I>>) -> ContainerAsync<I> {
+        let container = self.client.run(image).await;
+        self.container_ids
+            .lock()
+            .expect("failed to lock Mutex")
+            .push(container.id().to_owned());
+
+        container
+    }
+
+    /// Removes all containers started through this context.
+    ///
+    /// Handles of containers that are still around afterwards don't fail when they are dropped.
+    pub async fn shutdown(self) {
+        let container_ids = self
+            .container_ids
+            .into_inner()
+            .expect("failed to lock Mutex");
+
+        for id in container_ids {
+            log::debug!("Deleting docker container {}", id);
+            self.client.rm(&id).await;
+
+            #[cfg(feature = "watchdog")]
+            crate::watchdog::unregister(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{clients::http, GenericImage};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn shutdown_should_remove_all_containers() {
+        let context = TestContext::default();
+        let first = context
+            .run(GenericImage::new("hello-world", "latest"))
+            .await;
+        let second = context
+            .run(GenericImage::new("hello-world", "latest"))
+            .await;
+        let ids = vec![first.id().to_owned(), second.id().to_owned()];
+
+        context.shutdown().await;
+
+        let docker = http::connect();
+        for id in ids {
+            assert!(
+                docker.inspect_container(&id, None).await.is_err(),
+                "container {id} should have been removed"
+            );
+        }
+    }
+}
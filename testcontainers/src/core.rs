@@ -3,12 +3,21 @@ pub(crate) use container::Docker;
 pub(crate) use container_async::DockerAsync;
 
 pub use self::{
+    bridge::IpError,
     container::Container,
-    image::{ContainerState, ExecCommand, Host, Image, ImageArgs, Port, RunnableImage, WaitFor},
+    image::{
+        BindMode, Capability, ContainerState, ExecCommand, ExecSpec, Healthcheck, Host, Image,
+        ImageArgs, Port, Resources, RunnableImage, SelinuxRelabel, WaitFor, SOURCE_LABEL,
+    },
+    logs::{LogConsumer, LogPattern, LogSource, LoggingConsumer},
+    ports::PortMappingError,
 };
 
 #[cfg(feature = "experimental")]
-pub use self::container_async::ContainerAsync;
+pub use self::container_async::{
+    ChangeKind, ContainerAsync, CopyFromError, CopyFromOptions, CreatedContainer, ExecResult,
+    FilesystemChange, Timings,
+};
 
 mod container;
 #[cfg(feature = "experimental")]
@@ -16,5 +25,19 @@ mod container_async;
 pub mod env;
 mod image;
 
+pub(crate) mod bridge;
+#[cfg(feature = "grpc")]
+pub(crate) mod grpc;
+#[cfg(feature = "experimental")]
+pub(crate) mod gzip;
+pub(crate) mod health;
+pub(crate) mod http_probe;
+#[cfg(feature = "experimental")]
+pub(crate) mod lifecycle;
 pub(crate) mod logs;
 pub(crate) mod ports;
+#[cfg(feature = "experimental")]
+pub(crate) mod registry_auth;
+pub(crate) mod restarts;
+#[cfg(feature = "experimental")]
+pub(crate) mod tar;
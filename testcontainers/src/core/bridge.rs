@@ -0,0 +1,117 @@
+use bollard_stubs::models::ContainerInspectResponse;
+use std::{fmt, net::IpAddr, str::FromStr, time::Duration};
+
+/// How often the container is inspected until its bridge IP address is known.
+pub(crate) const BRIDGE_IP_ATTEMPTS: usize = 10;
+/// The pause between two attempts to look up the bridge IP address of a container.
+pub(crate) const BRIDGE_IP_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Defines error cases when looking up the bridge IP address of a container.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum IpError {
+    /// Docker did not report any network settings for the container.
+    NoNetworkSettings,
+    /// The container is not attached to its bridge network or has no IP address on it yet.
+    NotOnBridge,
+    /// Docker reported an IP address that could not be parsed.
+    InvalidIp(String),
+}
+
+impl IpError {
+    /// Whether the IP address might still show up when the container is inspected again.
+    pub(crate) fn is_transient(&self) -> bool {
+        !matches!(self, IpError::InvalidIp(_))
+    }
+}
+
+impl fmt::Display for IpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpError::NoNetworkSettings => write!(f, "container has no network settings"),
+            IpError::NotOnBridge => write!(f, "container has no IP address on its bridge network"),
+            IpError::InvalidIp(ip) => write!(f, "container has invalid bridge IP '{ip}'"),
+        }
+    }
+}
+
+impl std::error::Error for IpError {}
+
+/// Extracts the IP address of a container on the given network, or on its default bridge if the
+/// container was not started in a specific network.
+pub(crate) fn bridge_ip_address(
+    details: ContainerInspectResponse,
+    network: Option<String>,
+) -> Result<IpAddr, IpError> {
+    let settings = details.network_settings.ok_or(IpError::NoNetworkSettings)?;
+
+    let bridge_name = network.or(settings.bridge).ok_or(IpError::NotOnBridge)?;
+
+    let ip = settings
+        .networks
+        .and_then(|mut networks| networks.remove(&bridge_name))
+        .and_then(|network| network.ip_address)
+        .filter(|ip| !ip.is_empty())
+        .ok_or(IpError::NotOnBridge)?;
+
+    IpAddr::from_str(&ip).map_err(|_| IpError::InvalidIp(ip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard_stubs::models::{EndpointSettings, NetworkSettings};
+    use std::collections::HashMap;
+
+    fn details(ip: &str) -> ContainerInspectResponse {
+        ContainerInspectResponse {
+            network_settings: Some(NetworkSettings {
+                bridge: Some("bridge".to_owned()),
+                networks: Some(HashMap::from([(
+                    "bridge".to_owned(),
+                    EndpointSettings {
+                        ip_address: Some(ip.to_owned()),
+                        ..Default::default()
+                    },
+                )])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bridge_ip_address_should_be_parsed() {
+        let ip = bridge_ip_address(details("172.17.0.2"), None);
+
+        assert_eq!(ip, Ok("172.17.0.2".parse().unwrap()));
+    }
+
+    #[test]
+    fn missing_network_settings_should_be_reported() {
+        let ip = bridge_ip_address(ContainerInspectResponse::default(), None);
+
+        assert_eq!(ip, Err(IpError::NoNetworkSettings));
+    }
+
+    #[test]
+    fn container_on_other_network_should_not_be_on_bridge() {
+        let ip = bridge_ip_address(details("172.17.0.2"), Some("awesome-net".to_owned()));
+
+        assert_eq!(ip, Err(IpError::NotOnBridge));
+    }
+
+    #[test]
+    fn empty_ip_should_not_be_on_bridge_yet() {
+        let ip = bridge_ip_address(details(""), None);
+
+        assert_eq!(ip, Err(IpError::NotOnBridge));
+    }
+
+    #[test]
+    fn invalid_ip_should_be_reported() {
+        let ip = bridge_ip_address(details("not-an-ip"), None);
+
+        assert_eq!(ip, Err(IpError::InvalidIp("not-an-ip".to_owned())));
+        assert!(!ip.unwrap_err().is_transient());
+    }
+}
@@ -1,10 +1,16 @@
 use crate::{
-    core::{env::Command, logs::LogStream, ports::Ports, ExecCommand, WaitFor},
+    core::{
+        bridge::{bridge_ip_address, IpError, BRIDGE_IP_ATTEMPTS, BRIDGE_IP_RETRY_INTERVAL},
+        env::Command,
+        logs::LogStream,
+        ports::{PortMappingError, Ports},
+        ExecCommand, WaitFor,
+    },
     Image, RunnableImage,
 };
 use bollard_stubs::models::ContainerInspectResponse;
 
-use std::{fmt, net::IpAddr, str::FromStr};
+use std::{collections::BTreeMap, fmt, net::IpAddr, time::Duration};
 
 /// Represents a running docker container.
 ///
@@ -42,6 +48,10 @@ where
         f.debug_struct("Container")
             .field("id", &self.id)
             .field("image", &self.image)
+            .field(
+                "metadata",
+                &self.image.metadata().collect::<BTreeMap<_, _>>(),
+            )
             .field("command", &self.command)
             .finish()
     }
@@ -92,6 +102,11 @@ where
     pub fn ports(&self) -> Ports {
         self.ports.clone()
     }
+
+    #[cfg(feature = "presets")]
+    pub(crate) fn runnable_image(&self) -> &RunnableImage<I> {
+        &self.image
+    }
 }
 
 impl<I> Container<I>
@@ -103,6 +118,13 @@ where
         &self.id
     }
 
+    /// Returns the host the ports of this container are published on.
+    ///
+    /// This is the host of a `tcp://` Docker daemon and `127.0.0.1` for a local one.
+    pub fn get_host(&self) -> String {
+        self.docker_client.host()
+    }
+
     /// Returns the mapped host port for an internal port of this docker container, on the host's
     /// IPv4 interfaces.
     ///
@@ -156,42 +178,47 @@ where
     /// Testcontainers is designed to be used in tests only. If a certain port is not mapped, the container
     /// is unlikely to be useful.
     pub fn get_host_port_ipv6(&self, internal_port: u16) -> u16 {
-        self.ports
-            .map_to_host_port_ipv6(internal_port)
-            .unwrap_or_else(|| {
-                panic!(
-                    "container {} does not expose port {}",
-                    self.id, internal_port
-                )
-            })
+        self.try_get_host_port_ipv6(internal_port)
+            .unwrap_or_else(|e| panic!("container {} does not expose port: {e}", self.id))
+    }
+
+    /// Returns the mapped host port for an internal port of this docker container, on the host's
+    /// IPv6 interfaces.
+    ///
+    /// The returned error distinguishes a port that is not mapped from a Docker host without
+    /// IPv6 support.
+    pub fn try_get_host_port_ipv6(&self, internal_port: u16) -> Result<u16, PortMappingError> {
+        self.ports.try_map_to_host_port_ipv6(internal_port)
     }
 
     /// Returns the bridge ip address of docker container as specified in NetworkSettings.Networks.IPAddress
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the IP address is not known, see
+    /// [`Container::try_get_bridge_ip_address`].
     pub fn get_bridge_ip_address(&self) -> IpAddr {
-        let settings = self
-            .docker_client
-            .inspect(&self.id)
-            .network_settings
-            .unwrap_or_else(|| panic!("container {} has no network settings", self.id));
-
-        let mut networks = settings
-            .networks
-            .unwrap_or_else(|| panic!("container {} has no any networks", self.id));
-
-        let bridge_name = self
-            .image
-            .network()
-            .clone()
-            .or(settings.bridge)
-            .unwrap_or_else(|| panic!("container {} has missing bridge name", self.id));
-
-        let ip = networks
-            .remove(&bridge_name)
-            .and_then(|network| network.ip_address)
-            .unwrap_or_else(|| panic!("container {} has missing bridge IP", self.id));
-
-        IpAddr::from_str(&ip)
-            .unwrap_or_else(|_| panic!("container {} has invalid bridge IP", self.id))
+        self.try_get_bridge_ip_address()
+            .unwrap_or_else(|e| panic!("failed to get bridge IP of container {}: {e}", self.id))
+    }
+
+    /// Returns the bridge ip address of docker container as specified in NetworkSettings.Networks.IPAddress
+    ///
+    /// Right after the start of a container, Docker might not have assigned an IP address yet.
+    /// The container is therefore inspected again a few times before giving up.
+    pub fn try_get_bridge_ip_address(&self) -> Result<IpAddr, IpError> {
+        let mut attempt = 1;
+        loop {
+            let details = self.docker_client.inspect(&self.id);
+            match bridge_ip_address(details, self.image.network().clone()) {
+                Err(e) if e.is_transient() && attempt < BRIDGE_IP_ATTEMPTS => {
+                    log::debug!("Bridge IP of container {} not known yet: {e}", self.id);
+                    attempt += 1;
+                    std::thread::sleep(BRIDGE_IP_RETRY_INTERVAL);
+                }
+                result => return result,
+            }
+        }
     }
 
     pub fn exec(&self, cmd: ExecCommand) -> ExecOutput {
@@ -204,8 +231,11 @@ where
 
         let output = self.docker_client.exec(self.id(), cmd);
 
-        self.docker_client
-            .block_until_ready(self.id(), ready_conditions);
+        self.docker_client.block_until_ready(
+            self.id(),
+            ready_conditions,
+            self.image.poll_interval(),
+        );
 
         ExecOutput {
             stdout: output.stdout,
@@ -226,7 +256,11 @@ where
     pub fn rm(&self) {
         log::debug!("Deleting docker container {}", self.id);
 
-        self.docker_client.rm(&self.id)
+        if self.image.remove_volumes() {
+            self.docker_client.rm(&self.id)
+        } else {
+            self.docker_client.rm_keep_volumes(&self.id)
+        }
     }
 }
 
@@ -244,14 +278,14 @@ pub struct ExecOutput {
 ///
 /// Setting it to `keep` will stop container.
 /// Setting it to `remove` will remove it.
+/// Setting it to `keep-on-failure` will remove it unless the current thread is panicking.
 impl<I> Drop for Container<I>
 where
     I: Image,
 {
     fn drop(&mut self) {
-        match self.command {
-            Command::Keep => {}
-            Command::Remove => self.rm(),
+        if self.command.should_remove() {
+            self.rm()
         }
         #[cfg(feature = "watchdog")]
         crate::watchdog::unregister(self.id());
@@ -265,13 +299,20 @@ where
 pub(crate) trait Docker: Sync + Send {
     fn stdout_logs(&self, id: &str) -> LogStream;
     fn stderr_logs(&self, id: &str) -> LogStream;
+    /// Follows stdout and stderr of the container, interleaving their lines as they arrive.
+    fn combined_logs(&self, id: &str) -> LogStream;
+    /// The host on which the ports of containers are published.
+    fn host(&self) -> String;
     fn ports(&self, id: &str) -> Ports;
     fn inspect(&self, id: &str) -> ContainerInspectResponse;
+    /// Removes the container along with its anonymous volumes.
     fn rm(&self, id: &str);
+    /// Removes the container but keeps its anonymous volumes.
+    fn rm_keep_volumes(&self, id: &str);
     fn stop(&self, id: &str);
     fn start(&self, id: &str);
     fn exec(&self, id: &str, cmd: String) -> std::process::Output;
-    fn block_until_ready(&self, id: &str, ready_conditions: Vec<WaitFor>);
+    fn block_until_ready(&self, id: &str, ready_conditions: Vec<WaitFor>, poll_interval: Duration);
 }
 
 #[cfg(test)]
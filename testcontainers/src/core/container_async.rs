@@ -1,12 +1,55 @@
+#[cfg(feature = "grpc")]
+use crate::core::grpc;
 use crate::{
-    core::{env, env::Command, logs::LogStreamAsync, ports::Ports, WaitFor},
+    core::{
+        bridge::{bridge_ip_address, IpError, BRIDGE_IP_ATTEMPTS, BRIDGE_IP_RETRY_INTERVAL},
+        env,
+        env::Command,
+        gzip,
+        health::{self, HealthReport, REPORT_LOG_LINES},
+        http_probe::{self, HttpProbe},
+        lifecycle::Step,
+        logs::{LogMatch, LogRingBuffer, LogSource, LogStreamAsync, RawLogStreamAsync, WaitError},
+        ports::{PortMappingError, Ports},
+        restarts::{will_be_restarted, RestartTracker},
+        tar, ExecSpec, WaitFor,
+    },
     Image, RunnableImage,
 };
 use async_trait::async_trait;
-use bollard::models::{ContainerInspectResponse, HealthStatusEnum};
-use futures::executor::block_on;
-use std::{fmt, net::IpAddr, str::FromStr, time::Duration};
-use tokio::time::sleep;
+use bollard::models::{
+    ContainerInspectResponse, ContainerStateStatusEnum, HealthStatusEnum, HealthcheckResult,
+};
+use futures::{
+    executor::block_on,
+    future::{self, Either, FutureExt},
+    stream::{self, BoxStream, StreamExt},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    fs::Metadata,
+    io,
+    net::IpAddr,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{fs::File, io::AsyncReadExt, net::TcpStream, task::JoinHandle, time::sleep};
+
+/// How long a started container may take to be reported as running by the daemon.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Permission bits of files copied into a container, unless specified otherwise.
+const DEFAULT_FILE_MODE: u32 = 0o644;
+
+/// The size of the chunks in which files are streamed into a container, and in which archives
+/// copied out of a container are compressed.
+const COPY_CHUNK_SIZE: usize = 64 * 1024;
 
 /// Represents a running docker container that has been started using an async client..
 ///
@@ -33,9 +76,26 @@ use tokio::time::sleep;
 /// [drop_impl]: struct.ContainerAsync.html#impl-Drop
 pub struct ContainerAsync<I: Image> {
     id: String,
-    docker_client: Box<dyn DockerAsync>,
+    docker_client: Arc<dyn DockerAsync>,
     image: RunnableImage<I>,
     command: Command,
+    background_tasks: Vec<JoinHandle<()>>,
+    time_to_ready: Duration,
+    seen_log_lines: SeenLogLines,
+    log_buffer: Option<Arc<LogRingBuffer>>,
+    /// The most recent details of the container along with the time they were fetched.
+    inspect_cache: Mutex<Option<(Instant, ContainerInspectResponse)>>,
+}
+
+/// The number of lines of each log stream that ready conditions have looked at already.
+///
+/// Log streams always start at the beginning of the output, so a wait for a message skips these
+/// lines instead of matching a stale message that an earlier condition has seen.
+#[derive(Debug, Default)]
+struct SeenLogLines {
+    stdout: AtomicUsize,
+    stderr: AtomicUsize,
+    combined: AtomicUsize,
 }
 
 impl<I> ContainerAsync<I>
@@ -47,6 +107,18 @@ where
         &self.id
     }
 
+    /// Returns the host the ports of this container are published on.
+    ///
+    /// This is the host of a `tcp://` Docker daemon and `127.0.0.1` for a local one.
+    pub fn get_host(&self) -> String {
+        self.docker_client.host()
+    }
+
+    #[cfg(feature = "presets")]
+    pub(crate) fn runnable_image(&self) -> &RunnableImage<I> {
+        &self.image
+    }
+
     /// Returns the mapped host port for an internal port of this docker container, on the host's
     /// IPv4 interfaces.
     ///
@@ -78,8 +150,11 @@ where
     /// Testcontainers is designed to be used in tests only. If a certain port is not mapped, the container
     /// is unlikely to be useful.
     pub async fn get_host_port_ipv4(&self, internal_port: u16) -> u16 {
-        self.docker_client
-            .ports(&self.id)
+        if let Some(port) = self.explicitly_mapped_port(internal_port) {
+            return port;
+        }
+
+        self.cached_ports()
             .await
             .map_to_host_port_ipv4(internal_port)
             .unwrap_or_else(|| {
@@ -90,6 +165,53 @@ where
             })
     }
 
+    /// Returns the mapped host port for an internal port of this docker container, on the host's
+    /// IPv4 interfaces, waiting up to `timeout` for the port to be mapped.
+    ///
+    /// Right after a container was started, Docker may not report its port mappings yet. This
+    /// method inspects the container in the configured poll interval until the mapping shows up.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the given port is not mapped within `timeout`.
+    pub async fn get_host_port_ipv4_with_timeout(
+        &self,
+        internal_port: u16,
+        timeout: Duration,
+    ) -> u16 {
+        let started = Instant::now();
+
+        loop {
+            let ports = self.docker_client.ports(&self.id).await;
+            if let Some(port) = ports.map_to_host_port_ipv4(internal_port) {
+                return port;
+            }
+            if started.elapsed() >= timeout {
+                panic!(
+                    "container {} did not map port {} within {:?}",
+                    self.id, internal_port, timeout
+                )
+            }
+
+            sleep(self.image.poll_interval()).await;
+        }
+    }
+
+    /// Waits until an internal port of this docker container is mapped to a port on the host's
+    /// IPv4 interfaces and returns the host port.
+    ///
+    /// This is the awaitable counterpart of [`ContainerAsync::get_host_port_ipv4`], e.g. for
+    /// containers that were just restarted. See
+    /// [`ContainerAsync::get_host_port_ipv4_with_timeout`] to choose how long to wait.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the given port is not mapped within a minute.
+    pub async fn await_port_mapping(&self, internal_port: u16) -> u16 {
+        self.get_host_port_ipv4_with_timeout(internal_port, STARTUP_TIMEOUT)
+            .await
+    }
+
     /// Returns the mapped host port for an internal port of this docker container, on the host's
     /// IPv6 interfaces.
     ///
@@ -102,66 +224,532 @@ where
     /// Testcontainers is designed to be used in tests only. If a certain port is not mapped, the container
     /// is unlikely to be useful.
     pub async fn get_host_port_ipv6(&self, internal_port: u16) -> u16 {
-        self.docker_client
-            .ports(&self.id)
+        self.try_get_host_port_ipv6(internal_port)
             .await
-            .map_to_host_port_ipv6(internal_port)
-            .unwrap_or_else(|| {
-                panic!(
-                    "container {} does not expose port {}",
-                    self.id, internal_port
-                )
-            })
+            .unwrap_or_else(|e| panic!("container {} does not expose port: {e}", self.id))
+    }
+
+    /// Returns the mapped host port for an internal port of this docker container, on the host's
+    /// IPv6 interfaces.
+    ///
+    /// The returned error distinguishes a port that is not mapped from a Docker host without
+    /// IPv6 support.
+    pub async fn try_get_host_port_ipv6(
+        &self,
+        internal_port: u16,
+    ) -> Result<u16, PortMappingError> {
+        self.cached_ports()
+            .await
+            .try_map_to_host_port_ipv6(internal_port)
     }
 
     /// Returns the bridge ip address of docker container as specified in NetworkSettings.Networks.IPAddress
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the IP address is not known, see
+    /// [`ContainerAsync::try_get_bridge_ip_address`].
     pub async fn get_bridge_ip_address(&self) -> IpAddr {
-        let result = self.docker_client.inspect(&self.id).await;
+        self.try_get_bridge_ip_address()
+            .await
+            .unwrap_or_else(|e| panic!("failed to get bridge IP of container {}: {e}", self.id))
+    }
+
+    /// Returns the bridge ip address of docker container as specified in NetworkSettings.Networks.IPAddress
+    ///
+    /// Right after the start of a container, Docker might not have assigned an IP address yet.
+    /// The container is therefore inspected again a few times before giving up.
+    pub async fn try_get_bridge_ip_address(&self) -> Result<IpAddr, IpError> {
+        let mut attempt = 1;
+        loop {
+            let details = self.cached_inspect().await;
+            match bridge_ip_address(details, self.image.network().clone()) {
+                Err(e) if e.is_transient() && attempt < BRIDGE_IP_ATTEMPTS => {
+                    log::debug!("Bridge IP of container {} not known yet: {e}", self.id);
+                    self.invalidate_inspect_cache();
+                    attempt += 1;
+                    sleep(BRIDGE_IP_RETRY_INTERVAL).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns the names of the networks this container is connected to, sorted by name.
+    pub async fn networks(&self) -> Vec<String> {
+        let mut networks = self
+            .cached_inspect()
+            .await
+            .network_settings
+            .and_then(|settings| settings.networks)
+            .unwrap_or_default()
+            .into_keys()
+            .collect::<Vec<_>>();
+        networks.sort();
+
+        networks
+    }
+
+    /// Asserts that this container is connected to exactly the given networks.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the container is not connected to one of the expected networks or
+    /// connected to any other network. The panic message lists the differences.
+    pub async fn assert_networks(&self, expected: &[&str]) {
+        let actual = self.networks().await;
+        let missing = expected
+            .iter()
+            .filter(|network| !actual.iter().any(|actual| actual == *network))
+            .collect::<Vec<_>>();
+        let unexpected = actual
+            .iter()
+            .filter(|network| !expected.contains(&network.as_str()))
+            .collect::<Vec<_>>();
+
+        if !missing.is_empty() || !unexpected.is_empty() {
+            panic!(
+                "container {} is not connected to the expected networks\nmissing: {missing:?}\nunexpected: {unexpected:?}",
+                self.id
+            )
+        }
+    }
+
+    /// Asserts that this container keeps running for at least the given duration.
+    ///
+    /// Useful to catch containers that pass their ready conditions but crash shortly afterwards.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the container exits before `duration` has elapsed. The panic message
+    /// contains the exit code as well as the logs of the container.
+    pub async fn ensure_stable_for(&self, duration: Duration) {
+        let exit_code =
+            match tokio::time::timeout(duration, self.docker_client.wait(&self.id)).await {
+                Ok(exit_code) => exit_code,
+                Err(_) => return,
+            };
+
+        panic!(
+            "container {} exited with code {exit_code} within {duration:?}\n{}",
+            self.id,
+            self.exited_container_logs().await
+        )
+    }
+
+    /// Waits until this container is running.
+    ///
+    /// Containers that already ran to completion with an exit code of `0` are considered to have
+    /// been running. Containers that exited but are restarted by their restart policy are waited
+    /// for.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the container exited with a non-zero exit code, e.g. because of an
+    /// invalid entrypoint, or if it is not running after `timeout` has elapsed. The panic message
+    /// contains the logs of an exited container.
+    pub async fn wait_for_running(&self, timeout: Duration) {
+        let wait_for_running = async {
+            loop {
+                let details = self.docker_client.inspect(&self.id).await;
+                let state = details
+                    .state
+                    .clone()
+                    .unwrap_or_else(|| panic!("Container state not available"));
+
+                if state.running == Some(true) {
+                    return;
+                }
+
+                match state.status {
+                    Some(ContainerStateStatusEnum::EXITED)
+                    | Some(ContainerStateStatusEnum::DEAD) => {
+                        let exit_code = state.exit_code.unwrap_or_default();
+                        if exit_code == 0 {
+                            return;
+                        }
+                        if will_be_restarted(&details) {
+                            sleep(self.image.poll_interval()).await;
+                            continue;
+                        }
+
+                        panic!(
+                            "container {} exited with code {exit_code} before it was running\n{}",
+                            self.id,
+                            self.exited_container_logs().await
+                        )
+                    }
+                    _ => sleep(self.image.poll_interval()).await,
+                }
+            }
+        };
+
+        if tokio::time::timeout(timeout, wait_for_running)
+            .await
+            .is_err()
+        {
+            panic!("container {} is not running after {timeout:?}", self.id)
+        }
+    }
+
+    /// Collects the complete output of an exited container for diagnostic messages.
+    async fn exited_container_logs(&self) -> String {
+        let stdout = self
+            .docker_client
+            .stdout_logs(&self.id)
+            .read_to_string()
+            .await
+            .unwrap_or_default();
+        let stderr = self
+            .docker_client
+            .stderr_logs(&self.id)
+            .read_to_string()
+            .await
+            .unwrap_or_default();
+
+        format!("Container stdout: {stdout}\nContainer stderr: {stderr}")
+    }
+
+    /// Returns the raw details of this container as reported by the Docker daemon.
+    ///
+    /// Meant as an escape hatch for assertions that aren't covered by more specific methods.
+    pub async fn inspect(&self) -> ContainerInspectResponse {
+        self.docker_client.inspect(&self.id).await
+    }
+
+    /// Exports the host ports of this container as environment variables of the current process,
+    /// so that child processes configured through their environment can reach the container.
+    ///
+    /// Every port mapped on the host's IPv4 interfaces is exported as `{prefix}_{port}_TCP`, e.g.
+    /// `PG_5432_TCP=49153` for a `prefix` of `PG`.
+    pub async fn export_ports_to_env(&self, prefix: &str) {
+        for (internal_port, host_port) in self.cached_ports().await.ipv4_mappings() {
+            std::env::set_var(
+                format!("{prefix}_{internal_port}_TCP"),
+                host_port.to_string(),
+            );
+        }
+    }
+
+    /// Returns the environment variables of this container as reported by the Docker daemon.
+    ///
+    /// This is the final environment after the variables of the image and all overrides have been
+    /// merged.
+    pub async fn env(&self) -> HashMap<String, String> {
+        self.cached_inspect()
+            .await
+            .config
+            .and_then(|config| config.env)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|variable| variable.split_once('='))
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    /// Returns timestamps of the startup of this container, e.g. to track startup regressions.
+    pub async fn timings(&self) -> Timings {
+        let details = self.docker_client.inspect(&self.id).await;
+
+        Timings {
+            created: details.created,
+            started_at: details.state.and_then(|state| state.started_at),
+            time_to_ready: self.time_to_ready,
+        }
+    }
+
+    /// Returns the results of the most recent healthchecks of this container, oldest first.
+    ///
+    /// Useful to find out why a container doesn't become healthy. The list is empty if the
+    /// container has no healthcheck configured or it hasn't run yet.
+    pub async fn health_log(&self) -> Vec<HealthcheckResult> {
+        health::health_log(self.docker_client.inspect(&self.id).await)
+    }
+
+    /// Returns the number of lines the container has written to stdout and stderr so far.
+    ///
+    /// A trailing line without a line break is counted as well.
+    pub async fn log_line_count(&self) -> usize {
+        self.current_logs().await.lines().count()
+    }
 
-        let settings = result
+    /// Returns the number of bytes the container has written to stdout and stderr so far.
+    pub async fn log_byte_count(&self) -> usize {
+        self.log_bytes().await.len()
+    }
+
+    /// Returns everything the container has written to stdout and stderr so far, as raw bytes.
+    ///
+    /// Unlike the text based accessors, this keeps output that is not valid UTF-8 as it is.
+    pub async fn log_bytes(&self) -> Vec<u8> {
+        self.docker_client
+            .current_raw_logs(&self.id)
+            .read_to_end()
+            .await
+            .unwrap_or_else(|e| panic!("failed to read logs of container {}: {e}", self.id))
+    }
+
+    /// Returns the most recent lines the container has written to stdout and stderr, oldest first.
+    ///
+    /// The lines are captured in the background while the container is running, see
+    /// [`RunnableImage::with_log_buffer`]. Returns an empty string if no log buffer is configured.
+    pub fn recent_logs(&self) -> String {
+        self.log_buffer
+            .as_ref()
+            .map(|buffer| buffer.contents())
+            .unwrap_or_default()
+    }
+
+    /// Returns the details of this container, from the cache if they are recent enough.
+    ///
+    /// See [`RunnableImage::with_inspect_cache`].
+    async fn cached_inspect(&self) -> ContainerInspectResponse {
+        let ttl = self.image.inspect_cache_ttl();
+        if let Some((fetched, details)) = &*self.inspect_cache.lock().unwrap() {
+            if fetched.elapsed() < ttl {
+                return details.clone();
+            }
+        }
+
+        let details = self.docker_client.inspect(&self.id).await;
+        if !ttl.is_zero() {
+            *self.inspect_cache.lock().unwrap() = Some((Instant::now(), details.clone()));
+        }
+
+        details
+    }
+
+    /// Returns the port mappings of this container, from the cache if they are recent enough.
+    async fn cached_ports(&self) -> Ports {
+        if self.image.inspect_cache_ttl().is_zero() {
+            return self.docker_client.ports(&self.id).await;
+        }
+
+        self.cached_inspect()
+            .await
             .network_settings
-            .unwrap_or_else(|| panic!("container {} has no network settings", self.id));
+            .and_then(|settings| settings.ports)
+            .map(Ports::from)
+            .unwrap_or_default()
+    }
+
+    /// Returns the host port of a port mapped with [`RunnableImage::with_mapped_port`], which is
+    /// known without inspecting the container.
+    fn explicitly_mapped_port(&self, internal_port: u16) -> Option<u16> {
+        if !self.image.publish_ports() {
+            return None;
+        }
+
+        self.image
+            .ports()
+            .as_ref()?
+            .iter()
+            // a local port of 0 lets Docker pick the host port
+            .find(|port| port.internal == internal_port && port.local != 0)
+            .map(|port| port.local)
+    }
+
+    fn invalidate_inspect_cache(&self) {
+        *self.inspect_cache.lock().unwrap() = None;
+    }
+
+    async fn current_logs(&self) -> String {
+        self.docker_client
+            .current_logs(&self.id)
+            .read_to_string()
+            .await
+            .unwrap_or_else(|e| panic!("failed to read logs of container {}: {e}", self.id))
+    }
+
+    /// Collects diagnostics of a container that didn't become healthy or ready.
+    async fn health_report(&self, reason: String) -> String {
+        let stdout = self
+            .docker_client
+            .recent_logs(&self.id, LogSource::StdOut, REPORT_LOG_LINES)
+            .read_to_string()
+            .await
+            .unwrap_or_default();
+        let stderr = self
+            .docker_client
+            .recent_logs(&self.id, LogSource::StdErr, REPORT_LOG_LINES)
+            .read_to_string()
+            .await
+            .unwrap_or_default();
+
+        HealthReport {
+            container_id: &self.id,
+            reason,
+            stdout,
+            stderr,
+            health_log: self.health_log().await,
+        }
+        .render()
+    }
+
+    /// Copies `bytes` into a file at the absolute path `target` inside the container.
+    ///
+    /// The file is readable by everyone and writable by its owner, see
+    /// [`ContainerAsync::copy_to_with_mode`] to copy executables.
+    pub async fn copy_to(&self, bytes: impl AsRef<[u8]>, target: &str) {
+        self.copy_to_with_mode(bytes, target, DEFAULT_FILE_MODE)
+            .await
+    }
+
+    /// Copies `bytes` into a file with the given permission bits, e.g. `0o755`, at the absolute
+    /// path `target` inside the container.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `target` is not an absolute path to a file or if its parent
+    /// directory does not exist inside the container.
+    pub async fn copy_to_with_mode(&self, bytes: impl AsRef<[u8]>, target: &str, mode: u32) {
+        copy_bytes_to(&*self.docker_client, &self.id, bytes.as_ref(), target, mode).await
+    }
+
+    /// Copies the file at `src` on the host into a file at the absolute path `target` inside the
+    /// container.
+    ///
+    /// Unlike [`ContainerAsync::copy_to`], the file is streamed into the container without
+    /// loading it into memory, which makes this suitable for large files. On unix, the
+    /// permission bits of `src` are preserved.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `src` cannot be read, if `target` is not an absolute path to a file
+    /// or if its parent directory does not exist inside the container.
+    pub async fn copy_file_to(&self, src: &Path, target: &str) {
+        let (directory, file_name) = split_target(target);
+
+        let file = File::open(src)
+            .await
+            .unwrap_or_else(|e| panic!("failed to open `{}`: {e}", src.display()));
+        let metadata = file
+            .metadata()
+            .await
+            .unwrap_or_else(|e| panic!("failed to read metadata of `{}`: {e}", src.display()));
+        let size = metadata.len();
+
+        let header = tar::header(file_name, size, file_mode(&metadata));
+        let contents = stream::try_unfold((file, size), |(mut file, remaining)| async move {
+            if remaining == 0 {
+                return Ok(None);
+            }
+
+            let mut chunk = vec![0; COPY_CHUNK_SIZE.min(remaining as usize)];
+            let read = file.read(&mut chunk).await?;
+            if read == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "file was truncated while being copied",
+                ));
+            }
+            chunk.truncate(read);
+
+            Ok(Some((chunk, (file, remaining - read as u64))))
+        });
+        let archive = stream::once(future::ready(Ok(header.to_vec())))
+            .chain(contents)
+            .chain(stream::once(future::ready(Ok(tar::trailer(size)))))
+            .boxed();
+
+        self.docker_client
+            .copy_stream_to(&self.id, archive, directory)
+            .await
+    }
 
-        let mut networks = settings
-            .networks
-            .unwrap_or_else(|| panic!("container {} has no any networks", self.id));
+    /// Copies the file or directory at the absolute path `source` out of the container and returns
+    /// it as a tar archive, e.g. to assert on files the container wrote.
+    ///
+    /// The archive contains a single entry named after the last component of `source`, followed
+    /// by the contents of the directory if `source` is one. See
+    /// [`ContainerAsync::copy_file_from_container`] to get the contents of a single file.
+    pub async fn copy_from_container(&self, source: &str) -> Result<Vec<u8>, CopyFromError> {
+        self.copy_from_container_with(source, CopyFromOptions::default())
+            .await
+    }
+
+    /// Like [`ContainerAsync::copy_from_container`], but configured by `options`, e.g. to
+    /// compress large archives while they are copied.
+    pub async fn copy_from_container_with(
+        &self,
+        source: &str,
+        options: CopyFromOptions,
+    ) -> Result<Vec<u8>, CopyFromError> {
+        self.docker_client
+            .copy_from(&self.id, source, options)
+            .await
+    }
+
+    /// Returns the contents of the file at the absolute path `source` inside the container.
+    pub async fn copy_file_from_container(&self, source: &str) -> Result<Vec<u8>, CopyFromError> {
+        let archive = self.copy_from_container(source).await?;
 
-        let bridge_name = self
-            .image
-            .network()
-            .clone()
-            .or(settings.bridge)
-            .unwrap_or_else(|| panic!("container {} has missing bridge name", self.id));
+        tar::first_file(&archive).ok_or_else(|| CopyFromError::NotAFile(source.to_owned()))
+    }
+
+    /// Returns the changes to the filesystem of this container, compared to its image.
+    pub async fn changes(&self) -> Vec<FilesystemChange> {
+        self.docker_client.changes(&self.id).await
+    }
 
-        let ip = networks
-            .remove(&bridge_name)
-            .and_then(|network| network.ip_address)
-            .unwrap_or_else(|| panic!("container {} has missing bridge IP", self.id));
+    /// Runs `cmd` inside the running container, e.g. `psql -c 'CREATE DATABASE test'`, waits
+    /// until it finishes and returns its output along with its exit code.
+    ///
+    /// The command is not run in a shell, so `cmd` is the program followed by its arguments.
+    pub async fn exec(&self, cmd: Vec<String>) -> ExecResult {
+        log::debug!("Executing command {cmd:?} in container {}", self.id);
 
-        IpAddr::from_str(&ip)
-            .unwrap_or_else(|_| panic!("container {} has invalid bridge IP", self.id))
+        let spec = ExecSpec {
+            cmd,
+            ..Default::default()
+        };
+        self.docker_client.exec(&self.id, spec).await
     }
 
     pub async fn start(&self) {
+        self.invalidate_inspect_cache();
         self.docker_client.start(&self.id).await
     }
 
     pub async fn stop(&self) {
         log::debug!("Stopping docker container {}", self.id);
 
+        self.invalidate_inspect_cache();
         self.docker_client.stop(&self.id).await
     }
 
+    pub async fn restart(&self) {
+        log::debug!("Restarting docker container {}", self.id);
+
+        self.invalidate_inspect_cache();
+        self.docker_client.restart(&self.id).await
+    }
+
+    /// Restarts this container and waits until it satisfies the ready conditions of its image
+    /// again.
+    pub async fn restart_and_wait(&self) {
+        self.restart().await;
+        self.block_until_ready().await;
+    }
+
     pub async fn rm(self) {
         log::debug!("Deleting docker container {}", self.id);
 
-        self.docker_client.rm(&self.id).await
+        self.remove().await
+    }
+
+    async fn remove(&self) {
+        let step = Step::new("remove", &self.image.descriptor(), Some(&self.id));
+        if self.image.remove_volumes() {
+            step.run(self.docker_client.rm(&self.id)).await
+        } else {
+            step.run(self.docker_client.rm_keep_volumes(&self.id)).await
+        }
     }
 
     async fn drop_async(&self) {
-        match self.command {
-            env::Command::Remove => self.docker_client.rm(&self.id).await,
-            env::Command::Keep => {}
+        if self.command.should_remove() {
+            self.remove().await
         }
         #[cfg(feature = "watchdog")]
         crate::watchdog::unregister(self.id());
@@ -176,69 +764,488 @@ where
         f.debug_struct("ContainerAsync")
             .field("id", &self.id)
             .field("image", &self.image)
+            .field(
+                "metadata",
+                &self.image.metadata().collect::<BTreeMap<_, _>>(),
+            )
             .finish()
     }
 }
 
-/// Represents Docker operations as an async trait.
-///
-/// This trait is `pub(crate)` to make sure we can make changes to this API without breaking clients.
-/// Users should interact through the [`ContainerAsync`] API.
-#[async_trait]
-pub(crate) trait DockerAsync
-where
-    Self: Sync + Send,
-{
-    fn stdout_logs(&self, id: &str) -> LogStreamAsync<'_>;
-    fn stderr_logs(&self, id: &str) -> LogStreamAsync<'_>;
-    async fn ports(&self, id: &str) -> Ports;
-    async fn inspect(&self, id: &str) -> ContainerInspectResponse;
-    async fn rm(&self, id: &str);
-    async fn stop(&self, id: &str);
-    async fn start(&self, id: &str);
+/// A container that has been created but not started yet, passed to the hook registered with
+/// [`RunnableImage::with_pre_start_hook`].
+pub struct CreatedContainer {
+    id: String,
+    docker_client: Box<dyn DockerAsync>,
 }
 
-impl<I> ContainerAsync<I>
-where
-    I: Image,
-{
-    /// Constructs a new container given an id, a docker client and the image.
-    /// ContainerAsync::new().await
-    pub(crate) async fn new(
-        id: String,
-        docker_client: impl DockerAsync + 'static,
-        image: RunnableImage<I>,
-        command: env::Command,
-    ) -> ContainerAsync<I> {
-        let container = ContainerAsync {
+impl fmt::Debug for CreatedContainer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreatedContainer")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl CreatedContainer {
+    pub(crate) fn new(id: String, docker_client: impl DockerAsync + 'static) -> Self {
+        Self {
             id,
             docker_client: Box::new(docker_client),
-            image,
-            command,
-        };
-
-        container.block_until_ready().await;
+        }
+    }
 
-        container
+    /// Returns the id of the container.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Copies `bytes` into a file at the absolute path `target` inside the container, see
+    /// [`ContainerAsync::copy_to`].
+    pub async fn copy_to(&self, bytes: impl AsRef<[u8]>, target: &str) {
+        self.copy_to_with_mode(bytes, target, DEFAULT_FILE_MODE)
+            .await
+    }
+
+    /// Copies `bytes` into a file with the given permission bits at the absolute path `target`
+    /// inside the container, see [`ContainerAsync::copy_to_with_mode`].
+    pub async fn copy_to_with_mode(&self, bytes: impl AsRef<[u8]>, target: &str, mode: u32) {
+        copy_bytes_to(&*self.docker_client, &self.id, bytes.as_ref(), target, mode).await
+    }
+}
+
+/// Timestamps of the startup of a container, see [`ContainerAsync::timings`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Timings {
+    /// When the container was created, as reported by the Docker daemon in RFC 3339 format.
+    pub created: Option<String>,
+    /// When the container was last started, as reported by the Docker daemon in RFC 3339 format.
+    pub started_at: Option<String>,
+    /// How long the container took to satisfy its ready conditions after it was started.
+    pub time_to_ready: Duration,
+}
+
+/// The outcome of a command run inside a container, see [`ContainerAsync::exec`].
+///
+/// `stdout` and `stderr` are bytes since the output of the command may not be valid UTF-8.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExecResult {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub exit_code: i64,
+}
+
+/// A change to the filesystem of a container, see [`ContainerAsync::changes`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FilesystemChange {
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+/// Options for copying files out of a container, see
+/// [`ContainerAsync::copy_from_container_with`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct CopyFromOptions {
+    chunk_size: usize,
+    gzip: bool,
+}
+
+impl Default for CopyFromOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: COPY_CHUNK_SIZE,
+            gzip: false,
+        }
+    }
+}
+
+impl CopyFromOptions {
+    /// Compresses the archive with gzip while it is copied, so that only the compressed archive
+    /// is kept in memory. The returned bytes are then a `.tar.gz` archive.
+    pub fn with_gzip(self) -> Self {
+        Self { gzip: true, ..self }
+    }
+
+    /// Sets how many bytes of the archive are buffered and compressed at once, 64 KiB by default.
+    /// Larger chunks compress better but take more memory. Only applies together with
+    /// [`CopyFromOptions::with_gzip`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `bytes` is zero.
+    pub fn with_chunk_size(self, bytes: usize) -> Self {
+        assert!(bytes > 0, "the chunk size of a copy must not be zero");
+
+        Self {
+            chunk_size: bytes,
+            ..self
+        }
+    }
+
+    /// Returns a writer to collect the archive in, compressing it if configured.
+    pub(crate) fn archive(&self) -> ArchiveWriter {
+        if self.gzip {
+            ArchiveWriter::Gzip(gzip::Encoder::new(self.chunk_size))
+        } else {
+            ArchiveWriter::Plain(Vec::new())
+        }
+    }
+}
+
+/// Collects an archive copied out of a container as configured by [`CopyFromOptions`].
+pub(crate) enum ArchiveWriter {
+    Plain(Vec<u8>),
+    Gzip(gzip::Encoder),
+}
+
+impl ArchiveWriter {
+    pub(crate) fn write(&mut self, chunk: &[u8]) {
+        match self {
+            ArchiveWriter::Plain(archive) => archive.extend_from_slice(chunk),
+            ArchiveWriter::Gzip(encoder) => encoder.write(chunk),
+        }
+    }
+
+    pub(crate) fn finish(self) -> Vec<u8> {
+        match self {
+            ArchiveWriter::Plain(archive) => archive,
+            ArchiveWriter::Gzip(encoder) => encoder.finish(),
+        }
+    }
+}
+
+/// Defines error cases when copying files out of a container, see
+/// [`ContainerAsync::copy_from_container`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CopyFromError {
+    /// Nothing exists at the given path inside the container.
+    NotFound(String),
+    /// The given path inside the container is not a regular file.
+    NotAFile(String),
+    /// The Docker daemon failed to copy the given path.
+    Docker { path: String, message: String },
+}
+
+impl fmt::Display for CopyFromError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CopyFromError::NotFound(path) => write!(f, "`{path}` does not exist in container"),
+            CopyFromError::NotAFile(path) => write!(f, "`{path}` is not a file"),
+            CopyFromError::Docker { path, message } => {
+                write!(f, "failed to copy `{path}` out of container: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CopyFromError {}
+
+/// The kind of a [`FilesystemChange`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ChangeKind {
+    Modified,
+    Added,
+    Deleted,
+}
+
+/// Represents Docker operations as an async trait.
+///
+/// This trait is `pub(crate)` to make sure we can make changes to this API without breaking clients.
+/// Users should interact through the [`ContainerAsync`] API.
+#[async_trait]
+pub(crate) trait DockerAsync
+where
+    Self: Sync + Send,
+{
+    fn stdout_logs(&self, id: &str) -> LogStreamAsync<'static>;
+    fn stderr_logs(&self, id: &str) -> LogStreamAsync<'static>;
+    /// Follows stdout and stderr of the container in the order the lines were written.
+    fn combined_logs(&self, id: &str) -> LogStreamAsync<'static>;
+    /// Returns the last `lines` lines the container has written to `source` so far.
+    fn recent_logs(&self, id: &str, source: LogSource, lines: usize) -> LogStreamAsync<'static>;
+    /// Returns everything the container has written to stdout and stderr so far, without following.
+    fn current_logs(&self, id: &str) -> LogStreamAsync<'static>;
+    /// Like [`DockerAsync::current_logs`], but without decoding the output as UTF-8.
+    fn current_raw_logs(&self, id: &str) -> RawLogStreamAsync<'static>;
+    /// The host on which the ports of containers are published.
+    fn host(&self) -> String;
+    async fn ports(&self, id: &str) -> Ports;
+    async fn inspect(&self, id: &str) -> ContainerInspectResponse;
+    /// Removes the container along with its anonymous volumes.
+    async fn rm(&self, id: &str);
+    /// Removes the container but keeps its anonymous volumes.
+    async fn rm_keep_volumes(&self, id: &str);
+    async fn stop(&self, id: &str);
+    async fn start(&self, id: &str);
+    /// Creates and starts a new container with the configuration of the container `id` and
+    /// returns the id of the new container.
+    async fn recreate(&self, id: &str) -> String;
+    async fn restart(&self, id: &str);
+    /// Waits until the container exits and returns its exit code.
+    async fn wait(&self, id: &str) -> i64;
+    async fn changes(&self, id: &str) -> Vec<FilesystemChange>;
+    /// Runs a command inside the container and waits until it finishes.
+    async fn exec(&self, id: &str, spec: ExecSpec) -> ExecResult;
+    /// Extracts the tar archive `tar_bytes` into the directory `dest_dir` of the container.
+    async fn copy_to(&self, id: &str, tar_bytes: Vec<u8>, dest_dir: &str);
+    /// Extracts the tar archive streamed from `tar` into the directory `dest_dir` of the container.
+    async fn copy_stream_to(
+        &self,
+        id: &str,
+        tar: BoxStream<'static, Result<Vec<u8>, io::Error>>,
+        dest_dir: &str,
+    );
+    /// Returns a tar archive of the file or directory at `path` inside the container, collected
+    /// as configured by `options`.
+    async fn copy_from(
+        &self,
+        id: &str,
+        path: &str,
+        options: CopyFromOptions,
+    ) -> Result<Vec<u8>, CopyFromError>;
+}
+
+impl<I> ContainerAsync<I>
+where
+    I: Image,
+{
+    /// Constructs a new container given an id, a docker client and the image.
+    /// ContainerAsync::new().await
+    pub(crate) async fn new(
+        id: String,
+        docker_client: impl DockerAsync + 'static,
+        image: RunnableImage<I>,
+        command: env::Command,
+    ) -> ContainerAsync<I> {
+        let mut container = ContainerAsync {
+            id,
+            docker_client: Arc::new(docker_client),
+            image,
+            command,
+            background_tasks: Vec::new(),
+            time_to_ready: Duration::ZERO,
+            seen_log_lines: SeenLogLines::default(),
+            log_buffer: None,
+            inspect_cache: Mutex::new(None),
+        };
+
+        let mut retries = container.image.startup_retries();
+        loop {
+            container.attach_log_consumers();
+            if let Some(lines) = container.image.log_buffer() {
+                container.start_log_buffer(lines);
+            }
+
+            let started = Instant::now();
+            let ready = Step::new("ready", &container.image.descriptor(), Some(&container.id))
+                .run(AssertUnwindSafe(container.block_until_ready()).catch_unwind())
+                .await;
+            container.time_to_ready = started.elapsed();
+
+            match ready {
+                Ok(()) => break,
+                Err(_) if retries > 0 => {
+                    retries -= 1;
+                    log::warn!(
+                        "Container {} failed to become ready, recreating it ({retries} retries left)",
+                        container.id
+                    );
+                    container.recreate().await;
+                }
+                Err(panic) => panic::resume_unwind(panic),
+            }
+        }
+
+        if let Some(max_restarts) = container.image.autoheal() {
+            container.start_autoheal(max_restarts);
+        }
+
+        container
+    }
+
+    /// Replaces the container by a fresh one with the same configuration, see
+    /// [`RunnableImage::with_startup_retries`].
+    async fn recreate(&mut self) {
+        for task in self.background_tasks.drain(..) {
+            task.abort();
+        }
+        self.log_buffer = None;
+        self.seen_log_lines = SeenLogLines::default();
+        self.invalidate_inspect_cache();
+
+        self.docker_client.rm(&self.id).await;
+        #[cfg(feature = "watchdog")]
+        crate::watchdog::unregister(&self.id);
+        self.id = self.docker_client.recreate(&self.id).await;
+    }
+
+    /// Restarts the container in the background whenever its healthcheck reports it as
+    /// unhealthy, at most `max_restarts` times.
+    fn start_autoheal(&mut self, max_restarts: u32) {
+        let id = self.id.clone();
+        let docker_client = self.docker_client.clone();
+        let poll_interval = self.image.poll_interval();
+
+        self.background_tasks.push(tokio::spawn(async move {
+            let mut restarts = 0;
+
+            while restarts < max_restarts {
+                let health_status = docker_client
+                    .inspect(&id)
+                    .await
+                    .state
+                    .and_then(|state| state.health)
+                    .and_then(|health| health.status);
+
+                if health_status == Some(HealthStatusEnum::UNHEALTHY) {
+                    restarts += 1;
+                    log::info!(
+                        "Restarting unhealthy container {id} ({restarts} of {max_restarts} restarts)"
+                    );
+                    docker_client.restart(&id).await;
+                }
+
+                sleep(poll_interval).await;
+            }
+
+            log::warn!("Container {id} reached the maximum of {max_restarts} autoheal restarts");
+        }));
+    }
+
+    fn attach_log_consumers(&mut self) {
+        for consumer in self.image.log_consumers() {
+            let streams = [
+                (LogSource::StdOut, self.docker_client.stdout_logs(&self.id)),
+                (LogSource::StdErr, self.docker_client.stderr_logs(&self.id)),
+            ];
+
+            for (source, stream) in streams {
+                let id = self.id.clone();
+                let consumer = consumer.clone();
+
+                self.background_tasks.push(tokio::spawn(async move {
+                    if let Err(e) = stream.consume_lines(&id, source, consumer.as_ref()).await {
+                        log::warn!("Failed to consume {source} of container {id}: {e}");
+                    }
+                }));
+            }
+        }
+    }
+
+    /// Tails the combined output of the container into a buffer of the most recent `lines` lines.
+    fn start_log_buffer(&mut self, lines: usize) {
+        let id = self.id.clone();
+        let stream = self.docker_client.combined_logs(&self.id);
+        let buffer = Arc::new(LogRingBuffer::new(lines));
+        self.log_buffer = Some(buffer.clone());
+
+        self.background_tasks.push(tokio::spawn(async move {
+            if let Err(e) = stream.tail_into(&buffer).await {
+                log::warn!("Failed to buffer the output of container {id}: {e}");
+            }
+        }));
+    }
+
+    /// Makes the configured users the owners of their bind mounts, see
+    /// [`RunnableImage::with_owned_bind_mount`].
+    async fn chown_mounts(&self) {
+        for (path, user) in self.image.mount_owners() {
+            let spec = ExecSpec {
+                cmd: vec![
+                    "chown".to_owned(),
+                    "-R".to_owned(),
+                    user.clone(),
+                    path.clone(),
+                ],
+                user: Some("0".to_owned()),
+                working_dir: None,
+            };
+            let result = self.docker_client.exec(&self.id, spec).await;
+            if result.exit_code != 0 {
+                panic!(
+                    "failed to make {user} the owner of {path} in container {}: {}",
+                    self.id,
+                    String::from_utf8_lossy(&result.stderr).trim_end()
+                )
+            }
+        }
     }
 
     async fn block_until_ready(&self) {
         log::debug!("Waiting for container {} to be ready", self.id);
 
+        self.wait_for_running(STARTUP_TIMEOUT).await;
+        self.chown_mounts().await;
+
         for condition in self.image.ready_conditions() {
             match condition {
-                WaitFor::StdOutMessage { message } => self
-                    .docker_client
-                    .stdout_logs(&self.id)
-                    .wait_for_message(&message)
+                WaitFor::StdOutMessage { message } => {
+                    let logs = self.docker_client.stdout_logs(&self.id);
+                    self.wait_for_new_message(
+                        logs,
+                        LogMatch::Message(&message),
+                        &self.seen_log_lines.stdout,
+                    )
+                    .await
+                }
+                WaitFor::StdErrMessage { message } => {
+                    let logs = self.docker_client.stderr_logs(&self.id);
+                    self.wait_for_new_message(
+                        logs,
+                        LogMatch::Message(&message),
+                        &self.seen_log_lines.stderr,
+                    )
+                    .await
+                }
+                WaitFor::AnyStreamMessage { message } => {
+                    let logs = self.docker_client.combined_logs(&self.id);
+                    self.wait_for_new_message(
+                        logs,
+                        LogMatch::Message(&message),
+                        &self.seen_log_lines.combined,
+                    )
+                    .await
+                }
+                WaitFor::StdOutMessageWithinLines { message, max_lines } => {
+                    let logs = self.docker_client.stdout_logs(&self.id);
+                    self.wait_for_new_message_within_lines(
+                        logs,
+                        LogMatch::Message(&message),
+                        &self.seen_log_lines.stdout,
+                        max_lines,
+                    )
                     .await
-                    .unwrap(),
-                WaitFor::StdErrMessage { message } => self
-                    .docker_client
-                    .stderr_logs(&self.id)
-                    .wait_for_message(&message)
+                }
+                WaitFor::StdOutRegex { pattern } => {
+                    let logs = self.docker_client.stdout_logs(&self.id);
+                    self.wait_for_new_message(
+                        logs,
+                        LogMatch::Pattern(&pattern),
+                        &self.seen_log_lines.stdout,
+                    )
+                    .await
+                }
+                WaitFor::StdErrRegex { pattern } => {
+                    let logs = self.docker_client.stderr_logs(&self.id);
+                    self.wait_for_new_message(
+                        logs,
+                        LogMatch::Pattern(&pattern),
+                        &self.seen_log_lines.stderr,
+                    )
                     .await
-                    .unwrap(),
+                }
+                WaitFor::StdOutMessageSequence { messages } => {
+                    // every message is looked for after the lines seen for the previous one
+                    for message in messages {
+                        let logs = self.docker_client.stdout_logs(&self.id);
+                        self.wait_for_new_message(
+                            logs,
+                            LogMatch::Message(&message),
+                            &self.seen_log_lines.stdout,
+                        )
+                        .await
+                    }
+                }
                 WaitFor::Duration { length } => {
                     tokio::time::sleep(length).await;
                 }
@@ -261,16 +1268,236 @@ where
                             panic!("Healthcheck not configured for container")
                         }
                         Some(UNHEALTHY) => panic!("Healthcheck reports unhealthy"),
-                        Some(STARTING) => sleep(Duration::from_millis(100)).await,
+                        Some(STARTING) => sleep(self.image.poll_interval()).await,
                     }
-                    panic!("Healthcheck for the container is not configured");
                 },
+                WaitFor::HealthcheckVerbose { timeout } => {
+                    use HealthStatusEnum::*;
+
+                    let started = Instant::now();
+
+                    loop {
+                        let health_status = self
+                            .docker_client
+                            .inspect(&self.id)
+                            .await
+                            .state
+                            .unwrap_or_else(|| panic!("Container state not available"))
+                            .health
+                            .unwrap_or_else(|| panic!("Health state not available"))
+                            .status;
+
+                        let reason = match health_status {
+                            Some(HEALTHY) => break,
+                            None | Some(EMPTY) | Some(NONE) => {
+                                panic!("Healthcheck not configured for container")
+                            }
+                            Some(UNHEALTHY) => "reports unhealthy".to_owned(),
+                            Some(STARTING) if started.elapsed() >= timeout => {
+                                format!("did not become healthy within {timeout:?}")
+                            }
+                            Some(STARTING) => {
+                                sleep(self.image.poll_interval()).await;
+                                continue;
+                            }
+                        };
+
+                        panic!("{}", self.health_report(reason).await)
+                    }
+                }
+                WaitFor::TcpPort { port, timeout } => {
+                    let host_port = self.get_host_port_ipv4(port).await;
+                    let host = self.docker_client.host();
+
+                    let started = Instant::now();
+                    while let Err(e) = TcpStream::connect((host.as_str(), host_port)).await {
+                        if started.elapsed() >= timeout {
+                            panic!(
+                                "port {port} of container {} did not accept connections on {host}:{host_port} within {timeout:?}, last attempt failed: {e}",
+                                self.id
+                            );
+                        }
+
+                        sleep(self.image.poll_interval()).await;
+                    }
+                }
+                WaitFor::StableAfterRestarts { max } => {
+                    let mut tracker = RestartTracker::new(max);
+
+                    while !tracker.observe(&self.docker_client.inspect(&self.id).await) {
+                        sleep(self.image.poll_interval()).await;
+                    }
+                }
+                WaitFor::PortsPublished { count, timeout } => {
+                    let started = Instant::now();
+
+                    loop {
+                        let published = self.docker_client.ports(&self.id).await.published_count();
+                        if published >= count {
+                            break;
+                        }
+                        if started.elapsed() >= timeout {
+                            panic!(
+                                "container {} published {published} of {count} ports within {timeout:?}",
+                                self.id
+                            );
+                        }
+
+                        sleep(self.image.poll_interval()).await;
+                    }
+                }
+                WaitFor::Http {
+                    port,
+                    path,
+                    expected_status,
+                    body_contains,
+                    timeout,
+                } => {
+                    let host = self.get_host();
+                    let host_port = self.get_host_port_ipv4(port).await;
+                    let probe = HttpProbe {
+                        path: &path,
+                        expected_status,
+                        body_contains: body_contains.as_deref(),
+                    };
+
+                    let started = Instant::now();
+                    let mut delay = self.image.poll_interval();
+                    loop {
+                        let remaining = timeout.saturating_sub(started.elapsed());
+                        match probe.send_async(&host, host_port, remaining).await {
+                            Ok(()) => break,
+                            Err(reason) if started.elapsed() >= timeout => {
+                                panic!("{}", probe.failure(&self.id, timeout, &reason))
+                            }
+                            Err(_) => {
+                                sleep(delay).await;
+                                delay = http_probe::backoff(delay, self.image.poll_interval());
+                            }
+                        }
+                    }
+                }
+                #[cfg(feature = "grpc")]
+                WaitFor::Grpc {
+                    port,
+                    service,
+                    timeout,
+                } => {
+                    let host_port = self.get_host_port_ipv4(port).await;
+
+                    grpc::wait_until_serving(
+                        &self.id,
+                        &self.get_host(),
+                        host_port,
+                        service.as_deref().unwrap_or_default(),
+                        timeout,
+                        self.image.poll_interval(),
+                    )
+                    .await
+                }
+                #[cfg(not(feature = "grpc"))]
+                WaitFor::Grpc { .. } => {
+                    panic!("waiting for a gRPC service requires the `grpc` feature")
+                }
                 WaitFor::Nothing => {}
             }
         }
 
         log::debug!("Container {} is now ready!", self.id);
     }
+
+    /// Waits for a message in the lines of `logs` that come after the `seen` ones and marks the
+    /// lines up to the message as seen.
+    ///
+    /// Fails as soon as the container crashes instead of waiting for a message that is never
+    /// going to be written.
+    async fn wait_for_new_message(
+        &self,
+        logs: LogStreamAsync<'static>,
+        expected: LogMatch<'_>,
+        seen: &AtomicUsize,
+    ) {
+        self.wait_for_new_message_within_lines(logs, expected, seen, usize::MAX)
+            .await
+    }
+
+    /// Like [`ContainerAsync::wait_for_new_message`], but gives up after `max_lines` new lines.
+    async fn wait_for_new_message_within_lines(
+        &self,
+        logs: LogStreamAsync<'static>,
+        expected: LogMatch<'_>,
+        seen: &AtomicUsize,
+        max_lines: usize,
+    ) {
+        let message_found =
+            logs.wait_for_match_within_lines(expected, seen.load(Ordering::SeqCst), max_lines);
+        let crashed = self.wait_for_crash();
+        futures::pin_mut!(message_found, crashed);
+
+        let result = match future::select(message_found, crashed).await {
+            Either::Left((result, _)) => result,
+            Either::Right((exit_code, _)) => {
+                panic!("{}", self.crash_report(exit_code, expected).await)
+            }
+        };
+
+        match result {
+            Ok(lines) => seen.store(lines, Ordering::SeqCst),
+            // the log stream also ends when the container exits
+            Err(WaitError::LineLimitExceeded(lines)) => panic!(
+                "failed to find {expected} in {} lines of the logs of container {}",
+                lines.len(),
+                self.id
+            ),
+            Err(e) => match self.crash_exit_code().await {
+                Some(exit_code) => panic!("{}", self.crash_report(exit_code, expected).await),
+                None => panic!(
+                    "failed to find {expected} in the logs of container {}: {e:?}",
+                    self.id
+                ),
+            },
+        }
+    }
+
+    /// Waits until the container exits with a non-zero exit code and its restart policy doesn't
+    /// restart it, never returns otherwise.
+    async fn wait_for_crash(&self) -> i64 {
+        loop {
+            let exit_code = self.docker_client.wait(&self.id).await;
+
+            if !will_be_restarted(&self.docker_client.inspect(&self.id).await) {
+                if exit_code == 0 {
+                    // a log message may still be found in the output of a cleanly exited container
+                    return future::pending().await;
+                }
+
+                return exit_code;
+            }
+
+            sleep(self.image.poll_interval()).await;
+        }
+    }
+
+    /// Returns the exit code of the container if it has exited with a non-zero exit code and its
+    /// restart policy doesn't restart it.
+    async fn crash_exit_code(&self) -> Option<i64> {
+        let details = self.docker_client.inspect(&self.id).await;
+        let state = details.state.as_ref()?;
+        let exit_code = state.exit_code.unwrap_or_default();
+
+        if state.running == Some(true) || exit_code == 0 || will_be_restarted(&details) {
+            return None;
+        }
+
+        Some(exit_code)
+    }
+
+    async fn crash_report(&self, exit_code: i64, expected: LogMatch<'_>) -> String {
+        self.health_report(format!(
+            "exited with code {exit_code} while waiting for {expected}"
+        ))
+        .await
+    }
 }
 
 impl<I> Drop for ContainerAsync<I>
@@ -278,6 +1505,1363 @@ where
     I: Image,
 {
     fn drop(&mut self) {
+        for task in &self.background_tasks {
+            task.abort();
+        }
+
         block_on(self.drop_async())
     }
 }
+
+/// Splits an absolute path to a file inside a container into its directory and file name.
+/// Copies `bytes` into a file with the given permission bits at the absolute path `target`
+/// inside the container `id`.
+async fn copy_bytes_to(
+    docker_client: &dyn DockerAsync,
+    id: &str,
+    bytes: &[u8],
+    target: &str,
+    mode: u32,
+) {
+    let (directory, file_name) = split_target(target);
+
+    let archive = tar::single_file(file_name, bytes, mode);
+    docker_client.copy_to(id, archive, directory).await
+}
+
+fn split_target(target: &str) -> (&str, &str) {
+    match target.rsplit_once('/') {
+        Some((directory, file_name)) if target.starts_with('/') && !file_name.is_empty() => (
+            if directory.is_empty() { "/" } else { directory },
+            file_name,
+        ),
+        _ => panic!("`{target}` is not an absolute path to a file"),
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &Metadata) -> u32 {
+    DEFAULT_FILE_MODE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::LogConsumer, images::generic::GenericImage};
+    use bollard::models::{
+        ContainerConfig, ContainerState, EndpointSettings, Health, NetworkSettings, PortBinding,
+        PortMap,
+    };
+    use futures::TryStreamExt;
+    use std::{io::Read, ops::Range};
+
+    /// A fake docker client which reports containers as healthy after a number of inspections.
+    #[derive(Default)]
+    struct FakeDocker {
+        inspect_calls: Arc<AtomicUsize>,
+        restart_calls: Arc<AtomicUsize>,
+        ports_calls: Arc<AtomicUsize>,
+        rm_calls: Arc<AtomicUsize>,
+        healthy_after: usize,
+        unhealthy_calls: Range<usize>,
+        health_log: Vec<HealthcheckResult>,
+        stdout: Vec<String>,
+        stderr: Vec<String>,
+        env: Vec<String>,
+        bridge_ip_after: Option<usize>,
+        networks: Vec<String>,
+        copied: Arc<Mutex<Vec<u8>>>,
+        /// Keeps stdout open after the given chunks, like a container that is still running.
+        follow_logs: bool,
+        /// Lets the container exit with the given code after the given delay.
+        exits_after: Option<(Duration, i64)>,
+        recreate_calls: Arc<AtomicUsize>,
+        /// The stdout of recreated containers.
+        recreated_stdout: Vec<String>,
+        execs: Arc<Mutex<Vec<ExecSpec>>>,
+    }
+
+    #[async_trait]
+    impl DockerAsync for FakeDocker {
+        fn stdout_logs(&self, id: &str) -> LogStreamAsync<'static> {
+            let stdout = if id.starts_with("recreated") {
+                &self.recreated_stdout
+            } else {
+                &self.stdout
+            };
+            let chunks = stdout.iter().cloned().map(Ok).collect::<Vec<_>>();
+
+            if self.follow_logs {
+                LogStreamAsync::new(futures::stream::iter(chunks).chain(futures::stream::pending()))
+            } else {
+                LogStreamAsync::new(futures::stream::iter(chunks))
+            }
+        }
+
+        fn stderr_logs(&self, _id: &str) -> LogStreamAsync<'static> {
+            let chunks = self.stderr.iter().cloned().map(Ok).collect::<Vec<_>>();
+
+            LogStreamAsync::new(futures::stream::iter(chunks))
+        }
+
+        fn combined_logs(&self, _id: &str) -> LogStreamAsync<'static> {
+            let chunks = self
+                .stdout
+                .iter()
+                .chain(&self.stderr)
+                .cloned()
+                .map(Ok)
+                .collect::<Vec<_>>();
+
+            LogStreamAsync::new(futures::stream::iter(chunks))
+        }
+
+        fn recent_logs(
+            &self,
+            id: &str,
+            source: LogSource,
+            _lines: usize,
+        ) -> LogStreamAsync<'static> {
+            match source {
+                LogSource::StdOut => {
+                    let chunks = self.stdout.iter().cloned().map(Ok).collect::<Vec<_>>();
+
+                    LogStreamAsync::new(futures::stream::iter(chunks))
+                }
+                LogSource::StdErr => self.stderr_logs(id),
+            }
+        }
+
+        fn current_logs(&self, id: &str) -> LogStreamAsync<'static> {
+            self.combined_logs(id)
+        }
+
+        fn current_raw_logs(&self, _id: &str) -> RawLogStreamAsync<'static> {
+            let chunks = self
+                .stdout
+                .iter()
+                .chain(&self.stderr)
+                .map(|chunk| Ok(chunk.clone().into_bytes()))
+                .collect::<Vec<_>>();
+
+            RawLogStreamAsync::new(futures::stream::iter(chunks))
+        }
+
+        fn host(&self) -> String {
+            env::LOCAL_HOST.to_owned()
+        }
+
+        async fn ports(&self, _id: &str) -> Ports {
+            // every call publishes one more port
+            let calls = self.ports_calls.fetch_add(1, Ordering::SeqCst) + 1;
+
+            (1..=calls)
+                .map(|port| {
+                    (
+                        format!("{port}/tcp"),
+                        Some(vec![PortBinding {
+                            host_ip: Some("0.0.0.0".to_owned()),
+                            host_port: Some((30000 + port).to_string()),
+                        }]),
+                    )
+                })
+                .collect::<PortMap>()
+                .into()
+        }
+
+        async fn inspect(&self, _id: &str) -> ContainerInspectResponse {
+            let calls = self.inspect_calls.fetch_add(1, Ordering::SeqCst) + 1;
+            let status = if self.unhealthy_calls.contains(&calls) {
+                HealthStatusEnum::UNHEALTHY
+            } else if calls >= self.healthy_after {
+                HealthStatusEnum::HEALTHY
+            } else {
+                HealthStatusEnum::STARTING
+            };
+
+            ContainerInspectResponse {
+                created: Some("2023-06-01T12:00:00.000000000Z".to_owned()),
+                config: Some(ContainerConfig {
+                    env: Some(self.env.clone()),
+                    ..Default::default()
+                }),
+                state: Some(ContainerState {
+                    running: Some(true),
+                    started_at: Some("2023-06-01T12:00:01.000000000Z".to_owned()),
+                    health: Some(Health {
+                        status: Some(status),
+                        log: Some(self.health_log.clone()),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                network_settings: self
+                    .bridge_ip_after
+                    .filter(|after| calls >= *after)
+                    .map(|_| NetworkSettings {
+                        bridge: Some("bridge".to_owned()),
+                        networks: Some(HashMap::from([(
+                            "bridge".to_owned(),
+                            EndpointSettings {
+                                ip_address: Some("172.17.0.2".to_owned()),
+                                ..Default::default()
+                            },
+                        )])),
+                        ..Default::default()
+                    })
+                    .or_else(|| {
+                        (!self.networks.is_empty()).then(|| NetworkSettings {
+                            networks: Some(
+                                self.networks
+                                    .iter()
+                                    .map(|network| (network.clone(), EndpointSettings::default()))
+                                    .collect(),
+                            ),
+                            ..Default::default()
+                        })
+                    }),
+                ..Default::default()
+            }
+        }
+
+        async fn rm(&self, _id: &str) {
+            self.rm_calls.fetch_add(1, Ordering::SeqCst);
+        }
+
+        async fn rm_keep_volumes(&self, id: &str) {
+            self.rm(id).await
+        }
+
+        async fn stop(&self, _id: &str) {}
+
+        async fn start(&self, _id: &str) {}
+
+        async fn recreate(&self, _id: &str) -> String {
+            let calls = self.recreate_calls.fetch_add(1, Ordering::SeqCst) + 1;
+            format!("recreated-{calls}")
+        }
+
+        async fn restart(&self, _id: &str) {
+            self.restart_calls.fetch_add(1, Ordering::SeqCst);
+        }
+
+        async fn wait(&self, _id: &str) -> i64 {
+            match self.exits_after {
+                Some((delay, exit_code)) => {
+                    sleep(delay).await;
+                    exit_code
+                }
+                None => futures::future::pending().await,
+            }
+        }
+
+        async fn changes(&self, _id: &str) -> Vec<FilesystemChange> {
+            Vec::new()
+        }
+
+        async fn exec(&self, _id: &str, spec: ExecSpec) -> ExecResult {
+            self.execs.lock().unwrap().push(spec.clone());
+
+            // echoes the arguments of the command
+            ExecResult {
+                stdout: spec.cmd[1..].join(" ").into_bytes(),
+                stderr: Vec::new(),
+                exit_code: 0,
+            }
+        }
+
+        async fn copy_to(&self, _id: &str, tar_bytes: Vec<u8>, _dest_dir: &str) {
+            self.copied.lock().unwrap().extend(tar_bytes);
+        }
+
+        async fn copy_stream_to(
+            &self,
+            _id: &str,
+            tar: BoxStream<'static, Result<Vec<u8>, io::Error>>,
+            _dest_dir: &str,
+        ) {
+            let chunks = tar.try_collect::<Vec<_>>().await.unwrap();
+            self.copied.lock().unwrap().extend(chunks.concat());
+        }
+
+        async fn copy_from(
+            &self,
+            _id: &str,
+            path: &str,
+            options: CopyFromOptions,
+        ) -> Result<Vec<u8>, CopyFromError> {
+            let archive = match path {
+                "/var/log/app.log" => tar::single_file("app.log", b"started", 0o644),
+                "/var/log" => {
+                    let mut builder = ::tar::Builder::new(Vec::new());
+                    let mut directory = ::tar::Header::new_ustar();
+                    directory.set_path("log/").unwrap();
+                    directory.set_entry_type(::tar::EntryType::Directory);
+                    directory.set_mode(0o755);
+                    directory.set_size(0);
+                    directory.set_cksum();
+                    builder.append(&directory, std::io::empty()).unwrap();
+                    let mut archive = builder.into_inner().unwrap();
+                    archive.extend(tar::single_file("log/app.log", b"started", 0o644));
+                    archive
+                }
+                _ => return Err(CopyFromError::NotFound(path.to_owned())),
+            };
+
+            let mut writer = options.archive();
+            writer.write(&archive);
+            Ok(writer.finish())
+        }
+    }
+
+    #[tokio::test]
+    async fn healthcheck_should_be_polled_in_configured_interval() {
+        let inspect_calls = Arc::new(AtomicUsize::new(0));
+        let docker = FakeDocker {
+            inspect_calls: inspect_calls.clone(),
+            // one inspection verifies that the container is running
+            healthy_after: 4,
+            ..Default::default()
+        };
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest").with_wait_for(WaitFor::Healthcheck),
+        )
+        .with_poll_interval(Duration::from_millis(200));
+
+        let before_run = Instant::now();
+        let _container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        assert_eq!(inspect_calls.load(Ordering::SeqCst), 4);
+        assert!(before_run.elapsed() >= Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn restart_and_wait_should_wait_for_ready_conditions_again() {
+        let inspect_calls = Arc::new(AtomicUsize::new(0));
+        let restart_calls = Arc::new(AtomicUsize::new(0));
+        let docker = FakeDocker {
+            inspect_calls: inspect_calls.clone(),
+            restart_calls: restart_calls.clone(),
+            ..Default::default()
+        };
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest").with_wait_for(WaitFor::Healthcheck),
+        );
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+        let inspect_calls_after_start = inspect_calls.load(Ordering::SeqCst);
+
+        container.restart_and_wait().await;
+
+        assert_eq!(restart_calls.load(Ordering::SeqCst), 1);
+        // the container is verified to be running and healthy again
+        assert_eq!(
+            inspect_calls.load(Ordering::SeqCst),
+            inspect_calls_after_start * 2
+        );
+    }
+
+    #[tokio::test]
+    async fn ports_published_should_wait_for_the_given_number_of_ports() {
+        let ports_calls = Arc::new(AtomicUsize::new(0));
+        let docker = FakeDocker {
+            ports_calls: ports_calls.clone(),
+            ..Default::default()
+        };
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest")
+                .with_wait_for(WaitFor::ports_published(3, Duration::from_secs(5))),
+        )
+        .with_poll_interval(Duration::from_millis(10));
+
+        let _container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        assert_eq!(ports_calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "of 10 ports within 150ms")]
+    async fn ports_published_should_panic_after_timeout() {
+        let docker = FakeDocker::default();
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest")
+                .with_wait_for(WaitFor::ports_published(10, Duration::from_millis(150))),
+        )
+        .with_poll_interval(Duration::from_millis(100));
+
+        ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "container fake exited with code 1 while waiting for message `ready`\n\
+                    --- last 20 lines of stdout ---\n\
+                    starting"
+    )]
+    async fn message_wait_should_panic_as_soon_as_container_crashes() {
+        let docker = FakeDocker {
+            stdout: vec!["starting\n".to_owned()],
+            follow_logs: true,
+            exits_after: Some((Duration::from_secs(1), 1)),
+            ..Default::default()
+        };
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest")
+                .with_wait_for(WaitFor::message_on_stdout("ready")),
+        );
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove),
+        )
+        .await
+        .expect("crash should be reported before the timeout");
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "failed to find message `ready` in 5 lines")]
+    async fn message_within_lines_should_give_up_after_line_budget() {
+        let docker = FakeDocker {
+            stdout: vec!["noise\n".repeat(10)],
+            follow_logs: true,
+            ..Default::default()
+        };
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest")
+                .with_wait_for(WaitFor::message_on_stdout_within_lines("ready", 5)),
+        );
+
+        ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+    }
+
+    /// Records the steps of all `container` spans along with the events emitted inside of them.
+    #[cfg(feature = "tracing")]
+    #[derive(Default)]
+    struct StepRecorder {
+        spans: Mutex<Vec<BTreeMap<&'static str, String>>>,
+        entered: Mutex<Vec<u64>>,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[cfg(feature = "tracing")]
+    struct FieldVisitor<'a>(&'a mut BTreeMap<&'static str, String>);
+
+    #[cfg(feature = "tracing")]
+    impl tracing::field::Visit for FieldVisitor<'_> {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.insert(field.name(), value.to_owned());
+        }
+
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+            self.0.insert(field.name(), format!("{value:?}"));
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for StepRecorder {
+        fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut fields = BTreeMap::new();
+            span.record(&mut FieldVisitor(&mut fields));
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(fields);
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+            let mut spans = self.spans.lock().unwrap();
+            values.record(&mut FieldVisitor(&mut spans[span.into_u64() as usize - 1]));
+        }
+
+        fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            let mut fields = BTreeMap::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            let span = *self.entered.lock().unwrap().last().unwrap();
+            let spans = self.spans.lock().unwrap();
+            let span = &spans[span as usize - 1];
+            self.events.lock().unwrap().push(format!(
+                "{} {} of {}: {}",
+                span["step"], span["id"], span["image"], fields["message"]
+            ));
+        }
+
+        fn enter(&self, span: &tracing::span::Id) {
+            self.entered.lock().unwrap().push(span.into_u64());
+        }
+
+        fn exit(&self, _span: &tracing::span::Id) {
+            self.entered.lock().unwrap().pop();
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn lifecycle_steps_should_be_traced() {
+        let recorder = StepRecorder::default();
+        let events = recorder.events.clone();
+        let _guard = tracing::subscriber::set_default(recorder);
+        let docker = FakeDocker {
+            stdout: vec!["ready\n".to_owned()],
+            ..Default::default()
+        };
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest")
+                .with_wait_for(WaitFor::message_on_stdout("ready")),
+        );
+
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+        drop(container);
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "ready fake of hello-world:latest: step started",
+                "ready fake of hello-world:latest: step finished",
+                "remove fake of hello-world:latest: step started",
+                "remove fake of hello-world:latest: step finished",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn exec_should_return_result_of_command() {
+        let docker = FakeDocker::default();
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        let result = container
+            .exec(vec!["echo".to_owned(), "hello".to_owned()])
+            .await;
+
+        assert_eq!(
+            result,
+            ExecResult {
+                stdout: b"hello".to_vec(),
+                stderr: Vec::new(),
+                exit_code: 0,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn owned_bind_mounts_should_be_chowned_as_root() {
+        let docker = FakeDocker::default();
+        let execs = docker.execs.clone();
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_owned_bind_mount(("/tmp/fixtures", "/data"), "1000:1000");
+
+        ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        assert_eq!(
+            *execs.lock().unwrap(),
+            vec![ExecSpec {
+                cmd: vec![
+                    "chown".to_owned(),
+                    "-R".to_owned(),
+                    "1000:1000".to_owned(),
+                    "/data".to_owned()
+                ],
+                user: Some("0".to_owned()),
+                working_dir: None,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn startup_retries_should_recreate_container_that_fails_readiness() {
+        let docker = FakeDocker {
+            stdout: vec!["noise\n".repeat(10)],
+            recreated_stdout: vec!["starting\nready\n".to_owned()],
+            follow_logs: true,
+            ..Default::default()
+        };
+        let recreate_calls = docker.recreate_calls.clone();
+        let rm_calls = docker.rm_calls.clone();
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest")
+                .with_wait_for(WaitFor::message_on_stdout_within_lines("ready", 5)),
+        )
+        .with_startup_retries(2);
+
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Keep).await;
+
+        assert_eq!(container.id(), "recreated-1");
+        assert_eq!(recreate_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(rm_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "failed to find message `ready` in 5 lines")]
+    async fn startup_retries_should_give_up_once_exhausted() {
+        let docker = FakeDocker {
+            stdout: vec!["noise\n".repeat(10)],
+            recreated_stdout: vec!["noise\n".repeat(10)],
+            follow_logs: true,
+            ..Default::default()
+        };
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest")
+                .with_wait_for(WaitFor::message_on_stdout_within_lines("ready", 5)),
+        )
+        .with_startup_retries(1);
+
+        ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+    }
+
+    #[tokio::test]
+    async fn message_sequence_should_find_milestones_in_order() {
+        let docker = FakeDocker {
+            stdout: vec![
+                "stage two\nstage one\n".to_owned(),
+                "stage two\nstage three\n".to_owned(),
+            ],
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest").with_wait_for(
+            WaitFor::message_sequence_on_stdout(["stage one", "stage two", "stage three"]),
+        ));
+
+        ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+    }
+
+    #[tokio::test]
+    async fn regex_on_stdout_should_find_matching_line() {
+        let docker = FakeDocker {
+            stdout: vec![
+                "database system is starting up\n".to_owned(),
+                "database system is ready to accept connections on port 5432\n".to_owned(),
+            ],
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest").with_wait_for(
+            WaitFor::regex_on_stdout("database system is ready.*port 5432"),
+        ));
+
+        ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "failed to find pattern `ready.*port 5433`")]
+    async fn regex_on_stderr_should_fail_if_no_line_matches() {
+        let docker = FakeDocker {
+            stderr: vec!["ready to accept connections on port 5432\n".to_owned()],
+            ..Default::default()
+        };
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest")
+                .with_wait_for(WaitFor::regex_on_stderr("ready.*port 5433")),
+        );
+
+        ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "failed to find message `stage two`")]
+    async fn message_sequence_should_ignore_milestones_out_of_order() {
+        let docker = FakeDocker {
+            stdout: vec!["stage two\nstage one\nstage three\n".to_owned()],
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest").with_wait_for(
+            WaitFor::message_sequence_on_stdout(["stage one", "stage two", "stage three"]),
+        ));
+
+        ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+    }
+
+    #[tokio::test]
+    async fn debug_output_should_include_metadata() {
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_metadata("test", "debug_output_should_include_metadata")
+            .with_metadata("run", "42");
+
+        let container = ContainerAsync::new(
+            "fake".to_owned(),
+            FakeDocker::default(),
+            image,
+            env::Command::Remove,
+        )
+        .await;
+
+        let debug = format!("{container:?}");
+        assert!(debug.starts_with(r#"ContainerAsync { id: "fake", image: RunnableImage {"#));
+        assert!(debug.ends_with(
+            r#"metadata: {"run": "42", "test": "debug_output_should_include_metadata"} }"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn health_log_should_return_results_of_failing_healthcheck() {
+        let failure = HealthcheckResult {
+            exit_code: Some(1),
+            output: Some("connection refused".to_owned()),
+            ..Default::default()
+        };
+        let docker = FakeDocker {
+            health_log: vec![failure.clone(), failure.clone()],
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        assert_eq!(container.health_log().await, vec![failure.clone(), failure]);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "did not become healthy within 300ms\n\
+                               --- last 20 lines of stdout ---\n\
+                               booting\n\
+                               waiting for database")]
+    async fn healthcheck_verbose_should_panic_with_logs_of_never_healthy_container() {
+        let docker = FakeDocker {
+            healthy_after: usize::MAX,
+            stdout: vec!["booting\nwaiting for database\n".to_owned()],
+            ..Default::default()
+        };
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest")
+                .with_wait_for(WaitFor::healthcheck_verbose(Duration::from_millis(300))),
+        );
+
+        ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+    }
+
+    #[tokio::test]
+    async fn host_port_with_timeout_should_wait_for_port_to_be_mapped() {
+        let ports_calls = Arc::new(AtomicUsize::new(0));
+        let docker = FakeDocker {
+            ports_calls: ports_calls.clone(),
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_poll_interval(Duration::from_millis(10));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        let host_port = container
+            .get_host_port_ipv4_with_timeout(3, Duration::from_secs(5))
+            .await;
+
+        assert_eq!(host_port, 30003);
+        assert_eq!(ports_calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn host_port_of_explicitly_mapped_port_should_not_inspect_container() {
+        let inspect_calls = Arc::new(AtomicUsize::new(0));
+        let ports_calls = Arc::new(AtomicUsize::new(0));
+        let docker = FakeDocker {
+            inspect_calls: inspect_calls.clone(),
+            ports_calls: ports_calls.clone(),
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_mapped_port((8080, 80));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+        let inspections = inspect_calls.load(Ordering::SeqCst);
+
+        let host_port = container.get_host_port_ipv4(80).await;
+
+        assert_eq!(host_port, 8080);
+        assert_eq!(inspect_calls.load(Ordering::SeqCst), inspections);
+        assert_eq!(ports_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn host_port_of_auto_published_port_should_inspect_container() {
+        let ports_calls = Arc::new(AtomicUsize::new(0));
+        let docker = FakeDocker {
+            ports_calls: ports_calls.clone(),
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_mapped_port((8080, 80));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        let host_port = container.get_host_port_ipv4(1).await;
+
+        assert_eq!(host_port, 30001);
+        assert_eq!(ports_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn export_ports_to_env_should_set_host_port_of_each_mapped_port() {
+        let docker = FakeDocker::default();
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        container.export_ports_to_env("EXPORTED").await;
+
+        assert_eq!(std::env::var("EXPORTED_1_TCP").as_deref(), Ok("30001"));
+    }
+
+    #[tokio::test]
+    async fn await_port_mapping_should_return_port_once_it_is_mapped() {
+        let ports_calls = Arc::new(AtomicUsize::new(0));
+        let docker = FakeDocker {
+            ports_calls: ports_calls.clone(),
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_poll_interval(Duration::from_millis(10));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        let host_port = container.await_port_mapping(4).await;
+
+        assert_eq!(host_port, 30004);
+        assert_eq!(ports_calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "container fake did not map port 100 within 50ms")]
+    async fn host_port_with_timeout_should_panic_if_port_is_not_mapped_in_time() {
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_poll_interval(Duration::from_millis(10));
+        let container = ContainerAsync::new(
+            "fake".to_owned(),
+            FakeDocker::default(),
+            image,
+            env::Command::Remove,
+        )
+        .await;
+
+        container
+            .get_host_port_ipv4_with_timeout(100, Duration::from_millis(50))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn any_stream_message_should_find_message_on_stderr() {
+        let docker = FakeDocker {
+            stdout: vec!["starting\n".to_owned()],
+            stderr: vec!["ready to accept connections\n".to_owned()],
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest").with_wait_for(
+            WaitFor::message_on_any_stream("ready to accept connections"),
+        ));
+
+        ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+    }
+
+    #[tokio::test]
+    async fn autoheal_should_restart_unhealthy_container() {
+        let restart_calls = Arc::new(AtomicUsize::new(0));
+        let docker = FakeDocker {
+            restart_calls: restart_calls.clone(),
+            // the first inspection verifies that the container is running
+            unhealthy_calls: 3..4,
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_autoheal(5)
+            .with_poll_interval(Duration::from_millis(10));
+
+        let _container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert_eq!(restart_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn autoheal_should_stop_after_max_restarts() {
+        let restart_calls = Arc::new(AtomicUsize::new(0));
+        let docker = FakeDocker {
+            restart_calls: restart_calls.clone(),
+            unhealthy_calls: 2..usize::MAX,
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_autoheal(2)
+            .with_poll_interval(Duration::from_millis(10));
+
+        let _container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert_eq!(restart_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn assert_networks_should_accept_exact_set_of_networks() {
+        let docker = FakeDocker {
+            networks: vec!["frontend".to_owned(), "backend".to_owned()],
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        assert_eq!(container.networks().await, vec!["backend", "frontend"]);
+        container.assert_networks(&["frontend", "backend"]).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "missing: [\"bridge\"]\nunexpected: [\"frontend\"]")]
+    async fn assert_networks_should_panic_with_differences() {
+        let docker = FakeDocker {
+            networks: vec!["frontend".to_owned(), "backend".to_owned()],
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        container.assert_networks(&["backend", "bridge"]).await;
+    }
+
+    #[tokio::test]
+    async fn env_should_parse_environment_of_container() {
+        let docker = FakeDocker {
+            env: vec![
+                "PATH=/usr/bin".to_owned(),
+                "OPTIONS=a=1,b=2".to_owned(),
+                "EMPTY=".to_owned(),
+            ],
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        let env = container.env().await;
+
+        assert_eq!(env.len(), 3);
+        assert_eq!(env["PATH"], "/usr/bin");
+        assert_eq!(env["OPTIONS"], "a=1,b=2");
+        assert_eq!(env["EMPTY"], "");
+    }
+
+    #[tokio::test]
+    async fn log_counts_should_include_stdout_and_stderr() {
+        let docker = FakeDocker {
+            stdout: vec!["one\ntwo\nthr".to_owned(), "ee\nfour\n".to_owned()],
+            stderr: vec!["five".to_owned()],
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        assert_eq!(container.log_line_count().await, 5);
+        assert_eq!(container.log_byte_count().await, 23);
+    }
+
+    #[tokio::test]
+    async fn bridge_ip_address_should_be_found_once_it_is_assigned() {
+        let inspect_calls = Arc::new(AtomicUsize::new(0));
+        let docker = FakeDocker {
+            inspect_calls: inspect_calls.clone(),
+            bridge_ip_after: Some(3),
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+        inspect_calls.store(0, Ordering::SeqCst);
+
+        let ip = container.try_get_bridge_ip_address().await;
+
+        assert_eq!(ip, Ok(IpAddr::from([172, 17, 0, 2])));
+        assert_eq!(inspect_calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn inspect_cache_should_share_details_between_accessors() {
+        let inspect_calls = Arc::new(AtomicUsize::new(0));
+        let docker = FakeDocker {
+            inspect_calls: inspect_calls.clone(),
+            bridge_ip_after: Some(0),
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_inspect_cache(Duration::from_secs(60));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+        inspect_calls.store(0, Ordering::SeqCst);
+
+        container.get_bridge_ip_address().await;
+        container.networks().await;
+        assert_eq!(inspect_calls.load(Ordering::SeqCst), 1);
+
+        container.restart().await;
+        container.networks().await;
+        assert_eq!(inspect_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn missing_bridge_ip_address_should_be_reported_after_retries() {
+        let inspect_calls = Arc::new(AtomicUsize::new(0));
+        let docker = FakeDocker {
+            inspect_calls: inspect_calls.clone(),
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+        inspect_calls.store(0, Ordering::SeqCst);
+
+        let ip = container.try_get_bridge_ip_address().await;
+
+        assert_eq!(ip, Err(IpError::NoNetworkSettings));
+        assert_eq!(inspect_calls.load(Ordering::SeqCst), BRIDGE_IP_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn copy_file_to_should_stream_file_as_tar_archive() {
+        let src = std::env::temp_dir().join(format!("copy-file-to-{}", std::process::id()));
+        let contents = (0..1_000_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        std::fs::write(&src, &contents).unwrap();
+        let copied = Arc::new(Mutex::new(Vec::new()));
+        let docker = FakeDocker {
+            copied: copied.clone(),
+            ..Default::default()
+        };
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        container.copy_file_to(&src, "/data/file.bin").await;
+        std::fs::remove_file(&src).unwrap();
+
+        let copied = copied.lock().unwrap().clone();
+        assert_eq!(
+            copied.len(),
+            512 + contents.len() + tar::trailer(1_000_000).len()
+        );
+        let mut archive = ::tar::Archive::new(copied.as_slice());
+        let mut entries = archive.entries().unwrap();
+        let mut entry = entries.next().unwrap().unwrap();
+        assert_eq!(entry.path().unwrap().to_str(), Some("file.bin"));
+        let mut copied_contents = Vec::new();
+        entry.read_to_end(&mut copied_contents).unwrap();
+        assert_eq!(copied_contents, contents);
+        drop(entry);
+        assert!(entries.next().is_none());
+    }
+
+    #[tokio::test]
+    async fn created_container_should_copy_files_through_client() {
+        let copied = Arc::new(Mutex::new(Vec::new()));
+        let docker = FakeDocker {
+            copied: copied.clone(),
+            ..Default::default()
+        };
+        let container = CreatedContainer::new("fake".to_owned(), docker);
+
+        container.copy_to("configured", "/etc/app.conf").await;
+
+        assert_eq!(container.id(), "fake");
+        let copied = copied.lock().unwrap().clone();
+        assert_eq!(tar::first_file(&copied), Some(b"configured".to_vec()));
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "container fake did not answer GET /health with status 200 within 300ms, last request failed"
+    )]
+    async fn http_wait_should_give_up_after_timeout() {
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest").with_wait_for(WaitFor::http(
+                1,
+                "/health",
+                200,
+                Duration::from_millis(300),
+            )),
+        );
+
+        // nothing listens on the host port the fake maps the container port to
+        ContainerAsync::new(
+            "fake".to_owned(),
+            FakeDocker::default(),
+            image,
+            env::Command::Remove,
+        )
+        .await;
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    #[tokio::test]
+    #[should_panic(expected = "waiting for a gRPC service requires the `grpc` feature")]
+    async fn grpc_wait_should_require_grpc_feature() {
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest")
+                .with_wait_for(WaitFor::grpc(1, Duration::from_millis(300))),
+        );
+
+        ContainerAsync::new(
+            "fake".to_owned(),
+            FakeDocker::default(),
+            image,
+            env::Command::Remove,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "port 1 of container fake did not accept connections on 127.0.0.1:30001 within 300ms"
+    )]
+    async fn tcp_port_wait_should_give_up_after_timeout() {
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest")
+                .with_wait_for(WaitFor::tcp_port(1, Duration::from_millis(300))),
+        );
+
+        // nothing listens on the host port the fake maps the container port to
+        ContainerAsync::new(
+            "fake".to_owned(),
+            FakeDocker::default(),
+            image,
+            env::Command::Remove,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn copy_file_from_container_should_unpack_single_file() {
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container = ContainerAsync::new(
+            "fake".to_owned(),
+            FakeDocker::default(),
+            image,
+            env::Command::Remove,
+        )
+        .await;
+
+        let contents = container
+            .copy_file_from_container("/var/log/app.log")
+            .await
+            .unwrap();
+
+        assert_eq!(contents, b"started");
+    }
+
+    #[tokio::test]
+    async fn copy_file_from_container_should_reject_directories() {
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container = ContainerAsync::new(
+            "fake".to_owned(),
+            FakeDocker::default(),
+            image,
+            env::Command::Remove,
+        )
+        .await;
+
+        let archive = container.copy_from_container("/var/log").await.unwrap();
+        let error = container.copy_file_from_container("/var/log").await;
+
+        let mut archive = ::tar::Archive::new(archive.as_slice());
+        let directory = archive.entries().unwrap().next().unwrap().unwrap();
+        assert!(directory.header().entry_type().is_dir());
+        assert_eq!(error, Err(CopyFromError::NotAFile("/var/log".to_owned())));
+    }
+
+    #[tokio::test]
+    async fn copy_from_container_with_gzip_should_return_compressed_archive() {
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container = ContainerAsync::new(
+            "fake".to_owned(),
+            FakeDocker::default(),
+            image,
+            env::Command::Remove,
+        )
+        .await;
+
+        let options = CopyFromOptions::default().with_gzip().with_chunk_size(4);
+        let compressed = container
+            .copy_from_container_with("/var/log/app.log", options)
+            .await
+            .unwrap();
+
+        let archive = gzip::tests::gunzip(&compressed);
+        assert_eq!(tar::first_file(&archive), Some(b"started".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn copy_from_container_should_report_missing_path() {
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+        let container = ContainerAsync::new(
+            "fake".to_owned(),
+            FakeDocker::default(),
+            image,
+            env::Command::Remove,
+        )
+        .await;
+
+        let error = container.copy_from_container("/missing").await.unwrap_err();
+
+        assert_eq!(error.to_string(), "`/missing` does not exist in container");
+    }
+
+    #[tokio::test]
+    async fn later_ready_condition_should_wait_for_a_new_message() {
+        let docker = FakeDocker {
+            stdout: vec!["ready\nwaiting\nready\n".to_owned()],
+            ..Default::default()
+        };
+        let image = GenericImage::new("hello-world", "latest")
+            .with_wait_for(WaitFor::message_on_stdout("ready"))
+            .with_wait_for(WaitFor::message_on_stdout("ready"));
+
+        ContainerAsync::new(
+            "fake".to_owned(),
+            docker,
+            image.into(),
+            env::Command::Remove,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "EndOfStream")]
+    async fn later_ready_condition_should_not_match_message_seen_by_earlier_one() {
+        let docker = FakeDocker {
+            stdout: vec!["ready\nwaiting\n".to_owned()],
+            ..Default::default()
+        };
+        let image = GenericImage::new("hello-world", "latest")
+            .with_wait_for(WaitFor::message_on_stdout("ready"))
+            .with_wait_for(WaitFor::message_on_stdout("ready"));
+
+        ContainerAsync::new(
+            "fake".to_owned(),
+            docker,
+            image.into(),
+            env::Command::Remove,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn timings_should_include_time_to_ready() {
+        let docker = FakeDocker {
+            healthy_after: 3,
+            ..Default::default()
+        };
+        let image = RunnableImage::from(
+            GenericImage::new("hello-world", "latest").with_wait_for(WaitFor::Healthcheck),
+        )
+        .with_poll_interval(Duration::from_millis(100));
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+
+        let timings = container.timings().await;
+
+        assert_eq!(
+            timings.created.as_deref(),
+            Some("2023-06-01T12:00:00.000000000Z")
+        );
+        assert_eq!(
+            timings.started_at.as_deref(),
+            Some("2023-06-01T12:00:01.000000000Z")
+        );
+        assert!(timings.time_to_ready >= Duration::from_millis(100));
+        assert!(timings.time_to_ready < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn keep_on_failure_should_only_remove_container_after_success() {
+        let rm_calls = Arc::new(AtomicUsize::new(0));
+        let run = || {
+            let docker = FakeDocker {
+                rm_calls: rm_calls.clone(),
+                ..Default::default()
+            };
+            let image = RunnableImage::from(GenericImage::new("hello-world", "latest"));
+
+            ContainerAsync::new(
+                "fake".to_owned(),
+                docker,
+                image,
+                env::Command::KeepOnFailure,
+            )
+        };
+
+        drop(run().await);
+        assert_eq!(rm_calls.load(Ordering::SeqCst), 1);
+
+        let container = run().await;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+            let _container = container;
+            panic!("test failed");
+        }));
+        assert!(result.is_err());
+        assert_eq!(rm_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn recent_logs_should_return_most_recent_lines_of_log_buffer() {
+        let docker = FakeDocker {
+            stdout: vec!["one\ntwo\nthree\n".to_owned(), "four\nfi".to_owned()],
+            stderr: vec!["ve\nsix\n".to_owned()],
+            ..Default::default()
+        };
+        let image =
+            RunnableImage::from(GenericImage::new("hello-world", "latest")).with_log_buffer(3);
+
+        let container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(container.recent_logs(), "four\nfive\nsix\n");
+    }
+
+    #[derive(Debug, Default)]
+    struct CollectingConsumer {
+        lines: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl LogConsumer for CollectingConsumer {
+        fn accept(&self, container_id: &str, source: LogSource, line: &str) {
+            self.lines
+                .lock()
+                .unwrap()
+                .push(format!("{container_id} {source}: {line}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn log_consumer_should_receive_container_output() {
+        let docker = FakeDocker {
+            stdout: vec!["first line\nsecond ".to_owned(), "line\n".to_owned()],
+            ..Default::default()
+        };
+        let consumer = CollectingConsumer::default();
+        let lines = consumer.lines.clone();
+        let image = RunnableImage::from(GenericImage::new("hello-world", "latest"))
+            .with_log_consumer(consumer);
+
+        let _container =
+            ContainerAsync::new("fake".to_owned(), docker, image, env::Command::Remove).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(
+            *lines.lock().unwrap(),
+            vec!["fake stdout: first line", "fake stdout: second line"]
+        );
+    }
+}
@@ -2,6 +2,15 @@ use std::str::FromStr;
 
 /// Lookup and parse the command specified through the `TESTCONTAINERS` env variable.
 pub fn command<E>() -> Option<Command>
+where
+    E: GetEnvValue,
+{
+    try_command::<E>().unwrap_or_else(|other| panic!("{}", unknown_command(&other)))
+}
+
+/// Like [`command`], but returns the unknown value of the `TESTCONTAINERS` env variable as error
+/// instead of panicking.
+pub(crate) fn try_command<E>() -> Result<Option<Command>, String>
 where
     E: GetEnvValue,
 {
@@ -10,10 +19,184 @@ where
         log::warn!("`KEEP_CONTAINERS` has been changed to `TESTCONTAINERS`");
     }
 
-    let command = E::get_env_value("TESTCONTAINERS")?;
-    let command = command.parse().ok()?;
+    match E::get_env_value("TESTCONTAINERS") {
+        Some(command) => Command::known(&command).map(Some).ok_or(command),
+        None => Ok(None),
+    }
+}
+
+/// Describes an unknown value of the `TESTCONTAINERS` env variable.
+pub(crate) fn unknown_command(command: &str) -> String {
+    format!("unknown command '{command}' provided via TESTCONTAINERS env variable")
+}
+
+/// The path of the Docker socket on the Docker host when no other path is configured.
+pub(crate) const DEFAULT_DOCKER_SOCKET: &str = "/var/run/docker.sock";
+
+/// The named pipe of the Docker daemon on Windows.
+#[cfg(all(windows, feature = "experimental"))]
+const DEFAULT_DOCKER_PIPE: &str = "npipe:////./pipe/docker_engine";
+
+/// The address of a Docker daemon exposed over TCP without TLS, tried if no local daemon exists.
+#[cfg(feature = "experimental")]
+const DEFAULT_DOCKER_TCP_HOST: &str = "tcp://localhost:2375";
+
+/// How long to wait for a daemon on [`DEFAULT_DOCKER_TCP_HOST`] to accept a connection.
+#[cfg(feature = "experimental")]
+const TCP_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Resolves the path of the Docker socket on the Docker host of a client connected to
+/// `docker_host`.
+///
+/// `TESTCONTAINERS_DOCKER_SOCKET_OVERRIDE` takes precedence, followed by the path of a
+/// `unix://` socket in `docker_host`. Otherwise, e.g. for daemons reached over TCP, the default
+/// path is used.
+pub(crate) fn docker_socket<E>(docker_host: Option<&str>) -> String
+where
+    E: GetEnvValue,
+{
+    if let Some(socket) = E::get_env_value("TESTCONTAINERS_DOCKER_SOCKET_OVERRIDE") {
+        return socket;
+    }
+
+    docker_host
+        .and_then(|host| host.strip_prefix("unix://"))
+        .unwrap_or(DEFAULT_DOCKER_SOCKET)
+        .to_owned()
+}
+
+/// The host on which a local Docker daemon publishes the ports of containers.
+pub(crate) const LOCAL_HOST: &str = "127.0.0.1";
+
+/// Resolves the host on which the daemon at `docker_host` publishes the ports of containers.
+///
+/// That's the host of a `tcp://` address. Daemons behind sockets and pipes, or if no address is
+/// configured, publish ports on [`LOCAL_HOST`].
+pub(crate) fn published_ports_host(docker_host: Option<&str>) -> String {
+    let authority = docker_host.and_then(|docker_host| {
+        ["tcp://", "http://", "https://"]
+            .iter()
+            .find_map(|scheme| docker_host.strip_prefix(scheme))
+            .and_then(|address| address.split('/').next())
+    });
+
+    let host = match authority {
+        Some(authority) if authority.starts_with('[') => authority[1..].split(']').next(),
+        Some(authority) => authority
+            .rsplit_once(':')
+            .map(|(host, _port)| host)
+            .or(Some(authority)),
+        None => None,
+    };
+
+    host.filter(|host| !host.is_empty())
+        .unwrap_or(LOCAL_HOST)
+        .to_owned()
+}
+
+/// Wraps an IPv6 address in brackets, as it has to be in the authority of a URL.
+pub(crate) fn url_host(host: &str) -> String {
+    if host.contains(':') && !host.starts_with('[') {
+        format!("[{host}]")
+    } else {
+        host.to_owned()
+    }
+}
+
+/// Resolves the address of the Docker daemon to connect to.
+///
+/// `DOCKER_HOST` takes precedence. Otherwise, the socket of a rootless Docker daemon in
+/// `$XDG_RUNTIME_DIR/docker.sock` is used if it exists, followed by the default socket, or the
+/// named pipe of Docker on Windows. A daemon listening on `tcp://localhost:2375` is the last
+/// resort. If none of them is found, all attempted locations are returned as error.
+#[cfg(feature = "experimental")]
+pub(crate) fn docker_host<E>() -> Result<String, Vec<String>>
+where
+    E: GetEnvValue,
+{
+    if let Some(host) = E::get_env_value("DOCKER_HOST") {
+        return Ok(host);
+    }
+
+    let mut candidates = local_daemons::<E>();
+    candidates.push(DEFAULT_DOCKER_TCP_HOST.to_owned());
+
+    first_listening_daemon(candidates)
+}
+
+#[cfg(all(unix, feature = "experimental"))]
+fn local_daemons<E>() -> Vec<String>
+where
+    E: GetEnvValue,
+{
+    let rootless_socket = E::get_env_value("XDG_RUNTIME_DIR")
+        .map(|runtime_dir| format!("{}/docker.sock", runtime_dir.trim_end_matches('/')));
+
+    rootless_socket
+        .into_iter()
+        .chain(Some(DEFAULT_DOCKER_SOCKET.to_owned()))
+        .map(|socket| format!("unix://{socket}"))
+        .collect()
+}
+
+#[cfg(all(windows, feature = "experimental"))]
+fn local_daemons<E>() -> Vec<String>
+where
+    E: GetEnvValue,
+{
+    vec![DEFAULT_DOCKER_PIPE.to_owned()]
+}
+
+/// Resolves the directory with the TLS certificates of the Docker daemon if `DOCKER_TLS_VERIFY`
+/// asks for a verified connection.
+///
+/// `DOCKER_CERT_PATH` takes precedence, followed by `$HOME/.docker`, just like the Docker CLI.
+#[cfg(feature = "experimental")]
+pub(crate) fn docker_tls_cert_path<E>() -> Option<std::path::PathBuf>
+where
+    E: GetEnvValue,
+{
+    match E::get_env_value("DOCKER_TLS_VERIFY").as_deref() {
+        None | Some("") | Some("0") => return None,
+        Some(_) => {}
+    }
+
+    let cert_path = E::get_env_value("DOCKER_CERT_PATH")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| {
+            std::path::PathBuf::from(E::get_env_value("HOME").unwrap_or_default()).join(".docker")
+        });
+
+    Some(cert_path)
+}
+
+#[cfg(feature = "experimental")]
+fn first_listening_daemon(candidates: Vec<String>) -> Result<String, Vec<String>> {
+    match candidates.iter().find(|address| daemon_listens_on(address)) {
+        Some(address) => Ok(address.clone()),
+        None => Err(candidates),
+    }
+}
+
+/// Checks whether the socket or pipe of a daemon exists, or whether something accepts connections
+/// on its TCP address.
+#[cfg(feature = "experimental")]
+fn daemon_listens_on(address: &str) -> bool {
+    use std::net::{TcpStream, ToSocketAddrs};
+
+    if let Some(socket) = address.strip_prefix("unix://") {
+        return std::path::Path::new(socket).exists();
+    }
+    if let Some(pipe) = address.strip_prefix("npipe://") {
+        return std::path::Path::new(&pipe.replace('/', "\\")).exists();
+    }
 
-    Some(command)
+    address
+        .strip_prefix("tcp://")
+        .and_then(|address| address.to_socket_addrs().ok())
+        .map_or(false, |mut addresses| {
+            addresses.any(|address| TcpStream::connect_timeout(&address, TCP_PROBE_TIMEOUT).is_ok())
+        })
 }
 
 /// Abstracts over reading a value from the environment.
@@ -37,16 +220,38 @@ pub enum Command {
     Keep,
     #[default]
     Remove,
+    /// Removes containers unless the current thread is panicking, which keeps the containers of
+    /// failed tests around for debugging.
+    KeepOnFailure,
+}
+
+impl Command {
+    /// Whether containers and networks should be removed at this point.
+    pub(crate) fn should_remove(&self) -> bool {
+        match self {
+            Command::Keep => false,
+            Command::Remove => true,
+            Command::KeepOnFailure => !std::thread::panicking(),
+        }
+    }
+
+    fn known(s: &str) -> Option<Self> {
+        match s {
+            "keep" => Some(Command::Keep),
+            "remove" => Some(Command::Remove),
+            "keep-on-failure" => Some(Command::KeepOnFailure),
+            _ => None,
+        }
+    }
 }
 
 impl FromStr for Command {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "keep" => Ok(Command::Keep),
-            "remove" => Ok(Command::Remove),
-            other => panic!("unknown command '{other}' provided via TESTCONTAINERS env variable",),
+        match Command::known(s) {
+            Some(command) => Ok(command),
+            None => panic!("{}", unknown_command(s)),
         }
     }
 }
@@ -73,6 +278,27 @@ mod tests {
         let _ = "foobar".parse::<Command>();
     }
 
+    #[derive(Debug)]
+    struct FakeEnvUnknownCommand;
+
+    impl GetEnvValue for FakeEnvUnknownCommand {
+        fn get_env_value(key: &str) -> Option<String> {
+            match key {
+                "TESTCONTAINERS" => Some("foobar".to_owned()),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn try_command_reports_unknown_command() {
+        assert_eq!(try_command::<FakeEnvAlwaysKeep>(), Ok(Some(Command::Keep)));
+        assert_eq!(
+            try_command::<FakeEnvUnknownCommand>(),
+            Err("foobar".to_owned())
+        );
+    }
+
     #[test]
     fn command_looks_up_testcontainers_env_variables() {
         let cmd = command::<FakeEnvAlwaysKeep>().unwrap();
@@ -86,4 +312,192 @@ mod tests {
 
         assert_eq!(cmd, Command::Remove)
     }
+
+    #[cfg(feature = "experimental")]
+    #[derive(Debug)]
+    struct FakeEnvUnixDockerHost;
+
+    #[cfg(feature = "experimental")]
+    impl GetEnvValue for FakeEnvUnixDockerHost {
+        fn get_env_value(key: &str) -> Option<String> {
+            match key {
+                "DOCKER_HOST" => Some("unix:///run/user/1000/docker.sock".to_owned()),
+                _ => None,
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct FakeEnvSocketOverride;
+
+    impl GetEnvValue for FakeEnvSocketOverride {
+        fn get_env_value(key: &str) -> Option<String> {
+            match key {
+                "TESTCONTAINERS_DOCKER_SOCKET_OVERRIDE" => Some("/var/run/docker.sock".to_owned()),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn docker_socket_defaults_to_standard_path() {
+        assert_eq!(
+            docker_socket::<FakeEnvAlwaysKeep>(None),
+            "/var/run/docker.sock"
+        );
+        assert_eq!(
+            docker_socket::<FakeEnvAlwaysKeep>(Some("tcp://10.0.0.5:2375")),
+            "/var/run/docker.sock"
+        )
+    }
+
+    #[test]
+    fn docker_socket_is_taken_from_unix_docker_host() {
+        assert_eq!(
+            docker_socket::<FakeEnvAlwaysKeep>(Some("unix:///run/user/1000/docker.sock")),
+            "/run/user/1000/docker.sock"
+        )
+    }
+
+    #[test]
+    fn docker_socket_override_takes_precedence() {
+        assert_eq!(
+            docker_socket::<FakeEnvSocketOverride>(Some("unix:///run/user/1000/docker.sock")),
+            "/var/run/docker.sock"
+        )
+    }
+
+    #[test]
+    fn ports_are_published_on_host_of_tcp_docker_host() {
+        assert_eq!(published_ports_host(None), "127.0.0.1");
+        assert_eq!(
+            published_ports_host(Some("unix:///var/run/docker.sock")),
+            "127.0.0.1"
+        );
+        assert_eq!(
+            published_ports_host(Some("tcp://10.0.0.5:2376")),
+            "10.0.0.5"
+        );
+        assert_eq!(
+            published_ports_host(Some("tcp://docker.internal")),
+            "docker.internal"
+        );
+        assert_eq!(
+            published_ports_host(Some("tcp://[fd00::5]:2375")),
+            "fd00::5"
+        );
+    }
+
+    #[cfg(feature = "experimental")]
+    #[derive(Debug)]
+    struct FakeEnvRootless;
+
+    #[cfg(feature = "experimental")]
+    impl FakeEnvRootless {
+        fn runtime_dir() -> std::path::PathBuf {
+            std::env::temp_dir().join("testcontainers-rootless-runtime-dir")
+        }
+    }
+
+    #[cfg(feature = "experimental")]
+    impl GetEnvValue for FakeEnvRootless {
+        fn get_env_value(key: &str) -> Option<String> {
+            match key {
+                "XDG_RUNTIME_DIR" => Some(Self::runtime_dir().to_str()?.to_owned()),
+                _ => None,
+            }
+        }
+    }
+
+    #[cfg(feature = "experimental")]
+    #[test]
+    fn docker_host_is_taken_from_env_variable() {
+        assert_eq!(
+            docker_host::<FakeEnvUnixDockerHost>(),
+            Ok("unix:///run/user/1000/docker.sock".to_owned())
+        )
+    }
+
+    #[cfg(all(unix, feature = "experimental"))]
+    #[test]
+    fn docker_host_prefers_rootless_socket() {
+        let runtime_dir = FakeEnvRootless::runtime_dir();
+        std::fs::create_dir_all(&runtime_dir).unwrap();
+        std::fs::write(runtime_dir.join("docker.sock"), "").unwrap();
+
+        assert_eq!(
+            docker_host::<FakeEnvRootless>(),
+            Ok(format!("unix://{}/docker.sock", runtime_dir.display()))
+        )
+    }
+
+    #[cfg(feature = "experimental")]
+    #[derive(Debug)]
+    struct FakeEnvTls;
+
+    #[cfg(feature = "experimental")]
+    impl GetEnvValue for FakeEnvTls {
+        fn get_env_value(key: &str) -> Option<String> {
+            match key {
+                "DOCKER_HOST" => Some("tcp://10.0.0.5:2376".to_owned()),
+                "DOCKER_TLS_VERIFY" => Some("1".to_owned()),
+                "HOME" => Some("/home/tester".to_owned()),
+                _ => None,
+            }
+        }
+    }
+
+    #[cfg(feature = "experimental")]
+    #[test]
+    fn tls_cert_path_defaults_to_docker_config_dir() {
+        assert_eq!(
+            docker_tls_cert_path::<FakeEnvTls>(),
+            Some(std::path::PathBuf::from("/home/tester/.docker"))
+        );
+        assert_eq!(docker_tls_cert_path::<FakeEnvUnixDockerHost>(), None);
+    }
+
+    #[cfg(feature = "experimental")]
+    #[test]
+    fn missing_daemons_are_reported_with_all_attempted_locations() {
+        let candidates = vec![
+            "unix:///does/not/exist/docker.sock".to_owned(),
+            "npipe:////./pipe/does_not_exist".to_owned(),
+        ];
+
+        assert_eq!(first_listening_daemon(candidates.clone()), Err(candidates))
+    }
+
+    #[cfg(feature = "experimental")]
+    #[test]
+    fn daemon_listening_on_tcp_is_the_last_resort() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let tcp_host = format!("tcp://{}", listener.local_addr().unwrap());
+        let candidates = vec![
+            "unix:///does/not/exist/docker.sock".to_owned(),
+            tcp_host.clone(),
+        ];
+
+        assert_eq!(first_listening_daemon(candidates), Ok(tcp_host))
+    }
+
+    #[test]
+    fn keep_on_failure_removes_unless_panicking() {
+        let command = "keep-on-failure".parse::<Command>().unwrap();
+        assert!(command.should_remove());
+
+        struct CheckOnDrop(Command);
+
+        impl Drop for CheckOnDrop {
+            fn drop(&mut self) {
+                assert!(!self.0.should_remove());
+            }
+        }
+
+        let result = std::panic::catch_unwind(|| {
+            let _guard = CheckOnDrop(command);
+            panic!("test failed");
+        });
+        assert!(result.is_err());
+    }
 }
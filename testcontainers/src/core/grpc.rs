@@ -0,0 +1,210 @@
+use crate::core::env;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+use tonic::transport::{Channel, Endpoint};
+use tonic_health::pb::{
+    health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+};
+
+/// Blocks until the gRPC server listening on `host_port` of `host` reports `service` as
+/// `SERVING`.
+///
+/// Used to evaluate [`WaitFor::Grpc`](crate::core::WaitFor::Grpc) for the synchronous clients.
+/// The checks run on a separate thread, so that waiting works regardless of whether the caller
+/// is already running inside a tokio runtime.
+///
+/// # Panics
+///
+/// See [`wait_until_serving`].
+pub(crate) fn block_until_serving(
+    id: &str,
+    host: &str,
+    host_port: u16,
+    service: &str,
+    timeout: Duration,
+    poll_interval: Duration,
+) {
+    let (id, host, service) = (id.to_owned(), host.to_owned(), service.to_owned());
+
+    let waiting = thread::spawn(move || {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start runtime to check gRPC health")
+            .block_on(wait_until_serving(
+                &id,
+                &host,
+                host_port,
+                &service,
+                timeout,
+                poll_interval,
+            ))
+    });
+
+    if let Err(panic) = waiting.join() {
+        std::panic::resume_unwind(panic)
+    }
+}
+
+/// Waits until the gRPC server listening on `host_port` of `host` reports `service` as
+/// `SERVING`, calling the health `Check` RPC every `poll_interval`.
+///
+/// # Panics
+///
+/// If the server doesn't report `SERVING` within `timeout`. The panic message includes the
+/// result of the last check.
+pub(crate) async fn wait_until_serving(
+    id: &str,
+    host: &str,
+    host_port: u16,
+    service: &str,
+    timeout: Duration,
+    poll_interval: Duration,
+) {
+    let endpoint = Endpoint::from_shared(format!("http://{}:{host_port}", env::url_host(host)))
+        .unwrap_or_else(|e| panic!("invalid address of gRPC server on {host}: {e}"));
+    // connects on the first check, and again after the connection is lost
+    let mut client = HealthClient::new(endpoint.connect_lazy());
+    let started = Instant::now();
+
+    // a check cut short by the timeout keeps the result of the previous one
+    let mut last_result = "did not answer".to_owned();
+    loop {
+        let remaining = timeout.saturating_sub(started.elapsed());
+        match tokio::time::timeout(remaining, check(&mut client, service)).await {
+            Ok(Ok(ServingStatus::Serving)) => return,
+            Ok(Ok(status)) => last_result = format!("reported {}", status.as_str_name()),
+            Ok(Err(e)) => last_result = e,
+            Err(_) => {}
+        }
+
+        if started.elapsed() >= timeout {
+            panic!(
+                "container {id} did not report gRPC service `{service}` as SERVING within {timeout:?}, last check {last_result}"
+            );
+        }
+
+        sleep(poll_interval).await;
+    }
+}
+
+/// Calls the health `Check` RPC for `service` and returns the reported status.
+async fn check(client: &mut HealthClient<Channel>, service: &str) -> Result<ServingStatus, String> {
+    let request = HealthCheckRequest {
+        service: service.to_owned(),
+    };
+    let response = client.check(request).await.map_err(|status| {
+        format!(
+            "failed with gRPC status {}: {}",
+            i32::from(status.code()),
+            status.message()
+        )
+    })?;
+
+    let status = response.into_inner().status;
+    ServingStatus::from_i32(status).ok_or_else(|| format!("reported unknown status {status}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::transport::Server;
+    use tonic_health::server::{health_reporter, HealthReporter};
+
+    /// Starts a gRPC health server on a random port, reporting the overall health as `status`.
+    /// Returns the port of the server along with the reporter to change the health with.
+    async fn health_server(status: tonic_health::ServingStatus) -> (u16, HealthReporter) {
+        let (mut reporter, service) = health_reporter();
+        reporter.set_service_status("", status).await;
+
+        let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(
+            Server::builder()
+                .add_service(service)
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        (port, reporter)
+    }
+
+    #[tokio::test]
+    async fn wait_should_poll_until_server_reports_serving() {
+        let (port, mut reporter) = health_server(tonic_health::ServingStatus::NotServing).await;
+        reporter
+            .set_service_status("greeter", tonic_health::ServingStatus::NotServing)
+            .await;
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(100)).await;
+            reporter
+                .set_service_status("greeter", tonic_health::ServingStatus::Serving)
+                .await;
+        });
+
+        wait_until_serving(
+            "abc",
+            "127.0.0.1",
+            port,
+            "greeter",
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "container abc did not report gRPC service `` as SERVING within 100ms, last check reported NOT_SERVING"
+    )]
+    async fn wait_should_give_up_after_timeout() {
+        let (port, _reporter) = health_server(tonic_health::ServingStatus::NotServing).await;
+
+        wait_until_serving(
+            "abc",
+            "127.0.0.1",
+            port,
+            "",
+            Duration::from_millis(100),
+            Duration::from_millis(10),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "last check failed with gRPC status 5: ")]
+    async fn wait_should_report_unknown_service() {
+        let (port, _reporter) = health_server(tonic_health::ServingStatus::Serving).await;
+
+        wait_until_serving(
+            "abc",
+            "127.0.0.1",
+            port,
+            "unknown",
+            Duration::from_millis(100),
+            Duration::from_millis(10),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn wait_should_reach_server_on_given_host() {
+        let (port, _reporter) = health_server(tonic_health::ServingStatus::Serving).await;
+
+        wait_until_serving(
+            "abc",
+            "localhost",
+            port,
+            "",
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+        )
+        .await;
+    }
+}
@@ -0,0 +1,322 @@
+//! Gzip compression of archives copied out of containers, see
+//! [`CopyFromOptions::with_gzip`](crate::core::CopyFromOptions::with_gzip).
+//!
+//! The input is compressed in chunks, each one a deflate block using the fixed Huffman codes of
+//! RFC 1951. This keeps the encoder small, at the cost of a slightly worse ratio than `gzip`
+//! achieves with its own codes. Matches are only searched for within a chunk.
+
+/// The largest distance of a match that deflate can encode.
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// How many earlier positions with the same hash are compared when searching for a match.
+const MAX_CHAIN: usize = 32;
+const HASH_BITS: u32 = 15;
+const END_OF_BLOCK: u16 = 256;
+const NO_POSITION: usize = usize::MAX;
+
+/// Magic bytes, deflate, no flags, no modification time, no extra flags and an unknown OS.
+const HEADER: [u8; 10] = [0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+/// Compresses everything written to it into a gzip stream.
+pub(crate) struct Encoder {
+    chunk_size: usize,
+    pending: Vec<u8>,
+    out: BitWriter,
+    crc: u32,
+    size: u32,
+}
+
+impl Encoder {
+    /// Creates an encoder that compresses its input in chunks of `chunk_size` bytes.
+    pub(crate) fn new(chunk_size: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+        let mut out = BitWriter::default();
+        out.bytes.extend_from_slice(&HEADER);
+
+        Self {
+            chunk_size,
+            pending: Vec::with_capacity(chunk_size),
+            out,
+            crc: !0,
+            size: 0,
+        }
+    }
+
+    pub(crate) fn write(&mut self, mut data: &[u8]) {
+        self.crc = update_crc(self.crc, data);
+        // the size is stored modulo 2^32
+        self.size = self.size.wrapping_add(data.len() as u32);
+
+        while !data.is_empty() {
+            let free = self.chunk_size - self.pending.len();
+            let (chunk, rest) = data.split_at(free.min(data.len()));
+            self.pending.extend_from_slice(chunk);
+            data = rest;
+
+            if self.pending.len() == self.chunk_size {
+                self.compress_pending(false);
+            }
+        }
+    }
+
+    /// Compresses the rest of the input and returns the complete gzip stream.
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        self.compress_pending(true);
+
+        let mut bytes = self.out.into_bytes();
+        bytes.extend_from_slice(&(!self.crc).to_le_bytes());
+        bytes.extend_from_slice(&self.size.to_le_bytes());
+        bytes
+    }
+
+    fn compress_pending(&mut self, last: bool) {
+        self.out.write(u32::from(last), 1);
+        // the block type of fixed Huffman codes
+        self.out.write(1, 2);
+
+        let data = &self.pending;
+        let mut head = vec![NO_POSITION; 1 << HASH_BITS];
+        let mut previous = vec![NO_POSITION; data.len()];
+        let mut position = 0;
+        while position < data.len() {
+            let (length, distance) = longest_match(data, position, &head, &previous);
+            let consumed = if length >= MIN_MATCH {
+                self.out.write_match(length, distance);
+                length
+            } else {
+                self.out.write_symbol(u16::from(data[position]));
+                1
+            };
+
+            for inserted in position..position + consumed {
+                if inserted + MIN_MATCH <= data.len() {
+                    let hash = hash(&data[inserted..]);
+                    previous[inserted] = head[hash];
+                    head[hash] = inserted;
+                }
+            }
+            position += consumed;
+        }
+
+        self.out.write_symbol(END_OF_BLOCK);
+        self.pending.clear();
+    }
+}
+
+/// Returns the length and distance of the longest earlier match of the bytes at `position`, or a
+/// length of zero if there is none.
+fn longest_match(
+    data: &[u8],
+    position: usize,
+    head: &[usize],
+    previous: &[usize],
+) -> (usize, usize) {
+    if position + MIN_MATCH > data.len() {
+        return (0, 0);
+    }
+
+    let max_length = MAX_MATCH.min(data.len() - position);
+    let mut best = (0, 0);
+    let mut candidate = head[hash(&data[position..])];
+    for _ in 0..MAX_CHAIN {
+        if candidate == NO_POSITION || position - candidate > WINDOW_SIZE {
+            break;
+        }
+
+        let length = data[candidate..]
+            .iter()
+            .zip(&data[position..position + max_length])
+            .take_while(|(earlier, current)| earlier == current)
+            .count();
+        if length > best.0 {
+            best = (length, position - candidate);
+            if length == max_length {
+                break;
+            }
+        }
+        candidate = previous[candidate];
+    }
+
+    best
+}
+
+fn hash(bytes: &[u8]) -> usize {
+    let hash = (usize::from(bytes[0]) << 10) ^ (usize::from(bytes[1]) << 5) ^ usize::from(bytes[2]);
+    hash & ((1 << HASH_BITS) - 1)
+}
+
+/// Writes bits starting at the least significant bit of each byte, as deflate requires.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u64,
+    buffered: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u32) {
+        self.buffer |= u64::from(value) << self.buffered;
+        self.buffered += bits;
+        while self.buffered >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.buffered -= 8;
+        }
+    }
+
+    /// Writes a Huffman code, which unlike other values starts with its most significant bit.
+    fn write_code(&mut self, code: u16, bits: u32) {
+        self.write(u32::from(code.reverse_bits() >> (16 - bits)), bits)
+    }
+
+    /// Writes a literal byte, the end of a block or a match length with its fixed Huffman code.
+    fn write_symbol(&mut self, symbol: u16) {
+        match symbol {
+            0..=143 => self.write_code(0x30 + symbol, 8),
+            144..=255 => self.write_code(0x190 + symbol - 144, 9),
+            256..=279 => self.write_code(symbol - 256, 7),
+            _ => self.write_code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn write_match(&mut self, length: usize, distance: usize) {
+        let index = LENGTH_BASE
+            .iter()
+            .rposition(|&base| usize::from(base) <= length)
+            .expect("matches are at least 3 bytes long");
+        self.write_symbol(257 + index as u16);
+        self.write(
+            (length - usize::from(LENGTH_BASE[index])) as u32,
+            LENGTH_EXTRA_BITS[index],
+        );
+
+        let index = DISTANCE_BASE
+            .iter()
+            .rposition(|&base| usize::from(base) <= distance)
+            .expect("matches are at least 1 byte apart");
+        // distances have fixed codes of 5 bits
+        self.write_code(index as u16, 5);
+        self.write(
+            (distance - usize::from(DISTANCE_BASE[index])) as u32,
+            DISTANCE_EXTRA_BITS[index],
+        );
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        if self.buffered > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+
+        self.bytes
+    }
+}
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut crc = n as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[n] = crc;
+        n += 1;
+    }
+
+    table
+}
+
+fn update_crc(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &byte| {
+        CRC_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    /// Decompresses `compressed` with the `gzip` command, failing if it is not a valid gzip
+    /// stream.
+    pub(crate) fn gunzip(compressed: &[u8]) -> Vec<u8> {
+        let mut gzip = Command::new("gzip")
+            .arg("--decompress")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("failed to run gzip");
+        gzip.stdin.take().unwrap().write_all(compressed).unwrap();
+        let output = gzip.wait_with_output().unwrap();
+        assert!(
+            output.status.success(),
+            "invalid gzip stream: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        output.stdout
+    }
+
+    fn compress(data: &[u8], chunk_size: usize, writes: usize) -> Vec<u8> {
+        let mut encoder = Encoder::new(chunk_size);
+        for part in data.chunks((data.len() / writes).max(1)) {
+            encoder.write(part);
+        }
+        encoder.finish()
+    }
+
+    #[test]
+    fn compressed_data_should_decompress_to_the_input() {
+        let data = (0..20_000u32)
+            .flat_map(|n| format!("line {} of {}\n", n % 97, n % 13).into_bytes())
+            .collect::<Vec<_>>();
+
+        let compressed = compress(&data, 64 * 1024, 7);
+
+        assert!(compressed.len() < data.len() / 4);
+        assert_eq!(gunzip(&compressed), data);
+    }
+
+    #[test]
+    fn input_should_be_compressed_in_chunks_of_any_size() {
+        let data = (0..=255u8).cycle().take(10_000).collect::<Vec<_>>();
+
+        for chunk_size in [100, 4096, 1 << 20] {
+            assert_eq!(gunzip(&compress(&data, chunk_size, 3)), data);
+        }
+    }
+
+    #[test]
+    fn empty_input_should_be_a_valid_stream() {
+        assert_eq!(gunzip(&Encoder::new(16).finish()), b"");
+    }
+}
@@ -0,0 +1,109 @@
+use bollard_stubs::models::{ContainerInspectResponse, HealthcheckResult};
+use std::fmt::Write;
+
+/// How many of the most recent log lines are included in a [`HealthReport`].
+pub(crate) const REPORT_LOG_LINES: usize = 20;
+
+/// Diagnostics of a container that didn't become healthy.
+///
+/// Used by [`WaitFor::HealthcheckVerbose`](crate::core::WaitFor::HealthcheckVerbose) to make
+/// startup failures actionable without having to reproduce them locally.
+#[derive(Debug)]
+pub(crate) struct HealthReport<'a> {
+    pub(crate) container_id: &'a str,
+    pub(crate) reason: String,
+    pub(crate) stdout: String,
+    pub(crate) stderr: String,
+    pub(crate) health_log: Vec<HealthcheckResult>,
+}
+
+impl HealthReport<'_> {
+    pub(crate) fn render(&self) -> String {
+        let mut report = format!("container {} {}", self.container_id, self.reason);
+
+        for (source, output) in [("stdout", &self.stdout), ("stderr", &self.stderr)] {
+            let _ = write!(
+                report,
+                "\n--- last {REPORT_LOG_LINES} lines of {source} ---\n{}",
+                output.trim_end()
+            );
+        }
+
+        report.push_str("\n--- health log ---");
+        for result in &self.health_log {
+            let _ = write!(
+                report,
+                "\nexit code {}: {}",
+                result.exit_code.unwrap_or_default(),
+                result.output.as_deref().unwrap_or_default().trim_end()
+            );
+        }
+
+        report
+    }
+}
+
+/// The first API version of the Docker daemon that supports
+/// [`Healthcheck::start_interval`](crate::core::Healthcheck::start_interval).
+const START_INTERVAL_API_VERSION: (u32, u32) = (1, 44);
+
+/// Whether a Docker daemon with the given API version, e.g. `1.43`, supports the start interval
+/// of healthchecks.
+pub(crate) fn supports_start_interval(api_version: &str) -> bool {
+    let version = api_version
+        .trim()
+        .split_once('.')
+        .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)));
+
+    matches!(version, Some(version) if version >= START_INTERVAL_API_VERSION)
+}
+
+/// Returns the results of the most recent healthchecks of a container, oldest first.
+pub(crate) fn health_log(details: ContainerInspectResponse) -> Vec<HealthcheckResult> {
+    details
+        .state
+        .and_then(|state| state.health)
+        .and_then(|health| health.log)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_interval_should_be_supported_from_api_version_1_44() {
+        assert!(!supports_start_interval("1.43"));
+        assert!(supports_start_interval("1.44"));
+        assert!(supports_start_interval("1.45\n"));
+        assert!(supports_start_interval("2.0"));
+        assert!(!supports_start_interval("unknown"));
+    }
+
+    #[test]
+    fn report_should_include_logs_and_health_log() {
+        let report = HealthReport {
+            container_id: "fake",
+            reason: "did not become healthy within 1s".to_owned(),
+            stdout: "starting\nlistening\n".to_owned(),
+            stderr: "".to_owned(),
+            health_log: vec![HealthcheckResult {
+                exit_code: Some(1),
+                output: Some("connection refused\n".to_owned()),
+                ..Default::default()
+            }],
+        };
+
+        assert_eq!(
+            report.render(),
+            "container fake did not become healthy within 1s\n\
+             --- last 20 lines of stdout ---\n\
+             starting\n\
+             listening\n\
+             --- last 20 lines of stderr ---\n\
+             \n\
+             --- health log ---\n\
+             exit code 1: connection refused"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use crate::core::env;
+use std::{
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+/// The longest pause between two requests of a [`HttpProbe`], unless the poll interval of the
+/// container is even longer.
+const MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+/// A request that has to be answered as expected to satisfy
+/// [`WaitFor::Http`](crate::core::WaitFor::Http).
+#[derive(Debug)]
+pub(crate) struct HttpProbe<'a> {
+    pub(crate) path: &'a str,
+    pub(crate) expected_status: u16,
+    pub(crate) body_contains: Option<&'a str>,
+}
+
+impl HttpProbe<'_> {
+    /// Sends the request to `host_port` on `host` and checks the response, giving up on
+    /// connecting, writing or reading after `timeout`.
+    ///
+    /// The request is written by hand, so that the synchronous clients don't depend on an HTTP
+    /// client.
+    pub(crate) fn send(&self, host: &str, host_port: u16, timeout: Duration) -> Result<(), String> {
+        // a zero timeout is rejected by the socket
+        let timeout = timeout.max(Duration::from_millis(1));
+
+        let mut response = Vec::new();
+        connect(host, host_port, timeout)
+            .and_then(|mut stream| {
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                stream.write_all(self.request(host, host_port).as_bytes())?;
+                stream.read_to_end(&mut response)
+            })
+            .map_err(|e| format!("failed: {e}"))?;
+
+        self.check(&response)
+    }
+
+    /// Like [`HttpProbe::send`], without blocking the runtime.
+    #[cfg(feature = "experimental")]
+    pub(crate) async fn send_async(
+        &self,
+        host: &str,
+        host_port: u16,
+        timeout: Duration,
+    ) -> Result<(), String> {
+        let uri = format!("http://{}:{host_port}{}", env::url_host(host), self.path)
+            .parse::<hyper::Uri>()
+            .map_err(|e| format!("failed: {e}"))?;
+
+        let exchange = async {
+            let response = hyper::Client::new().get(uri).await?;
+            let status = response.status().as_u16();
+            let body = hyper::body::to_bytes(response.into_body()).await?;
+
+            Ok::<_, hyper::Error>((status, body))
+        };
+
+        let (status, body) = tokio::time::timeout(timeout, exchange)
+            .await
+            .map_err(|_| "did not answer".to_owned())?
+            .map_err(|e| format!("failed: {e}"))?;
+
+        self.check_status_and_body(status, &String::from_utf8_lossy(&body))
+    }
+
+    /// The panic message of a container that didn't answer the request as expected within
+    /// `timeout`, the last attempt having resulted in `reason`.
+    pub(crate) fn failure(&self, container_id: &str, timeout: Duration, reason: &str) -> String {
+        format!(
+            "container {container_id} did not answer GET {} with status {} within {timeout:?}, last request {reason}",
+            self.path, self.expected_status
+        )
+    }
+
+    fn request(&self, host: &str, host_port: u16) -> String {
+        // HTTP/1.0 keeps servers from chunking the body and makes them close the connection
+        format!(
+            "GET {} HTTP/1.0\r\nHost: {}:{host_port}\r\n\r\n",
+            self.path,
+            env::url_host(host)
+        )
+    }
+
+    /// Checks the raw `response` to the request.
+    fn check(&self, response: &[u8]) -> Result<(), String> {
+        if response.is_empty() {
+            return Err("got no response".to_owned());
+        }
+
+        let response = String::from_utf8_lossy(response);
+        let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+        let status = head
+            .split(' ')
+            .nth(1)
+            .and_then(|status| status.parse::<u16>().ok())
+            .ok_or_else(|| "got an invalid response".to_owned())?;
+
+        self.check_status_and_body(status, body)
+    }
+
+    /// Checks the `status` and `body` of the response to the request.
+    fn check_status_and_body(&self, status: u16, body: &str) -> Result<(), String> {
+        if status != self.expected_status {
+            return Err(format!("got status {status}"));
+        }
+
+        match self.body_contains {
+            Some(expected) if !body.contains(expected) => {
+                Err(format!("got a body without `{expected}`"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Connects to `host_port` on the first address of `host` that accepts a connection within
+/// `timeout`.
+fn connect(host: &str, host_port: u16, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for address in (host, host_port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, format!("{host} has no address"))
+    }))
+}
+
+/// The pause before the next request after waiting for `delay`, doubling it up to a second unless
+/// the `poll_interval` is even longer.
+pub(crate) fn backoff(delay: Duration, poll_interval: Duration) -> Duration {
+    (delay * 2).min(MAX_BACKOFF.max(poll_interval))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        net::{Ipv4Addr, TcpListener},
+        thread,
+    };
+
+    const HEALTH: HttpProbe<'static> = HttpProbe {
+        path: "/health",
+        expected_status: 200,
+        body_contains: Some("UP"),
+    };
+
+    /// Answers a single request with `response` and returns the port it listens on.
+    fn fake_server(response: &'static str) -> u16 {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let read = stream.read(&mut request).unwrap();
+            assert!(request[..read].starts_with(b"GET /health HTTP/1."));
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+
+        port
+    }
+
+    #[test]
+    fn response_with_expected_status_and_body_should_pass() {
+        let port = fake_server("HTTP/1.0 200 OK\r\nContent-Length: 15\r\n\r\n{\"status\":\"UP\"}");
+
+        assert_eq!(
+            HEALTH.send("localhost", port, Duration::from_secs(5)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn response_with_other_status_should_fail() {
+        let port = fake_server("HTTP/1.1 503 Service Unavailable\r\n\r\n{\"status\":\"UP\"}");
+
+        assert_eq!(
+            HEALTH.send("127.0.0.1", port, Duration::from_secs(5)),
+            Err("got status 503".to_owned())
+        );
+    }
+
+    #[test]
+    fn response_without_expected_body_should_fail() {
+        let port = fake_server("HTTP/1.1 200 OK\r\n\r\n{\"status\":\"DOWN\"}");
+
+        assert_eq!(
+            HEALTH.send("127.0.0.1", port, Duration::from_secs(5)),
+            Err("got a body without `UP`".to_owned())
+        );
+    }
+
+    #[test]
+    fn request_should_name_host() {
+        assert_eq!(
+            HEALTH.request("::1", 8080),
+            "GET /health HTTP/1.0\r\nHost: [::1]:8080\r\n\r\n"
+        );
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn async_response_with_expected_status_and_body_should_pass() {
+        let port = fake_server("HTTP/1.1 200 OK\r\nContent-Length: 15\r\n\r\n{\"status\":\"UP\"}");
+
+        assert_eq!(
+            HEALTH
+                .send_async("localhost", port, Duration::from_secs(5))
+                .await,
+            Ok(())
+        );
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn async_response_with_other_status_should_fail() {
+        let port = fake_server(
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 15\r\n\r\n{\"status\":\"UP\"}",
+        );
+
+        assert_eq!(
+            HEALTH
+                .send_async("127.0.0.1", port, Duration::from_secs(5))
+                .await,
+            Err("got status 503".to_owned())
+        );
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn async_response_without_expected_body_should_fail() {
+        let port =
+            fake_server("HTTP/1.1 200 OK\r\nContent-Length: 17\r\n\r\n{\"status\":\"DOWN\"}");
+
+        assert_eq!(
+            HEALTH
+                .send_async("127.0.0.1", port, Duration::from_secs(5))
+                .await,
+            Err("got a body without `UP`".to_owned())
+        );
+    }
+
+    #[test]
+    fn backoff_should_double_up_to_a_second() {
+        let poll_interval = Duration::from_millis(100);
+
+        assert_eq!(
+            backoff(poll_interval, poll_interval),
+            Duration::from_millis(200)
+        );
+        assert_eq!(
+            backoff(Duration::from_millis(800), poll_interval),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            backoff(Duration::from_secs(2), Duration::from_secs(2)),
+            Duration::from_secs(2)
+        );
+    }
+}
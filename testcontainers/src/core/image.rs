@@ -1,12 +1,23 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet, HashMap},
     env::var,
     fmt::{Debug, Display},
     net::IpAddr,
+    sync::Arc,
     time::Duration,
 };
 
-use super::ports::Ports;
+use super::{
+    env::{GetEnvValue, DEFAULT_DOCKER_SOCKET},
+    logs::{LogConsumer, LogPattern},
+    ports::Ports,
+};
+
+/// The default interval in which a container is inspected while waiting for it to become ready.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The label [`run!`](crate::run) attaches to containers to record the call site that started them.
+pub const SOURCE_LABEL: &str = "org.testcontainers.source";
 
 /// Represents a docker image.
 ///
@@ -165,6 +176,248 @@ impl Display for Host {
     }
 }
 
+/// The access mode of a volume mounted into a container.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BindMode {
+    ReadOnly,
+    ReadWrite,
+}
+
+impl Display for BindMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindMode::ReadOnly => write!(f, "ro"),
+            BindMode::ReadWrite => write!(f, "rw"),
+        }
+    }
+}
+
+/// How the content of a bind mount is relabeled on SELinux hosts, see
+/// [`RunnableImage::with_relabeled_bind_mount`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SelinuxRelabel {
+    /// Relabels the mounted content so that it can be shared between containers.
+    Shared,
+    /// Relabels the mounted content so that only this container can use it.
+    Private,
+}
+
+impl Display for SelinuxRelabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelinuxRelabel::Shared => write!(f, "z"),
+            SelinuxRelabel::Private => write!(f, "Z"),
+        }
+    }
+}
+
+/// A Linux capability that can be added to or dropped from a container.
+///
+/// Capabilities that are not listed can be created from their name, e.g.
+/// `Capability::from("BPF")`.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Capability {
+    /// All capabilities, e.g. to drop every capability before adding back the ones a container
+    /// needs.
+    All,
+    AuditWrite,
+    Chown,
+    DacOverride,
+    Fowner,
+    IpcLock,
+    Kill,
+    Mknod,
+    NetAdmin,
+    NetBindService,
+    NetRaw,
+    Setgid,
+    Setuid,
+    SysAdmin,
+    SysNice,
+    SysPtrace,
+    SysResource,
+    SysTime,
+    /// A capability that has no dedicated variant, named without the `CAP_` prefix.
+    Other(String),
+}
+
+impl Capability {
+    fn name(&self) -> &str {
+        match self {
+            Capability::All => "ALL",
+            Capability::AuditWrite => "AUDIT_WRITE",
+            Capability::Chown => "CHOWN",
+            Capability::DacOverride => "DAC_OVERRIDE",
+            Capability::Fowner => "FOWNER",
+            Capability::IpcLock => "IPC_LOCK",
+            Capability::Kill => "KILL",
+            Capability::Mknod => "MKNOD",
+            Capability::NetAdmin => "NET_ADMIN",
+            Capability::NetBindService => "NET_BIND_SERVICE",
+            Capability::NetRaw => "NET_RAW",
+            Capability::Setgid => "SETGID",
+            Capability::Setuid => "SETUID",
+            Capability::SysAdmin => "SYS_ADMIN",
+            Capability::SysNice => "SYS_NICE",
+            Capability::SysPtrace => "SYS_PTRACE",
+            Capability::SysResource => "SYS_RESOURCE",
+            Capability::SysTime => "SYS_TIME",
+            Capability::Other(name) => name,
+        }
+    }
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // Docker only accepts `ALL` without the prefix
+            Capability::All => write!(f, "ALL"),
+            _ => write!(f, "CAP_{}", self.name()),
+        }
+    }
+}
+
+impl From<&str> for Capability {
+    fn from(name: &str) -> Self {
+        let name = name.to_uppercase();
+        let name = name.strip_prefix("CAP_").unwrap_or(&name);
+
+        match name {
+            "ALL" => Capability::All,
+            "AUDIT_WRITE" => Capability::AuditWrite,
+            "CHOWN" => Capability::Chown,
+            "DAC_OVERRIDE" => Capability::DacOverride,
+            "FOWNER" => Capability::Fowner,
+            "IPC_LOCK" => Capability::IpcLock,
+            "KILL" => Capability::Kill,
+            "MKNOD" => Capability::Mknod,
+            "NET_ADMIN" => Capability::NetAdmin,
+            "NET_BIND_SERVICE" => Capability::NetBindService,
+            "NET_RAW" => Capability::NetRaw,
+            "SETGID" => Capability::Setgid,
+            "SETUID" => Capability::Setuid,
+            "SYS_ADMIN" => Capability::SysAdmin,
+            "SYS_NICE" => Capability::SysNice,
+            "SYS_PTRACE" => Capability::SysPtrace,
+            "SYS_RESOURCE" => Capability::SysResource,
+            "SYS_TIME" => Capability::SysTime,
+            _ => Capability::Other(name.to_owned()),
+        }
+    }
+}
+
+/// Limits of the resources a container may use, see [`RunnableImage::with_resources`].
+///
+/// Limits that are `None` are left to the defaults of the Docker daemon.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Resources {
+    /// Hard limit of the memory in bytes.
+    pub memory: Option<i64>,
+    /// Limit of the memory plus swap in bytes, `-1` for unlimited swap.
+    pub memory_swap: Option<i64>,
+    /// CPU quota in units of 10<sup>-9</sup> CPUs, e.g. `1_500_000_000` for one and a half CPUs.
+    pub nano_cpus: Option<i64>,
+    /// CPU shares relative to other containers.
+    pub cpu_shares: Option<i64>,
+    /// Maximum number of processes, `-1` for unlimited processes.
+    pub pids_limit: Option<i64>,
+}
+
+/// A healthcheck that is defined when a container is started, see [`RunnableImage::with_healthcheck`].
+///
+/// Settings that are `None` are left to the defaults of the Docker daemon.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Healthcheck {
+    /// The test to perform in the form expected by Docker, e.g.
+    /// `["CMD-SHELL", "curl -f http://localhost/ || exit 1"]`.
+    pub test: Vec<String>,
+    /// Time between two checks.
+    pub interval: Option<Duration>,
+    /// Time after which a check is considered to have failed.
+    pub timeout: Option<Duration>,
+    /// Number of consecutive failures after which the container is reported as unhealthy.
+    pub retries: Option<i64>,
+    /// Time the container is given to start up, failures during this period are not counted.
+    pub start_period: Option<Duration>,
+    /// Time between two checks during the start period.
+    ///
+    /// Requires Docker 25 (API version 1.44) or newer and is ignored on older daemons. Only the
+    /// [`Cli`](crate::clients::Cli) client sends it, the models of the Docker API used by the
+    /// async [`Http`](crate::clients::Http) client predate it.
+    pub start_interval: Option<Duration>,
+}
+
+/// The command of a container together with the user and working directory it runs with, see
+/// [`RunnableImage::with_exec_spec`].
+///
+/// Settings that are `None` are left to the defaults of the image.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ExecSpec {
+    /// The command to run, replacing the arguments of the image if not empty.
+    pub cmd: Vec<String>,
+    /// The user to run the command as, e.g. `1000:1000` or `postgres`.
+    pub user: Option<String>,
+    /// The working directory to run the command in.
+    pub working_dir: Option<String>,
+}
+
+/// Computes environment variables when a container is started.
+#[derive(Clone)]
+struct EnvProvider(Arc<dyn Fn() -> HashMap<String, String> + Send + Sync>);
+
+impl Debug for EnvProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EnvProvider").finish()
+    }
+}
+
+/// Runs against a container after it has been created and before it is started.
+#[cfg(feature = "experimental")]
+pub(crate) struct PreStartHook(Box<PreStartFn>);
+
+#[cfg(feature = "experimental")]
+type PreStartFn = dyn FnOnce(crate::core::CreatedContainer) -> futures::future::BoxFuture<'static, ()>
+    + Send
+    + Sync;
+
+#[cfg(feature = "experimental")]
+impl PreStartHook {
+    pub(crate) fn call(
+        self,
+        container: crate::core::CreatedContainer,
+    ) -> futures::future::BoxFuture<'static, ()> {
+        (self.0)(container)
+    }
+}
+
+#[cfg(feature = "experimental")]
+impl Debug for PreStartHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreStartHook").finish()
+    }
+}
+
+/// Adjusts the configuration a container is created with.
+#[cfg(feature = "experimental")]
+pub(crate) struct ConfigHook(Box<ConfigFn>);
+
+#[cfg(feature = "experimental")]
+type ConfigFn = dyn FnOnce(&mut bollard::container::Config<String>) + Send + Sync;
+
+#[cfg(feature = "experimental")]
+impl ConfigHook {
+    pub(crate) fn call(self, config: &mut bollard::container::Config<String>) {
+        (self.0)(config)
+    }
+}
+
+#[cfg(feature = "experimental")]
+impl Debug for ConfigHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigHook").finish()
+    }
+}
+
 #[must_use]
 #[derive(Debug)]
 pub struct RunnableImage<I: Image> {
@@ -173,13 +426,52 @@ pub struct RunnableImage<I: Image> {
     image_tag: Option<String>,
     container_name: Option<String>,
     network: Option<String>,
+    network_driver: Option<String>,
+    network_driver_opts: BTreeMap<String, String>,
+    network_aliases: BTreeMap<String, Vec<String>>,
     env_vars: BTreeMap<String, String>,
+    env_vars_from_host: BTreeSet<String>,
+    env_providers: Vec<EnvProvider>,
     hosts: BTreeMap<String, Host>,
     volumes: BTreeMap<String, String>,
+    volumes_from: BTreeMap<String, BindMode>,
+    mount_owners: BTreeMap<String, String>,
+    docker_socket: bool,
     ports: Option<Vec<Port>>,
     run_options: BTreeMap<String, String>,
     privileged: bool,
+    runtime: Option<String>,
+    tty: bool,
+    console_size: Option<(u16, u16)>,
+    expose_on_localhost: bool,
+    publish_ports: bool,
     shm_size: Option<u64>,
+    tmpfs: BTreeMap<String, String>,
+    memory_reservation: Option<i64>,
+    platform: Option<String>,
+    metadata: BTreeMap<String, String>,
+    labels: BTreeMap<String, String>,
+    hostname: Option<String>,
+    domainname: Option<String>,
+    cap_add: BTreeSet<Capability>,
+    cap_drop: BTreeSet<Capability>,
+    userns_mode: Option<String>,
+    healthcheck: Option<Healthcheck>,
+    exec_spec: Option<ExecSpec>,
+    default_cmd: bool,
+    #[cfg(feature = "experimental")]
+    pre_start_hook: Option<PreStartHook>,
+    #[cfg(feature = "experimental")]
+    config_hook: Option<ConfigHook>,
+    storage_opts: BTreeMap<String, String>,
+    remove_volumes: bool,
+    autoheal: Option<u32>,
+    startup_retries: u32,
+    resources: Resources,
+    poll_interval: Duration,
+    inspect_cache_ttl: Duration,
+    log_consumers: Vec<Arc<dyn LogConsumer>>,
+    log_buffer: Option<usize>,
 }
 
 impl<I: Image> RunnableImage<I> {
@@ -195,6 +487,21 @@ impl<I: Image> RunnableImage<I> {
         &self.network
     }
 
+    /// Driver used when the network of this image has to be created, e.g. `bridge` or `macvlan`.
+    pub fn network_driver(&self) -> &Option<String> {
+        &self.network_driver
+    }
+
+    pub fn network_driver_opts(&self) -> Box<dyn Iterator<Item = (&String, &String)> + '_> {
+        Box::new(self.network_driver_opts.iter())
+    }
+
+    /// Additional networks of the container along with its aliases on them, see
+    /// [`RunnableImage::with_network_alias`].
+    pub fn network_aliases(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.network_aliases
+    }
+
     pub fn container_name(&self) -> &Option<String> {
         &self.container_name
     }
@@ -203,6 +510,27 @@ impl<I: Image> RunnableImage<I> {
         Box::new(self.image.env_vars().chain(self.env_vars.iter()))
     }
 
+    /// Resolves the environment variables that are only known when the container is started,
+    /// i.e. the ones forwarded from the host and the ones returned by environment providers.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if one of the forwarded variables is not set on the host.
+    pub(crate) fn runtime_env_vars<E: GetEnvValue>(&self) -> Vec<(String, String)> {
+        let from_host = self.env_vars_from_host.iter().map(|name| {
+            let value = E::get_env_value(name)
+                .unwrap_or_else(|| panic!("environment variable `{name}` is not set on the host"));
+
+            (name.clone(), value)
+        });
+        let provided = self
+            .env_providers
+            .iter()
+            .flat_map(|provider| (provider.0)());
+
+        from_host.chain(provided).collect()
+    }
+
     pub fn hosts(&self) -> Box<dyn Iterator<Item = (&String, &Host)> + '_> {
         Box::new(self.hosts.iter())
     }
@@ -211,6 +539,17 @@ impl<I: Image> RunnableImage<I> {
         Box::new(self.image.volumes().chain(self.volumes.iter()))
     }
 
+    /// Paths in the container along with the user that is made their owner once the container
+    /// runs, see [`RunnableImage::with_owned_bind_mount`].
+    pub fn mount_owners(&self) -> &BTreeMap<String, String> {
+        &self.mount_owners
+    }
+
+    /// Containers whose volumes are mounted into this container, along with the access mode.
+    pub fn volumes_from(&self) -> Box<dyn Iterator<Item = (&String, &BindMode)> + '_> {
+        Box::new(self.volumes_from.iter())
+    }
+
     pub fn ports(&self) -> &Option<Vec<Port>> {
         &self.ports
     }
@@ -223,11 +562,161 @@ impl<I: Image> RunnableImage<I> {
         self.privileged
     }
 
+    /// Container runtime the container runs with, see [`RunnableImage::with_runtime`].
+    pub fn runtime(&self) -> &Option<String> {
+        &self.runtime
+    }
+
+    /// Whether a pseudo-TTY is allocated for the container.
+    pub fn tty(&self) -> bool {
+        self.tty
+    }
+
+    /// Size of the TTY of the container as height and width in characters.
+    pub fn console_size(&self) -> Option<(u16, u16)> {
+        self.console_size
+    }
+
+    /// Whether exposed ports are only published on the loopback interface of the host.
+    pub fn expose_on_localhost(&self) -> bool {
+        self.expose_on_localhost
+    }
+
+    /// Whether ports of the container are published on the host, see
+    /// [`RunnableImage::with_no_published_ports`]
+    pub fn publish_ports(&self) -> bool {
+        self.publish_ports
+    }
+
     /// Shared memory size in bytes
     pub fn shm_size(&self) -> Option<u64> {
         self.shm_size
     }
 
+    /// tmpfs mounts of the container along with their mount options, e.g. `size=67108864`.
+    pub fn tmpfs(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.tmpfs.iter()
+    }
+
+    /// Soft limit of the memory of the container in bytes
+    pub fn memory_reservation(&self) -> Option<i64> {
+        self.memory_reservation
+    }
+
+    /// Platform of the image to run, e.g. `linux/arm64`
+    pub fn platform(&self) -> &Option<String> {
+        &self.platform
+    }
+
+    /// Metadata attached to the container for diagnostic purposes, see [`RunnableImage::with_metadata`]
+    pub fn metadata(&self) -> Box<dyn Iterator<Item = (&String, &String)> + '_> {
+        Box::new(self.metadata.iter())
+    }
+
+    /// Labels of the container, see [`RunnableImage::with_label`]
+    pub fn labels(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.labels.iter()
+    }
+
+    /// Hostname of the container
+    pub fn hostname(&self) -> &Option<String> {
+        &self.hostname
+    }
+
+    /// Domain name of the container
+    pub fn domainname(&self) -> &Option<String> {
+        &self.domainname
+    }
+
+    /// Capabilities added to the container
+    pub fn cap_add(&self) -> impl Iterator<Item = &Capability> {
+        self.cap_add.iter()
+    }
+
+    /// Capabilities dropped from the container
+    pub fn cap_drop(&self) -> impl Iterator<Item = &Capability> {
+        self.cap_drop.iter()
+    }
+
+    /// User namespace mode of the container, e.g. `host`
+    pub fn userns_mode(&self) -> &Option<String> {
+        &self.userns_mode
+    }
+
+    /// Healthcheck of the container, see [`RunnableImage::with_healthcheck`]
+    pub fn healthcheck(&self) -> &Option<Healthcheck> {
+        &self.healthcheck
+    }
+
+    /// Command, user and working directory of the container, see [`RunnableImage::with_exec_spec`]
+    pub fn exec_spec(&self) -> &Option<ExecSpec> {
+        &self.exec_spec
+    }
+
+    /// Whether the container runs the default command of the image, see
+    /// [`RunnableImage::with_default_cmd`]
+    pub fn default_cmd(&self) -> bool {
+        self.default_cmd
+    }
+
+    /// Arguments the container is started with, which are the ones of the [`ExecSpec`] if any.
+    pub(crate) fn cmd(&self) -> Vec<String> {
+        if self.default_cmd {
+            return Vec::new();
+        }
+
+        match &self.exec_spec {
+            Some(spec) if !spec.cmd.is_empty() => spec.cmd.clone(),
+            _ => self.image_args.clone().into_iterator().collect(),
+        }
+    }
+
+    /// Whether the anonymous volumes of the container are removed along with it.
+    pub fn remove_volumes(&self) -> bool {
+        self.remove_volumes
+    }
+
+    /// Storage driver options of the container, e.g. `size=10G`.
+    pub fn storage_opts(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.storage_opts.iter()
+    }
+
+    /// Maximum number of restarts of an unhealthy container, see [`RunnableImage::with_autoheal`]
+    pub fn autoheal(&self) -> Option<u32> {
+        self.autoheal
+    }
+
+    /// Number of times a container that fails to become ready is recreated, see
+    /// [`RunnableImage::with_startup_retries`]
+    pub fn startup_retries(&self) -> u32 {
+        self.startup_retries
+    }
+
+    /// Resource limits of the container
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// Interval in which the container is inspected by ready conditions that poll its state,
+    /// e.g. [`WaitFor::Healthcheck`].
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// How long the details of the container are cached, see [`RunnableImage::with_inspect_cache`]
+    pub fn inspect_cache_ttl(&self) -> Duration {
+        self.inspect_cache_ttl
+    }
+
+    pub fn log_consumers(&self) -> impl Iterator<Item = &Arc<dyn LogConsumer>> {
+        self.log_consumers.iter()
+    }
+
+    /// Number of recent log lines kept in memory, see [`RunnableImage::with_log_buffer`]
+    pub fn log_buffer(&self) -> Option<usize> {
+        self.log_buffer
+    }
+
     pub fn entrypoint(&self) -> Option<String> {
         self.image.entrypoint()
     }
@@ -277,12 +766,85 @@ impl<I: Image> RunnableImage<I> {
         }
     }
 
+    /// Sets the driver used if the network of this image doesn't exist yet and has to be created.
+    ///
+    /// Has no effect on networks that already exist.
+    pub fn with_network_driver(self, driver: impl Into<String>) -> Self {
+        Self {
+            network_driver: Some(driver.into()),
+            ..self
+        }
+    }
+
+    /// Adds a driver specific option used if the network of this image has to be created.
+    pub fn with_network_driver_opt(
+        self,
+        (key, value): (impl Into<String>, impl Into<String>),
+    ) -> Self {
+        let mut network_driver_opts = self.network_driver_opts;
+        network_driver_opts.insert(key.into(), value.into());
+        Self {
+            network_driver_opts,
+            ..self
+        }
+    }
+
+    /// Connects the container to the additional network `network` under the given `alias`, so
+    /// that peers on that network can reach it by name. The network is created if it doesn't
+    /// exist and removed together with the client.
+    ///
+    /// The container is connected once it is running and before its ready conditions are checked,
+    /// since some network drivers, e.g. `overlay` or `macvlan`, only apply aliases to running
+    /// containers. The network must differ from the one set with [`RunnableImage::with_network`].
+    /// This is only supported by containers started with the async
+    /// [`Http`](crate::clients::Http) client.
+    pub fn with_network_alias(self, network: impl Into<String>, alias: impl Into<String>) -> Self {
+        let mut network_aliases = self.network_aliases;
+        network_aliases
+            .entry(network.into())
+            .or_default()
+            .push(alias.into());
+        Self {
+            network_aliases,
+            ..self
+        }
+    }
+
     pub fn with_env_var(self, (key, value): (impl Into<String>, impl Into<String>)) -> Self {
         let mut env_vars = self.env_vars;
         env_vars.insert(key.into(), value.into());
         Self { env_vars, ..self }
     }
 
+    /// Forwards the value of an environment variable of the host into the container.
+    ///
+    /// The value is read when the container is started. Starting the container panics if the
+    /// variable is not set on the host.
+    pub fn with_env_var_from_host(self, name: impl Into<String>) -> Self {
+        let mut env_vars_from_host = self.env_vars_from_host;
+        env_vars_from_host.insert(name.into());
+        Self {
+            env_vars_from_host,
+            ..self
+        }
+    }
+
+    /// Registers a provider of environment variables that is called when the container is started.
+    ///
+    /// Useful for values that are only known at runtime, e.g. a token fetched from a secret store
+    /// running in another container.
+    pub fn with_env_provider(
+        self,
+        provider: impl Fn() -> HashMap<String, String> + Send + Sync + 'static,
+    ) -> Self {
+        let mut env_providers = self.env_providers;
+        env_providers.push(EnvProvider(Arc::new(provider)));
+        Self {
+            env_providers,
+            ..self
+        }
+    }
+
     pub fn with_host(self, key: impl Into<String>, value: impl Into<Host>) -> Self {
         let mut hosts = self.hosts;
         hosts.insert(key.into(), value.into());
@@ -295,6 +857,92 @@ impl<I: Image> RunnableImage<I> {
         Self { volumes, ..self }
     }
 
+    /// Mounts a host directory into the container with the given access mode.
+    ///
+    /// On SELinux hosts (e.g. Fedora or RHEL), the container won't be able to read the mounted
+    /// files unless they are relabeled, see [`RunnableImage::with_relabeled_bind_mount`].
+    pub fn with_bind_mount(
+        self,
+        (orig, dest): (impl Into<String>, impl Into<String>),
+        mode: BindMode,
+    ) -> Self {
+        self.with_volume((orig.into(), format!("{}:{mode}", dest.into())))
+    }
+
+    /// Mounts a host directory into the container with the given access mode, relabeling its
+    /// content for SELinux hosts.
+    pub fn with_relabeled_bind_mount(
+        self,
+        (orig, dest): (impl Into<String>, impl Into<String>),
+        mode: BindMode,
+        relabel: SelinuxRelabel,
+    ) -> Self {
+        self.with_volume((orig.into(), format!("{}:{mode},{relabel}", dest.into())))
+    }
+
+    /// Mounts a host directory into the container read-write and makes `user`, e.g. `postgres`
+    /// or `1000:1000`, the owner of everything in it once the container runs.
+    ///
+    /// Bind mounts keep the owner of the files on the host, so a container running as a
+    /// non-root user usually can't write to them otherwise. Note that this changes the owner of
+    /// the files on the host as well. The owner is changed before the ready conditions are
+    /// checked, but the command of the container may already be running by then.
+    pub fn with_owned_bind_mount(
+        self,
+        (orig, dest): (impl Into<String>, impl Into<String>),
+        user: impl Into<String>,
+    ) -> Self {
+        let dest = dest.into();
+        let mut mount_owners = self.mount_owners;
+        mount_owners.insert(dest.clone(), user.into());
+        Self {
+            mount_owners,
+            ..self
+        }
+        .with_bind_mount((orig, dest), BindMode::ReadWrite)
+    }
+
+    /// Mounts the Docker socket of the Docker host into the container at `/var/run/docker.sock`,
+    /// which allows tools inside the container to talk to the Docker daemon.
+    ///
+    /// The socket is the one of the daemon the client starting the container is connected to,
+    /// e.g. a rootless daemon, and can be overridden with `TESTCONTAINERS_DOCKER_SOCKET_OVERRIDE`.
+    ///
+    /// # Security
+    ///
+    /// Access to the Docker socket is equivalent to root access on the Docker host, so only use
+    /// this with images you trust.
+    pub fn with_docker_socket(self) -> Self {
+        Self {
+            docker_socket: true,
+            ..self
+        }
+    }
+
+    /// Adds the mount requested by [`RunnableImage::with_docker_socket`] once the client starting
+    /// the container has resolved the Docker `socket` of its daemon.
+    pub(crate) fn with_resolved_docker_socket(self, socket: impl FnOnce() -> String) -> Self {
+        if !self.docker_socket {
+            return self;
+        }
+
+        Self {
+            docker_socket: false,
+            ..self
+        }
+        .with_volume((socket(), DEFAULT_DOCKER_SOCKET))
+    }
+
+    /// Mounts all volumes of another container, identified by its id or name, into this container.
+    pub fn with_volumes_from(self, container: impl Into<String>, mode: BindMode) -> Self {
+        let mut volumes_from = self.volumes_from;
+        volumes_from.insert(container.into(), mode);
+        Self {
+            volumes_from,
+            ..self
+        }
+    }
+
     pub fn with_mapped_port<P: Into<Port>>(self, port: P) -> Self {
         let mut ports = self.ports.unwrap_or_default();
         ports.push(port.into());
@@ -309,6 +957,61 @@ impl<I: Image> RunnableImage<I> {
         Self { privileged, ..self }
     }
 
+    /// Runs the container with the given container runtime instead of the default one of the
+    /// Docker daemon, e.g. `sysbox-runc` to run a privileged workload like Docker in Docker.
+    ///
+    /// The [`Http`](crate::clients::Http) client checks that the Docker daemon knows the runtime
+    /// before creating the container and lists the available runtimes otherwise.
+    pub fn with_runtime(self, runtime: impl Into<String>) -> Self {
+        Self {
+            runtime: Some(runtime.into()),
+            ..self
+        }
+    }
+
+    /// Allocates a pseudo-TTY for the container.
+    ///
+    /// With a TTY, Docker merges the stderr of the container into its stdout. Ready conditions
+    /// waiting for a message should therefore use [`WaitFor::StdOutMessage`], even if the
+    /// message is written to stderr.
+    pub fn with_tty(self, tty: bool) -> Self {
+        Self { tty, ..self }
+    }
+
+    /// Sets the size of the TTY of the container, see [`RunnableImage::with_tty`].
+    ///
+    /// Only supported by the `Http` client, the docker CLI does not offer a flag for it.
+    pub fn with_console_size(self, height: u16, width: u16) -> Self {
+        Self {
+            console_size: Some((height, width)),
+            ..self
+        }
+    }
+
+    /// Publishes the ports returned by [`Image::expose_ports`] on `127.0.0.1` only, instead of
+    /// all interfaces of the host.
+    ///
+    /// Explicitly mapped ports, see [`RunnableImage::with_mapped_port`], are always published on
+    /// `127.0.0.1`.
+    pub fn with_expose_on_localhost(self, expose_on_localhost: bool) -> Self {
+        Self {
+            expose_on_localhost,
+            ..self
+        }
+    }
+
+    /// Publishes no ports of the container on the host at all, neither the ones mapped with
+    /// [`RunnableImage::with_mapped_port`] nor the ones returned by [`Image::expose_ports`].
+    ///
+    /// The container is then only reachable from within its network, e.g. by other containers
+    /// attached to the same network as [`RunnableImage::with_network`].
+    pub fn with_no_published_ports(self) -> Self {
+        Self {
+            publish_ports: false,
+            ..self
+        }
+    }
+
     pub fn with_shm_size(self, bytes: u64) -> Self {
         Self {
             shm_size: Some(bytes),
@@ -316,6 +1019,346 @@ impl<I: Image> RunnableImage<I> {
         }
     }
 
+    /// Mounts a tmpfs of the given size in bytes at `/dev/shm`.
+    ///
+    /// This is a portable alternative to [`RunnableImage::with_shm_size`] for environments that
+    /// don't allow to change the shared memory size.
+    pub fn with_dev_shm_tmpfs(self, bytes: u64) -> Self {
+        let mut tmpfs = self.tmpfs;
+        tmpfs.insert("/dev/shm".to_owned(), format!("size={bytes}"));
+        Self { tmpfs, ..self }
+    }
+
+    /// Sets a soft limit of the memory of the container in bytes.
+    ///
+    /// Unlike a hard limit, the reservation is only enforced when the Docker host is short on memory.
+    pub fn with_memory_reservation(self, bytes: i64) -> Self {
+        Self {
+            memory_reservation: Some(bytes),
+            ..self
+        }
+    }
+
+    /// Sets the platform of the image to run, e.g. `linux/arm64`.
+    ///
+    /// The image is pulled for the given platform even if it differs from the platform of the Docker host,
+    /// which requires emulation to be set up on the host in order to run the container.
+    pub fn with_platform(self, platform: impl Into<String>) -> Self {
+        Self {
+            platform: Some(platform.into()),
+            ..self
+        }
+    }
+
+    /// Attaches metadata such as the name of the test to the container.
+    ///
+    /// The metadata is not passed to Docker, it only shows up in the `Debug` output of the container
+    /// to make it easier to tell containers apart when debugging tests.
+    pub fn with_metadata(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let mut metadata = self.metadata;
+        metadata.insert(key.into(), value.into());
+        Self { metadata, ..self }
+    }
+
+    /// Adds a label to the container.
+    ///
+    /// Unlike [`RunnableImage::with_metadata`], labels are passed to Docker and show up in
+    /// `docker inspect`, which makes them suitable for tracing containers back to their origin.
+    /// See [`run!`](crate::run) for labeling a container with the call site that started it.
+    pub fn with_label(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let mut labels = self.labels;
+        labels.insert(key.into(), value.into());
+        Self { labels, ..self }
+    }
+
+    /// Sets the hostname of the container.
+    pub fn with_hostname(self, hostname: impl Into<String>) -> Self {
+        Self {
+            hostname: Some(hostname.into()),
+            ..self
+        }
+    }
+
+    /// Sets the domain name of the container.
+    ///
+    /// Together with [`RunnableImage::with_hostname`], this sets the fully qualified domain name that
+    /// clustered services often use to address their members.
+    pub fn with_domainname(self, domainname: impl Into<String>) -> Self {
+        Self {
+            domainname: Some(domainname.into()),
+            ..self
+        }
+    }
+
+    /// Adds a Linux capability to the container, e.g. [`Capability::NetAdmin`].
+    pub fn with_cap_add(self, capability: impl Into<Capability>) -> Self {
+        let mut cap_add = self.cap_add;
+        cap_add.insert(capability.into());
+        Self { cap_add, ..self }
+    }
+
+    /// Drops a Linux capability from the container, e.g. [`Capability::Kill`].
+    pub fn with_cap_drop(self, capability: impl Into<Capability>) -> Self {
+        let mut cap_drop = self.cap_drop;
+        cap_drop.insert(capability.into());
+        Self { cap_drop, ..self }
+    }
+
+    /// Sets the user namespace mode of the container, e.g. `host` to opt out of user namespace
+    /// remapping.
+    ///
+    /// This only has an effect if the Docker daemon is configured with `userns-remap`, otherwise
+    /// containers don't run in a separate user namespace in the first place.
+    pub fn with_userns_mode(self, mode: impl Into<String>) -> Self {
+        Self {
+            userns_mode: Some(mode.into()),
+            ..self
+        }
+    }
+
+    /// Defines the healthcheck of the container, overriding the one of the image.
+    ///
+    /// Combine it with [`WaitFor::Healthcheck`] to wait for the container to become healthy.
+    pub fn with_healthcheck(self, healthcheck: Healthcheck) -> Self {
+        Self {
+            healthcheck: Some(healthcheck),
+            ..self
+        }
+    }
+
+    /// Defines the healthcheck of the container as a shell command, e.g.
+    /// `curl -f http://localhost/health || exit 1`, which Docker runs with the default shell of the
+    /// container.
+    ///
+    /// Timings of a healthcheck defined before with [`RunnableImage::with_healthcheck`] are kept,
+    /// only its test is replaced.
+    pub fn with_healthcheck_cmd_shell(self, command: impl Into<String>) -> Self {
+        let mut healthcheck = self.healthcheck.unwrap_or_default();
+        healthcheck.test = vec!["CMD-SHELL".to_owned(), command.into()];
+        Self {
+            healthcheck: Some(healthcheck),
+            ..self
+        }
+    }
+
+    /// Runs the default command of the image, ignoring the arguments of the image and the command
+    /// of an [`ExecSpec`].
+    ///
+    /// The `Http` client keeps the default command even if the entrypoint is overridden, while
+    /// the `Cli` client drops it in that case, just like `docker run --entrypoint` does.
+    pub fn with_default_cmd(self) -> Self {
+        Self {
+            default_cmd: true,
+            ..self
+        }
+    }
+
+    /// Sets the command of the container together with the user and working directory it runs
+    /// with, e.g. to run a one-shot tool against files mounted into the container.
+    pub fn with_exec_spec(self, exec_spec: ExecSpec) -> Self {
+        Self {
+            exec_spec: Some(exec_spec),
+            ..self
+        }
+    }
+
+    /// Registers a hook that is called with the container after it has been created and before it
+    /// is started, e.g. to copy configuration files into it. The container is started once the
+    /// future returned by the hook completes.
+    ///
+    /// ```rust
+    /// use futures::FutureExt;
+    /// use testcontainers::{GenericImage, RunnableImage};
+    ///
+    /// let image = RunnableImage::from(GenericImage::new("redis", "7-alpine")).with_pre_start_hook(
+    ///     |container| async move { container.copy_to("port 7000\n", "/redis.conf").await }.boxed(),
+    /// );
+    /// ```
+    ///
+    /// Replaces any hook registered before. Pre-start hooks are currently only supported by the
+    /// async `Http` client and cannot be combined with [`RunnableImage::with_startup_retries`].
+    #[cfg(feature = "experimental")]
+    pub fn with_pre_start_hook(
+        self,
+        hook: impl FnOnce(crate::core::CreatedContainer) -> futures::future::BoxFuture<'static, ()>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self {
+            pre_start_hook: Some(PreStartHook(Box::new(hook))),
+            ..self
+        }
+    }
+
+    /// Takes the pre-start hook out of the image, so that it can be called.
+    #[cfg(feature = "experimental")]
+    pub(crate) fn take_pre_start_hook(&mut self) -> Option<PreStartHook> {
+        self.pre_start_hook.take()
+    }
+
+    #[cfg(feature = "experimental")]
+    pub(crate) fn has_pre_start_hook(&self) -> bool {
+        self.pre_start_hook.is_some()
+    }
+
+    /// Registers a hook that adjusts the configuration the container is created with, after all
+    /// other settings of the image have been applied.
+    ///
+    /// Allows setting any field of [`bollard::container::Config`], e.g. `stop_timeout` or
+    /// `mac_address`, that has no dedicated builder method. Replaces any hook registered before.
+    /// Config hooks are only supported by the async `Http` client.
+    #[cfg(feature = "experimental")]
+    pub fn with_config_hook(
+        self,
+        hook: impl FnOnce(&mut bollard::container::Config<String>) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            config_hook: Some(ConfigHook(Box::new(hook))),
+            ..self
+        }
+    }
+
+    /// Takes the config hook out of the image, so that it can be called.
+    #[cfg(feature = "experimental")]
+    pub(crate) fn take_config_hook(&mut self) -> Option<ConfigHook> {
+        self.config_hook.take()
+    }
+
+    /// Removes the start interval of the healthcheck for daemons that don't support it.
+    pub(crate) fn without_health_start_interval(self) -> Self {
+        Self {
+            healthcheck: self.healthcheck.map(|healthcheck| Healthcheck {
+                start_interval: None,
+                ..healthcheck
+            }),
+            ..self
+        }
+    }
+
+    /// Sets whether the anonymous volumes of the container are removed when the container is
+    /// removed, which is the default.
+    ///
+    /// Turn this off to keep data written to anonymous volumes around, e.g. to inspect it after a
+    /// test. The volumes have to be removed manually afterwards.
+    pub fn with_remove_volumes(self, remove_volumes: bool) -> Self {
+        Self {
+            remove_volumes,
+            ..self
+        }
+    }
+
+    /// Adds a storage driver option for the container, e.g. `("size", "10G")` to limit the size
+    /// of its root filesystem.
+    ///
+    /// Which options are supported depends on the storage driver of the Docker daemon, e.g. `size`
+    /// only works with `overlay2` on an `xfs` backing filesystem mounted with `pquota`. The
+    /// container fails to start if an option is not supported.
+    pub fn with_storage_opt(self, (key, value): (impl Into<String>, impl Into<String>)) -> Self {
+        let mut storage_opts = self.storage_opts;
+        storage_opts.insert(key.into(), value.into());
+        Self {
+            storage_opts,
+            ..self
+        }
+    }
+
+    /// Restarts the container whenever its healthcheck reports it as unhealthy, at most `max_restarts` times.
+    ///
+    /// The health of the container is monitored in the background from the moment it is ready until
+    /// it is dropped. This is only supported by containers started with the async [`Http`](crate::clients::Http) client.
+    pub fn with_autoheal(self, max_restarts: u32) -> Self {
+        Self {
+            autoheal: Some(max_restarts),
+            ..self
+        }
+    }
+
+    /// Removes and recreates the container if it fails to become ready, at most `retries` times,
+    /// e.g. to cope with images whose startup is occasionally flaky.
+    ///
+    /// Every attempt creates, starts and waits for a fresh container with the same configuration,
+    /// including the changes of a config hook. A pre-start hook could only run for the first
+    /// attempt, so running an image with both panics. This is only supported by containers
+    /// started with the async [`Http`](crate::clients::Http) client.
+    pub fn with_startup_retries(self, retries: u32) -> Self {
+        Self {
+            startup_retries: retries,
+            ..self
+        }
+    }
+
+    /// Limits the resources of the container, e.g. its memory and CPUs, all at once.
+    ///
+    /// Replaces any limits set before.
+    pub fn with_resources(self, resources: Resources) -> Self {
+        Self { resources, ..self }
+    }
+
+    /// Limits the number of processes inside the container, `-1` for unlimited processes.
+    ///
+    /// Useful to protect the Docker host from tests that fork too many processes.
+    pub fn with_pids_limit(self, limit: i64) -> Self {
+        Self {
+            resources: Resources {
+                pids_limit: Some(limit),
+                ..self.resources
+            },
+            ..self
+        }
+    }
+
+    /// Sets the interval in which the container is inspected while waiting for it to become ready.
+    ///
+    /// Defaults to 100 milliseconds.
+    pub fn with_poll_interval(self, poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            ..self
+        }
+    }
+
+    /// Caches the details of the container for the given time, so that accessors like
+    /// `get_host_port_ipv4` and `get_bridge_ip_address` called in quick succession share a single
+    /// request to the Docker daemon.
+    ///
+    /// The cache is cleared whenever the container is started, stopped or restarted. Ready
+    /// conditions always see fresh details. Disabled by default.
+    pub fn with_inspect_cache(self, ttl: Duration) -> Self {
+        Self {
+            inspect_cache_ttl: ttl,
+            ..self
+        }
+    }
+
+    /// Registers a consumer that receives the output of the container while it is running.
+    ///
+    /// The consumer is attached as soon as the container is started and detached when the
+    /// container is dropped. Use [`LoggingConsumer`](crate::core::LoggingConsumer) to forward
+    /// the output to the `log` crate.
+    ///
+    /// Log consumers are currently only supported by the async `Http` client.
+    pub fn with_log_consumer(self, consumer: impl LogConsumer + 'static) -> Self {
+        let mut log_consumers = self.log_consumers;
+        log_consumers.push(Arc::new(consumer));
+        Self {
+            log_consumers,
+            ..self
+        }
+    }
+
+    /// Keeps the most recent `lines` lines of the combined output of the container in memory.
+    ///
+    /// The output is captured in the background from the moment the container is started, so it
+    /// is still available through [`ContainerAsync::recent_logs`](crate::ContainerAsync::recent_logs)
+    /// after Docker has rotated its logs. This is only supported by the async `Http` client.
+    pub fn with_log_buffer(self, lines: usize) -> Self {
+        Self {
+            log_buffer: Some(lines),
+            ..self
+        }
+    }
+
     pub fn with_run_option(self, (key, value): (impl Into<String>, impl Into<String>)) -> Self {
         let mut run_options = self.run_options;
         run_options.insert(key.into(), value.into());
@@ -341,13 +1384,52 @@ impl<I: Image> From<(I, I::Args)> for RunnableImage<I> {
             image_tag: None,
             container_name: None,
             network: None,
+            network_driver: None,
+            network_driver_opts: BTreeMap::default(),
+            network_aliases: BTreeMap::default(),
             env_vars: BTreeMap::default(),
+            env_vars_from_host: BTreeSet::default(),
+            env_providers: Vec::new(),
             hosts: BTreeMap::default(),
             volumes: BTreeMap::default(),
+            volumes_from: BTreeMap::default(),
+            mount_owners: BTreeMap::default(),
+            docker_socket: false,
             ports: None,
             run_options: BTreeMap::default(),
             privileged: false,
+            runtime: None,
+            tty: false,
+            console_size: None,
+            expose_on_localhost: false,
+            publish_ports: true,
             shm_size: None,
+            tmpfs: BTreeMap::default(),
+            memory_reservation: None,
+            platform: None,
+            metadata: BTreeMap::default(),
+            labels: BTreeMap::default(),
+            hostname: None,
+            domainname: None,
+            cap_add: BTreeSet::default(),
+            cap_drop: BTreeSet::default(),
+            userns_mode: None,
+            healthcheck: None,
+            exec_spec: None,
+            default_cmd: false,
+            #[cfg(feature = "experimental")]
+            pre_start_hook: None,
+            #[cfg(feature = "experimental")]
+            config_hook: None,
+            storage_opts: BTreeMap::default(),
+            remove_volumes: true,
+            autoheal: None,
+            startup_retries: 0,
+            resources: Resources::default(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            inspect_cache_ttl: Duration::ZERO,
+            log_consumers: Vec::new(),
+            log_buffer: None,
         }
     }
 }
@@ -368,10 +1450,77 @@ pub enum WaitFor {
     StdOutMessage { message: String },
     /// Wait for a message on the stderr stream of the container's logs.
     StdErrMessage { message: String },
+    /// Wait for a message on either the stdout or the stderr stream of the container's logs.
+    ///
+    /// Useful for images that write their readiness message to different streams depending on
+    /// their version.
+    AnyStreamMessage { message: String },
+    /// Wait for a line matching a regular expression on the stdout stream of the container's
+    /// logs.
+    ///
+    /// Useful for messages that vary, e.g. with the version of the image.
+    StdOutRegex { pattern: LogPattern },
+    /// Wait for a line matching a regular expression on the stderr stream of the container's
+    /// logs.
+    StdErrRegex { pattern: LogPattern },
+    /// Wait for several messages to appear on the stdout stream of the container's logs, one
+    /// after another in the given order.
+    ///
+    /// A message is only looked for in the output after the previous one, so messages that
+    /// appear out of order don't count.
+    StdOutMessageSequence { messages: Vec<String> },
+    /// Wait for a message on the stdout stream of the container's logs, giving up after
+    /// `max_lines` lines without the message.
+    ///
+    /// Bounds the time spent waiting on containers that log a lot but never print the message.
+    StdOutMessageWithinLines { message: String, max_lines: usize },
     /// Wait for a certain amount of time.
     Duration { length: Duration },
     /// Wait for the container's status to become `healthy`.
     Healthcheck,
+    /// Wait for the container's status to become `healthy`, giving up after `timeout`.
+    ///
+    /// Unlike [`WaitFor::Healthcheck`], the panic message of a container that doesn't become
+    /// healthy includes its most recent output and healthcheck results.
+    HealthcheckVerbose { timeout: Duration },
+    /// Wait for the mapped host port of the given container port to accept TCP connections,
+    /// giving up after `timeout`.
+    TcpPort { port: u16, timeout: Duration },
+    /// Wait for the container to keep running for a second without being restarted, tolerating
+    /// at most `max` restarts in total.
+    ///
+    /// Requires a restart policy to be configured for the container.
+    StableAfterRestarts { max: u32 },
+    /// Wait for at least `count` container ports to be mapped to host ports, giving up after
+    /// `timeout`.
+    PortsPublished { count: usize, timeout: Duration },
+    /// Wait for `GET` requests to `path` on the mapped host port of the given container port to
+    /// be answered with `expected_status`, giving up after `timeout`.
+    ///
+    /// If `body_contains` is set, the body of the response has to contain it as well. Failed
+    /// requests are retried after the poll interval of the container, doubling the pause after
+    /// every attempt up to a second.
+    Http {
+        port: u16,
+        path: String,
+        expected_status: u16,
+        body_contains: Option<String>,
+        timeout: Duration,
+    },
+    /// Wait for the gRPC server behind the mapped port of the given container port on the Docker
+    /// host to report `SERVING` through the [health checking protocol], giving up after
+    /// `timeout`.
+    ///
+    /// Checks the overall health of the server if `service` is `None`.
+    ///
+    /// Waiting requires the `grpc` feature, containers panic while waiting without it.
+    ///
+    /// [health checking protocol]: https://github.com/grpc/grpc/blob/master/doc/health-checking.md
+    Grpc {
+        port: u16,
+        service: Option<String>,
+        timeout: Duration,
+    },
 }
 
 impl WaitFor {
@@ -387,6 +1536,115 @@ impl WaitFor {
         }
     }
 
+    /// Waits for a line on stdout matching the regular expression `pattern`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `pattern` is not a valid regular expression.
+    pub fn regex_on_stdout(pattern: &str) -> WaitFor {
+        WaitFor::StdOutRegex {
+            pattern: log_pattern(pattern),
+        }
+    }
+
+    /// Waits for a line on stderr matching the regular expression `pattern`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `pattern` is not a valid regular expression.
+    pub fn regex_on_stderr(pattern: &str) -> WaitFor {
+        WaitFor::StdErrRegex {
+            pattern: log_pattern(pattern),
+        }
+    }
+
+    pub fn message_on_stdout_within_lines<S: Into<String>>(
+        message: S,
+        max_lines: usize,
+    ) -> WaitFor {
+        WaitFor::StdOutMessageWithinLines {
+            message: message.into(),
+            max_lines,
+        }
+    }
+
+    pub fn message_sequence_on_stdout<S: Into<String>>(
+        messages: impl IntoIterator<Item = S>,
+    ) -> WaitFor {
+        WaitFor::StdOutMessageSequence {
+            messages: messages.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn healthcheck_verbose(timeout: Duration) -> WaitFor {
+        WaitFor::HealthcheckVerbose { timeout }
+    }
+
+    pub fn message_on_any_stream<S: Into<String>>(message: S) -> WaitFor {
+        WaitFor::AnyStreamMessage {
+            message: message.into(),
+        }
+    }
+
+    pub fn tcp_port(port: u16, timeout: Duration) -> WaitFor {
+        WaitFor::TcpPort { port, timeout }
+    }
+
+    pub fn stable_after_restarts(max: u32) -> WaitFor {
+        WaitFor::StableAfterRestarts { max }
+    }
+
+    pub fn ports_published(count: usize, timeout: Duration) -> WaitFor {
+        WaitFor::PortsPublished { count, timeout }
+    }
+
+    pub fn http<S: Into<String>>(
+        port: u16,
+        path: S,
+        expected_status: u16,
+        timeout: Duration,
+    ) -> WaitFor {
+        WaitFor::Http {
+            port,
+            path: path.into(),
+            expected_status,
+            body_contains: None,
+            timeout,
+        }
+    }
+
+    pub fn http_body_contains<S: Into<String>, B: Into<String>>(
+        port: u16,
+        path: S,
+        expected_status: u16,
+        body: B,
+        timeout: Duration,
+    ) -> WaitFor {
+        WaitFor::Http {
+            port,
+            path: path.into(),
+            expected_status,
+            body_contains: Some(body.into()),
+            timeout,
+        }
+    }
+
+    pub fn grpc(port: u16, timeout: Duration) -> WaitFor {
+        WaitFor::Grpc {
+            port,
+            service: None,
+            timeout,
+        }
+    }
+
+    pub fn grpc_service<S: Into<String>>(port: u16, service: S, timeout: Duration) -> WaitFor {
+        WaitFor::Grpc {
+            port,
+            service: Some(service.into()),
+            timeout,
+        }
+    }
+
     pub fn seconds(length: u64) -> WaitFor {
         WaitFor::Duration {
             length: Duration::from_secs(length),
@@ -413,8 +1671,224 @@ impl WaitFor {
     }
 }
 
+fn log_pattern(pattern: &str) -> LogPattern {
+    LogPattern::new(pattern)
+        .unwrap_or_else(|e| panic!("invalid regular expression `{pattern}`: {e}"))
+}
+
 impl From<(u16, u16)> for Port {
     fn from((local, internal): (u16, u16)) -> Self {
         Port { local, internal }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::generic::GenericImage;
+
+    struct FakeEnv;
+
+    impl GetEnvValue for FakeEnv {
+        fn get_env_value(key: &str) -> Option<String> {
+            match key {
+                "HOST_TOKEN" => Some("secret".to_owned()),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn should_forward_env_var_from_host() {
+        let image = RunnableImage::from(GenericImage::new("hello", "0.0"))
+            .with_env_var_from_host("HOST_TOKEN");
+
+        assert_eq!(
+            image.runtime_env_vars::<FakeEnv>(),
+            vec![("HOST_TOKEN".to_owned(), "secret".to_owned())]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "environment variable `UNSET_VAR` is not set on the host")]
+    fn should_panic_if_forwarded_env_var_is_not_set_on_host() {
+        let image = RunnableImage::from(GenericImage::new("hello", "0.0"))
+            .with_env_var_from_host("UNSET_VAR");
+
+        image.runtime_env_vars::<FakeEnv>();
+    }
+
+    #[test]
+    fn should_resolve_env_vars_from_provider() {
+        let provider = || {
+            let token = format!("token-{}", 6 * 7);
+
+            HashMap::from([("VAULT_TOKEN".to_owned(), token)])
+        };
+        let image =
+            RunnableImage::from(GenericImage::new("hello", "0.0")).with_env_provider(provider);
+
+        assert_eq!(
+            image.runtime_env_vars::<FakeEnv>(),
+            vec![("VAULT_TOKEN".to_owned(), "token-42".to_owned())]
+        );
+    }
+
+    #[test]
+    fn log_patterns_should_be_compared_by_expression() {
+        assert_eq!(
+            WaitFor::regex_on_stdout("ready.*port 5432"),
+            WaitFor::regex_on_stdout("ready.*port 5432")
+        );
+        assert_ne!(
+            WaitFor::regex_on_stdout("ready.*port 5432"),
+            WaitFor::regex_on_stderr("ready.*port 5432")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid regular expression `ready (port`")]
+    fn malformed_log_pattern_should_be_rejected_on_construction() {
+        WaitFor::regex_on_stdout("ready (port");
+    }
+
+    #[test]
+    fn capabilities_should_be_displayed_with_cap_prefix() {
+        assert_eq!(Capability::NetAdmin.to_string(), "CAP_NET_ADMIN");
+        assert_eq!(Capability::SysPtrace.to_string(), "CAP_SYS_PTRACE");
+        assert_eq!(Capability::Other("BPF".to_owned()).to_string(), "CAP_BPF");
+        assert_eq!(Capability::All.to_string(), "ALL");
+    }
+
+    #[test]
+    fn capabilities_should_be_parsed_from_their_name() {
+        assert_eq!(Capability::from("NET_ADMIN"), Capability::NetAdmin);
+        assert_eq!(Capability::from("cap_sys_ptrace"), Capability::SysPtrace);
+        assert_eq!(Capability::from("bpf"), Capability::Other("BPF".to_owned()));
+        assert_eq!(Capability::from("ALL"), Capability::All);
+        assert_eq!(Capability::from("all"), Capability::All);
+    }
+
+    #[test]
+    fn pids_limit_should_keep_other_resource_limits() {
+        let image = RunnableImage::from(GenericImage::new("hello", "0.0"))
+            .with_resources(Resources {
+                memory: Some(1024),
+                pids_limit: Some(10),
+                ..Default::default()
+            })
+            .with_pids_limit(20);
+
+        assert_eq!(
+            image.resources(),
+            &Resources {
+                memory: Some(1024),
+                pids_limit: Some(20),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn healthcheck_cmd_shell_should_keep_timings_of_healthcheck() {
+        let image = RunnableImage::from(GenericImage::new("hello", "0.0"))
+            .with_healthcheck(Healthcheck {
+                test: vec!["NONE".to_owned()],
+                interval: Some(Duration::from_secs(1)),
+                ..Default::default()
+            })
+            .with_healthcheck_cmd_shell("curl -f http://localhost/health || exit 1");
+
+        assert_eq!(
+            image.healthcheck(),
+            &Some(Healthcheck {
+                test: vec![
+                    "CMD-SHELL".to_owned(),
+                    "curl -f http://localhost/health || exit 1".to_owned()
+                ],
+                interval: Some(Duration::from_secs(1)),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn run_macro_should_label_container_with_call_site() {
+        struct Recorder;
+
+        impl Recorder {
+            fn run<I: Image>(&self, image: impl Into<RunnableImage<I>>) -> RunnableImage<I> {
+                image.into()
+            }
+        }
+
+        let line = line!() + 1;
+        let image = crate::run!(Recorder, GenericImage::new("hello", "0.0"));
+
+        assert_eq!(
+            image.labels().collect::<Vec<_>>(),
+            vec![(
+                &SOURCE_LABEL.to_owned(),
+                &format!("{} ({}:{line})", module_path!(), file!())
+            )]
+        );
+    }
+
+    #[test]
+    fn bind_mount_should_append_selinux_relabeling_suffix() {
+        let image = RunnableImage::from(GenericImage::new("hello", "0.0"))
+            .with_relabeled_bind_mount(
+                ("/shared", "/data"),
+                BindMode::ReadOnly,
+                SelinuxRelabel::Shared,
+            )
+            .with_relabeled_bind_mount(
+                ("/private", "/secrets"),
+                BindMode::ReadWrite,
+                SelinuxRelabel::Private,
+            );
+
+        let volumes = image
+            .volumes()
+            .map(|(orig, dest)| format!("{orig}:{dest}"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            volumes,
+            vec!["/private:/secrets:rw,Z", "/shared:/data:ro,z"]
+        );
+    }
+
+    #[test]
+    fn docker_socket_should_be_mounted_once_the_client_resolves_it() {
+        let image = RunnableImage::from(GenericImage::new("docker", "cli")).with_docker_socket();
+        assert_eq!(image.volumes().count(), 0);
+
+        let image = image.with_resolved_docker_socket(|| "/run/user/1000/docker.sock".to_owned());
+
+        assert_eq!(
+            image.volumes().collect::<Vec<_>>(),
+            vec![(
+                &"/run/user/1000/docker.sock".to_owned(),
+                &"/var/run/docker.sock".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn storage_opts_should_be_collected() {
+        let image = RunnableImage::from(GenericImage::new("hello", "0.0"))
+            .with_storage_opt(("size", "10G"))
+            .with_storage_opt(("size", "20G"))
+            .with_storage_opt(("dm.basesize", "5G"));
+
+        let storage_opts = image.storage_opts().collect::<Vec<_>>();
+
+        assert_eq!(
+            storage_opts,
+            vec![
+                (&"dm.basesize".to_owned(), &"5G".to_owned()),
+                (&"size".to_owned(), &"20G".to_owned())
+            ]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use std::future::Future;
+
+/// A step in the lifecycle of a container, e.g. its creation or removal.
+///
+/// With the `tracing` feature, every step runs inside a `container` span carrying the step, the
+/// image descriptor and the container id, and emits an event when it starts and finishes.
+/// Without it, steps just run their futures.
+pub(crate) struct Step {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl Step {
+    /// Starts the step `name` of the container `id` running `image`. The id of a container that
+    /// is yet to be created can be recorded later on, see [`Step::record_id`].
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn new(name: &'static str, image: &str, id: Option<&str>) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                "container",
+                step = name,
+                image,
+                id = id.map(tracing::field::display)
+            ),
+        }
+    }
+
+    /// Records the id of the container once it has been created.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn record_id(&self, id: &str) {
+        #[cfg(feature = "tracing")]
+        self.span.record("id", id);
+    }
+
+    /// Runs `future` as this step.
+    pub(crate) async fn run<F: Future>(&self, future: F) -> F::Output {
+        #[cfg(feature = "tracing")]
+        {
+            use tracing::Instrument;
+
+            let started = std::time::Instant::now();
+            self.span.in_scope(|| tracing::info!("step started"));
+            let output = future.instrument(self.span.clone()).await;
+            self.span.in_scope(|| {
+                tracing::info!(
+                    elapsed_ms = started.elapsed().as_millis() as u64,
+                    "step finished"
+                )
+            });
+
+            output
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        future.await
+    }
+}
@@ -1,10 +1,77 @@
 #[cfg(feature = "experimental")]
-use futures::{stream::BoxStream, StreamExt};
+use futures::{
+    stream::{BoxStream, Stream},
+    StreamExt,
+};
+use regex::Regex;
+#[cfg(feature = "experimental")]
+use std::{collections::VecDeque, sync::Mutex};
 use std::{
     fmt, io,
     io::{BufRead, BufReader, Read},
+    sync::mpsc,
+    thread,
 };
 
+/// A regular expression to look for in the logs of a container, see
+/// [`WaitFor::StdOutRegex`](crate::core::WaitFor::StdOutRegex).
+///
+/// The expression is compiled when the pattern is created, so a malformed expression is rejected
+/// right away instead of once the container is waited for.
+#[derive(Debug, Clone)]
+pub struct LogPattern(Regex);
+
+impl LogPattern {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern).map(Self)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl From<Regex> for LogPattern {
+    fn from(regex: Regex) -> Self {
+        Self(regex)
+    }
+}
+
+impl PartialEq for LogPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for LogPattern {}
+
+/// What a wait on logs looks for in every line.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum LogMatch<'a> {
+    /// A line containing the message.
+    Message(&'a str),
+    /// A line matching the pattern anywhere.
+    Pattern(&'a LogPattern),
+}
+
+impl LogMatch<'_> {
+    fn is_found_in(&self, text: &str) -> bool {
+        match self {
+            LogMatch::Message(message) => text.contains(message),
+            LogMatch::Pattern(pattern) => pattern.0.is_match(text),
+        }
+    }
+}
+
+impl fmt::Display for LogMatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogMatch::Message(message) => write!(f, "message `{message}`"),
+            LogMatch::Pattern(pattern) => write!(f, "pattern `{}`", pattern.as_str()),
+        }
+    }
+}
+
 #[cfg(feature = "experimental")]
 pub(crate) struct LogStreamAsync<'d> {
     inner: BoxStream<'d, Result<String, std::io::Error>>,
@@ -19,21 +86,311 @@ impl<'d> fmt::Debug for LogStreamAsync<'d> {
 
 #[cfg(feature = "experimental")]
 impl<'d> LogStreamAsync<'d> {
-    pub fn new(stream: BoxStream<'d, Result<String, std::io::Error>>) -> Self {
-        Self { inner: stream }
+    pub fn new(stream: impl Stream<Item = Result<String, io::Error>> + Send + 'd) -> Self {
+        Self {
+            inner: stream.boxed(),
+        }
     }
 
-    pub async fn wait_for_message(mut self, message: &str) -> Result<(), WaitError> {
+    /// Waits for a line containing what `expected` looks for, ignoring the first `skip` lines.
+    ///
+    /// The chunks of the underlying stream are not required to be aligned with lines, they are
+    /// reassembled into lines before being matched. A message split across several chunks is
+    /// found as soon as its last part arrives, even if the line has not been terminated yet.
+    /// Patterns are only matched against complete lines, as the rest of a line can change whether
+    /// they match.
+    ///
+    /// Returns the number of lines up to and including the one containing the message, which
+    /// allows a later wait on the same output to skip the lines that have been looked at already.
+    /// Gives up after comparing `max_lines` lines without finding the message.
+    pub async fn wait_for_match_within_lines(
+        mut self,
+        expected: LogMatch<'_>,
+        skip: usize,
+        max_lines: usize,
+    ) -> Result<usize, WaitError> {
+        let mut skipped = 0;
         let mut lines = vec![];
+        let mut pending = String::new();
 
-        while let Some(line) = self.inner.next().await.transpose()? {
-            if handle_line(line, message, &mut lines) {
-                return Ok(());
+        while let Some(chunk) = self.inner.next().await.transpose()? {
+            pending.push_str(&chunk);
+
+            while let Some(line) = take_line(&mut pending) {
+                if skipped < skip {
+                    skipped += 1;
+                    continue;
+                }
+                if handle_line(line, expected, &mut lines) {
+                    return Ok(skipped + lines.len() + 1);
+                }
+                if lines.len() >= max_lines {
+                    return Err(line_limit_exceeded(lines));
+                }
+            }
+
+            // the message might already be complete while the rest of its line is still pending
+            if skipped == skip
+                && matches!(expected, LogMatch::Message(_))
+                && expected.is_found_in(&pending)
+            {
+                log::info!("Found message after comparing {} lines", lines.len());
+
+                return Ok(skipped + lines.len() + 1);
             }
         }
 
+        if !pending.is_empty() && skipped == skip && handle_line(pending, expected, &mut lines) {
+            return Ok(skipped + lines.len() + 1);
+        }
+
         Err(end_of_stream(lines))
     }
+
+    /// Waits for a line containing the given message, ignoring the first `skip` lines, see
+    /// [`LogStreamAsync::wait_for_match_within_lines`].
+    #[cfg(test)]
+    pub async fn wait_for_message_after(
+        self,
+        message: &str,
+        skip: usize,
+    ) -> Result<usize, WaitError> {
+        self.wait_for_match_within_lines(LogMatch::Message(message), skip, usize::MAX)
+            .await
+    }
+
+    /// Reads the stream until the given message appears and returns everything read up to and
+    /// including the message.
+    pub async fn read_until_message(mut self, message: &str) -> Result<String, WaitError> {
+        let mut output = String::new();
+
+        while let Some(chunk) = self.inner.next().await.transpose()? {
+            // the message might have started in the previous chunk
+            let mut search_from = output.len().saturating_sub(message.len());
+            while !output.is_char_boundary(search_from) {
+                search_from -= 1;
+            }
+            output.push_str(&chunk);
+
+            if let Some(index) = output[search_from..].find(message) {
+                output.truncate(search_from + index + message.len());
+                return Ok(output);
+            }
+        }
+
+        Err(end_of_stream(output.lines().map(String::from).collect()))
+    }
+
+    /// Passes every line of the stream to the given consumer until the stream ends.
+    pub async fn consume_lines(
+        self,
+        container_id: &str,
+        source: LogSource,
+        consumer: &dyn LogConsumer,
+    ) -> Result<(), io::Error> {
+        self.for_each_line(|line| consumer.accept(container_id, source, line))
+            .await
+    }
+
+    /// Appends every line of the stream to the given buffer until the stream ends.
+    pub async fn tail_into(self, buffer: &LogRingBuffer) -> Result<(), io::Error> {
+        self.for_each_line(|line| buffer.push(line)).await
+    }
+
+    async fn for_each_line(mut self, mut f: impl FnMut(&str)) -> Result<(), io::Error> {
+        let mut pending = String::new();
+
+        while let Some(chunk) = self.inner.next().await.transpose()? {
+            pending.push_str(&chunk);
+
+            while let Some(line) = take_line(&mut pending) {
+                f(&line);
+            }
+        }
+
+        if !pending.is_empty() {
+            f(&pending);
+        }
+
+        Ok(())
+    }
+
+    /// Reads the remainder of the stream into a single string.
+    ///
+    /// Only terminates once the underlying stream ends, e.g. because the container exited.
+    pub async fn read_to_string(mut self) -> Result<String, io::Error> {
+        let mut output = String::new();
+
+        while let Some(chunk) = self.inner.next().await.transpose()? {
+            output.push_str(&chunk);
+        }
+
+        Ok(output)
+    }
+}
+
+/// The raw output of a container, which is not required to be valid UTF-8.
+#[cfg(feature = "experimental")]
+pub(crate) struct RawLogStreamAsync<'d> {
+    inner: BoxStream<'d, Result<Vec<u8>, io::Error>>,
+}
+
+#[cfg(feature = "experimental")]
+impl<'d> fmt::Debug for RawLogStreamAsync<'d> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawLogStreamAsync").finish()
+    }
+}
+
+#[cfg(feature = "experimental")]
+impl<'d> RawLogStreamAsync<'d> {
+    pub fn new(stream: impl Stream<Item = Result<Vec<u8>, io::Error>> + Send + 'd) -> Self {
+        Self {
+            inner: stream.boxed(),
+        }
+    }
+
+    /// Converts the output into text, replacing invalid UTF-8 with `U+FFFD`.
+    ///
+    /// A character split across chunks is decoded once all of its bytes have arrived. A character
+    /// cut off by the end of the stream is replaced as well.
+    pub fn into_lossy(self) -> LogStreamAsync<'d> {
+        let stream = self
+            .inner
+            .map(Some)
+            // marks the end of the stream
+            .chain(futures::stream::once(futures::future::ready(None)))
+            .scan(Vec::new(), |pending, chunk| {
+                let decoded = match chunk {
+                    Some(chunk) => Some(chunk.map(|bytes| {
+                        pending.extend_from_slice(&bytes);
+                        let complete = pending.len() - incomplete_suffix_len(pending);
+                        let decoded = String::from_utf8_lossy(&pending[..complete]).into_owned();
+                        pending.drain(..complete);
+
+                        decoded
+                    })),
+                    None if pending.is_empty() => None,
+                    None => Some(Ok(String::from_utf8_lossy(pending).into_owned())),
+                };
+
+                futures::future::ready(Some(decoded))
+            })
+            .filter_map(futures::future::ready);
+
+        LogStreamAsync::new(stream)
+    }
+
+    /// Reads the remainder of the stream.
+    ///
+    /// Only terminates once the underlying stream ends, e.g. because the container exited.
+    pub async fn read_to_end(mut self) -> Result<Vec<u8>, io::Error> {
+        let mut output = Vec::new();
+
+        while let Some(chunk) = self.inner.next().await.transpose()? {
+            output.extend_from_slice(&chunk);
+        }
+
+        Ok(output)
+    }
+}
+
+/// Returns the number of bytes at the end of `bytes` that start a UTF-8 character without
+/// completing it.
+#[cfg(feature = "experimental")]
+fn incomplete_suffix_len(bytes: &[u8]) -> usize {
+    for (index, byte) in bytes.iter().rev().take(3).enumerate() {
+        let length = match byte {
+            // a continuation byte, the character starts further in front
+            0x80..=0xBF => continue,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => return 0,
+        };
+
+        return if length > index + 1 { index + 1 } else { 0 };
+    }
+
+    0
+}
+
+/// The stream of a container a log line was written to.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LogSource {
+    StdOut,
+    StdErr,
+}
+
+impl fmt::Display for LogSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogSource::StdOut => write!(f, "stdout"),
+            LogSource::StdErr => write!(f, "stderr"),
+        }
+    }
+}
+
+/// Receives the output of a container line by line while the container is running.
+///
+/// See [`RunnableImage::with_log_consumer`](crate::RunnableImage::with_log_consumer).
+pub trait LogConsumer: fmt::Debug + Send + Sync {
+    fn accept(&self, container_id: &str, source: LogSource, line: &str);
+}
+
+/// A [`LogConsumer`] that forwards the output of a container to the `log` crate.
+///
+/// Every line is prefixed with the id of the container it originates from.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LoggingConsumer;
+
+impl LogConsumer for LoggingConsumer {
+    fn accept(&self, container_id: &str, source: LogSource, line: &str) {
+        log::info!("[{container_id}] {source}: {line}");
+    }
+}
+
+/// Keeps the most recent lines of the output of a container in memory.
+///
+/// See [`RunnableImage::with_log_buffer`](crate::RunnableImage::with_log_buffer).
+#[cfg(feature = "experimental")]
+#[derive(Debug)]
+pub(crate) struct LogRingBuffer {
+    capacity: usize,
+    lines: Mutex<VecDeque<String>>,
+}
+
+#[cfg(feature = "experimental")]
+impl LogRingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Appends a line, dropping the oldest one if the buffer is full.
+    pub fn push(&self, line: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line.to_owned());
+    }
+
+    /// Returns the buffered lines, oldest first, each terminated by a line break.
+    pub fn contents(&self) -> String {
+        self.lines
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect()
+    }
 }
 
 pub(crate) struct LogStream {
@@ -54,21 +411,150 @@ impl LogStream {
     }
 
     pub fn wait_for_message(self, message: &str) -> Result<(), WaitError> {
+        self.wait_for_message_within_lines(message, usize::MAX)
+    }
+
+    /// Waits for a line matching the given pattern.
+    pub fn wait_for_match(self, pattern: &LogPattern) -> Result<(), WaitError> {
+        self.wait_for_match_within_lines(LogMatch::Pattern(pattern), usize::MAX)
+    }
+
+    /// Waits for a line containing the given message, giving up after comparing `max_lines` lines
+    /// without finding it.
+    pub fn wait_for_message_within_lines(
+        self,
+        message: &str,
+        max_lines: usize,
+    ) -> Result<(), WaitError> {
+        self.wait_for_match_within_lines(LogMatch::Message(message), max_lines)
+    }
+
+    fn wait_for_match_within_lines(
+        self,
+        expected: LogMatch<'_>,
+        max_lines: usize,
+    ) -> Result<(), WaitError> {
         let logs = BufReader::new(self.inner);
         let mut lines = vec![];
 
         for line in logs.lines() {
-            if handle_line(line?, message, &mut lines) {
+            if handle_line(line?, expected, &mut lines) {
                 return Ok(());
             }
+            if lines.len() >= max_lines {
+                return Err(line_limit_exceeded(lines));
+            }
         }
 
         Err(end_of_stream(lines))
     }
+
+    /// Waits for the given messages to appear one after another, each in a line after the line
+    /// containing the previous message.
+    pub fn wait_for_messages_in_order(self, messages: &[String]) -> Result<(), WaitError> {
+        let logs = BufReader::new(self.inner);
+        let mut remaining = messages.iter();
+        let mut next = remaining.next();
+        let mut lines = vec![];
+
+        for line in logs.lines() {
+            let message = match next {
+                Some(message) => message,
+                None => break,
+            };
+            if handle_line(line?, LogMatch::Message(message), &mut lines) {
+                next = remaining.next();
+            }
+        }
+
+        match next {
+            Some(_) => Err(end_of_stream(lines)),
+            None => Ok(()),
+        }
+    }
+
+    /// Interleaves the lines of `stdout` and `stderr` in the order they can be read, calling
+    /// `on_end` once both streams are exhausted.
+    ///
+    /// Both streams are read on threads of their own, so a line that is still incomplete on one
+    /// stream doesn't hold up the other one.
+    pub(crate) fn interleaved(
+        stdout: impl Read + Send + 'static,
+        stderr: impl Read + Send + 'static,
+        on_end: impl FnOnce() + Send + 'static,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stdout = forward_lines(stdout, sender.clone());
+        let stderr = forward_lines(stderr, sender);
+
+        thread::spawn(move || {
+            let _ = stdout.join();
+            let _ = stderr.join();
+            on_end();
+        });
+
+        Self::new(ReceivedLines {
+            receiver,
+            current: io::Cursor::new(Vec::new()),
+        })
+    }
+}
+
+/// Sends the lines of `stream` through `sender` until the stream ends or nobody receives them
+/// anymore.
+fn forward_lines(
+    stream: impl Read + Send + 'static,
+    sender: mpsc::Sender<io::Result<Vec<u8>>>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut stream = BufReader::new(stream);
+        loop {
+            let mut line = Vec::new();
+            let line = match stream.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {
+                    // keeps a last unterminated line apart from the lines of the other stream
+                    if !line.ends_with(b"\n") {
+                        line.push(b'\n');
+                    }
+                    Ok(line)
+                }
+                Err(e) => Err(e),
+            };
+
+            let failed = line.is_err();
+            if sender.send(line).is_err() || failed {
+                break;
+            }
+        }
+    })
+}
+
+/// Reads the lines received through a channel one after another.
+struct ReceivedLines {
+    receiver: mpsc::Receiver<io::Result<Vec<u8>>>,
+    current: io::Cursor<Vec<u8>>,
+}
+
+impl Read for ReceivedLines {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.current.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+
+            match self.receiver.recv() {
+                Ok(line) => self.current = io::Cursor::new(line?),
+                // both streams have ended
+                Err(_) => return Ok(0),
+            }
+        }
+    }
 }
 
-fn handle_line(line: String, message: &str, lines: &mut Vec<String>) -> bool {
-    if line.contains(message) {
+fn handle_line(line: String, expected: LogMatch<'_>, lines: &mut Vec<String>) -> bool {
+    if expected.is_found_in(&line) {
         log::info!("Found message after comparing {} lines", lines.len());
 
         return true;
@@ -79,6 +565,23 @@ fn handle_line(line: String, message: &str, lines: &mut Vec<String>) -> bool {
     false
 }
 
+/// Removes the first complete line from the given buffer.
+#[cfg(feature = "experimental")]
+fn take_line(pending: &mut String) -> Option<String> {
+    let end = pending.find('\n')?;
+    let line = pending.drain(..=end).collect::<String>();
+
+    Some(trim_line_ending(line))
+}
+
+#[cfg(feature = "experimental")]
+fn trim_line_ending(mut line: String) -> String {
+    let len = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(len);
+
+    line
+}
+
 fn end_of_stream(lines: Vec<String>) -> WaitError {
     log::error!(
         "Failed to find message in stream after comparing {} lines.",
@@ -88,12 +591,24 @@ fn end_of_stream(lines: Vec<String>) -> WaitError {
     WaitError::EndOfStream(lines)
 }
 
+fn line_limit_exceeded(lines: Vec<String>) -> WaitError {
+    log::error!(
+        "Gave up looking for message in stream after comparing {} lines.",
+        lines.len()
+    );
+
+    WaitError::LineLimitExceeded(lines)
+}
+
 /// Defines error cases when waiting for a message in a stream.
 #[derive(Debug)]
 pub enum WaitError {
     /// Indicates the stream ended before finding the log line you were looking for.
     /// Contains all the lines that were read for debugging purposes.
     EndOfStream(Vec<String>),
+    /// Indicates the line limit was reached before finding the log line you were looking for.
+    /// Contains all the lines that were read for debugging purposes.
+    LineLimitExceeded(Vec<String>),
     Io(io::Error),
 }
 
@@ -122,4 +637,310 @@ mod tests {
 
         assert!(result.is_ok())
     }
+
+    #[test]
+    fn given_logs_when_line_matches_pattern_should_find_it() {
+        let log_stream = LogStream::new(
+            "database system is starting up\ndatabase system is ready to accept connections on port 5432\n"
+                .as_bytes(),
+        );
+        let pattern = LogPattern::new(r"database system is ready.*port 5432").unwrap();
+
+        let result = log_stream.wait_for_match(&pattern);
+
+        assert!(result.is_ok())
+    }
+
+    #[test]
+    fn interleaved_logs_should_contain_lines_of_both_streams() {
+        let (ended, has_ended) = mpsc::channel();
+        let log_stream = LogStream::interleaved(
+            "starting\nlistening on 8080\n".as_bytes(),
+            "deprecated option".as_bytes(),
+            move || ended.send(()).unwrap(),
+        );
+
+        let mut output = String::new();
+        BufReader::new(log_stream.inner)
+            .read_to_string(&mut output)
+            .unwrap();
+        let mut lines = output.lines().collect::<Vec<_>>();
+        lines.sort_unstable();
+
+        assert_eq!(
+            lines,
+            vec!["deprecated option", "listening on 8080", "starting"]
+        );
+        has_ended
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+    }
+
+    #[test]
+    fn given_logs_when_messages_appear_in_order_should_find_them() {
+        let log_stream =
+            LogStream::new("stage two\nstage one\nstage two\nstage three\n".as_bytes());
+
+        let result = log_stream.wait_for_messages_in_order(&[
+            "stage one".to_owned(),
+            "stage two".to_owned(),
+            "stage three".to_owned(),
+        ]);
+
+        assert!(result.is_ok())
+    }
+
+    #[test]
+    fn given_logs_when_messages_appear_out_of_order_should_not_find_them() {
+        let log_stream = LogStream::new("stage two\nstage one\nstage three\n".as_bytes());
+
+        let result = log_stream.wait_for_messages_in_order(&[
+            "stage one".to_owned(),
+            "stage two".to_owned(),
+            "stage three".to_owned(),
+        ]);
+
+        assert!(
+            matches!(result, Err(WaitError::EndOfStream(lines)) if lines == ["stage two", "stage three"])
+        );
+    }
+
+    #[cfg(feature = "experimental")]
+    fn log_stream_async(chunks: &[&str]) -> LogStreamAsync<'static> {
+        let chunks = chunks
+            .iter()
+            .map(|chunk| Ok(chunk.to_string()))
+            .collect::<Vec<_>>();
+
+        LogStreamAsync::new(futures::stream::iter(chunks))
+    }
+
+    #[cfg(feature = "experimental")]
+    fn raw_log_stream_async(chunks: &[&[u8]]) -> RawLogStreamAsync<'static> {
+        let chunks = chunks
+            .iter()
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect::<Vec<_>>();
+
+        RawLogStreamAsync::new(futures::stream::iter(chunks))
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_invalid_utf8_logs_when_reading_lossily_should_replace_invalid_bytes() {
+        let log_stream = raw_log_stream_async(&[b"binary \xff\xfe\n", b"ready\n"]);
+
+        let result = log_stream
+            .into_lossy()
+            .wait_for_message_after("ready", 0)
+            .await;
+
+        assert_eq!(result.unwrap(), 2);
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_pattern_split_across_chunks_when_waiting_async_should_find_it() {
+        let log_stream = raw_log_stream_async(&[b"listening on port ", b"5432 (v16.1)\n"]);
+        let pattern = LogPattern::new(r"port \d+ \(v16\.\d+\)").unwrap();
+
+        let result = log_stream
+            .into_lossy()
+            .wait_for_match_within_lines(LogMatch::Pattern(&pattern), 0, usize::MAX)
+            .await;
+
+        assert_eq!(result.unwrap(), 1);
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_pattern_matching_start_of_line_only_when_waiting_async_should_not_find_it() {
+        let log_stream = raw_log_stream_async(&[b"listening on port 5", b"432\n"]);
+        let pattern = LogPattern::new(r"port 5$").unwrap();
+
+        let result = log_stream
+            .into_lossy()
+            .wait_for_match_within_lines(LogMatch::Pattern(&pattern), 0, usize::MAX)
+            .await;
+
+        assert!(
+            matches!(result, Err(WaitError::EndOfStream(lines)) if lines == ["listening on port 5432"])
+        );
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_character_split_across_chunks_when_reading_lossily_should_decode_it() {
+        let log_stream = raw_log_stream_async(&[b"caf\xc3", b"\xa9\n"]);
+
+        let output = log_stream.into_lossy().read_to_string().await.unwrap();
+
+        assert_eq!(output, "caf\u{e9}\n");
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_character_cut_off_by_end_of_logs_when_reading_lossily_should_replace_it() {
+        let log_stream = raw_log_stream_async(&[b"caf\xc3"]);
+
+        let output = log_stream.into_lossy().read_to_string().await.unwrap();
+
+        assert_eq!(output, "caf\u{fffd}");
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_invalid_utf8_logs_when_reading_raw_should_keep_bytes() {
+        let log_stream = raw_log_stream_async(&[b"\xff", b"\xfe"]);
+
+        let output = log_stream.read_to_end().await.unwrap();
+
+        assert_eq!(output, b"\xff\xfe");
+    }
+
+    #[test]
+    fn given_noisy_logs_when_line_budget_is_exhausted_should_give_up() {
+        let log_stream = LogStream::new("one\ntwo\nthree\nready\n".as_bytes());
+
+        let result = log_stream.wait_for_message_within_lines("ready", 3);
+
+        assert!(
+            matches!(result, Err(WaitError::LineLimitExceeded(lines)) if lines == ["one", "two", "three"])
+        );
+    }
+
+    #[test]
+    fn given_logs_when_message_is_within_line_budget_should_find_it() {
+        let log_stream = LogStream::new("one\ntwo\nready\n".as_bytes());
+
+        let result = log_stream.wait_for_message_within_lines("ready", 3);
+
+        assert!(result.is_ok())
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_noisy_async_logs_when_line_budget_is_exhausted_should_give_up() {
+        // the stream never ends, like the output of a running container
+        let log_stream = LogStreamAsync::new(
+            futures::stream::iter(vec![Ok("one\ntwo\n".to_owned())])
+                .chain(futures::stream::pending()),
+        );
+
+        let result = log_stream
+            .wait_for_match_within_lines(LogMatch::Message("ready"), 0, 2)
+            .await;
+
+        assert!(
+            matches!(result, Err(WaitError::LineLimitExceeded(lines)) if lines == ["one", "two"])
+        );
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_async_logs_when_line_contains_message_should_find_it() {
+        let log_stream = log_stream_async(&["Message one\n", "Message two\n", "Message three\n"]);
+
+        let result = log_stream.wait_for_message_after("Message three", 0).await;
+
+        assert!(result.is_ok())
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_async_logs_when_skipping_lines_should_not_find_message_in_them() {
+        let log_stream = log_stream_async(&["ready\nwaiting\n", "ready\n"]);
+
+        let lines = log_stream.wait_for_message_after("ready", 1).await.unwrap();
+
+        assert_eq!(lines, 3);
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_async_logs_when_message_is_only_in_skipped_lines_should_not_find_it() {
+        let log_stream = log_stream_async(&["ready\n", "waiting\n"]);
+
+        let result = log_stream.wait_for_message_after("ready", 1).await;
+
+        assert!(matches!(result, Err(WaitError::EndOfStream(lines)) if lines == ["waiting"]));
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_async_logs_when_reading_until_message_should_return_output_up_to_it() {
+        let log_stream = log_stream_async(&["one\ntwo\nDO", "NE\nthree\n"]);
+
+        let output = log_stream.read_until_message("DONE").await.unwrap();
+
+        assert_eq!(output, "one\ntwo\nDONE");
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_async_logs_without_message_reading_until_it_should_fail() {
+        let log_stream = log_stream_async(&["one\n", "two\n"]);
+
+        let result = log_stream.read_until_message("DONE").await;
+
+        assert!(matches!(result, Err(WaitError::EndOfStream(lines)) if lines == ["one", "two"]));
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_async_logs_when_line_is_split_across_chunks_should_find_it() {
+        let log_stream = log_stream_async(&["Message one\nMess", "age tw", "o\nMessage three\n"]);
+
+        let result = log_stream.wait_for_message_after("Message two", 0).await;
+
+        assert!(result.is_ok())
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_async_logs_when_message_is_split_across_chunks_should_find_it_without_line_end()
+    {
+        let chunks = vec![
+            Ok("starting up\nwaiting for dat".to_owned()),
+            Ok("abase ready to accept connections".to_owned()),
+        ];
+        // the container keeps running without terminating the line
+        let stream = futures::stream::iter(chunks).chain(futures::stream::pending());
+        let log_stream = LogStreamAsync::new(stream);
+
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            log_stream.wait_for_message_after("database ready", 0),
+        )
+        .await
+        .expect("message should be found before the stream ends");
+
+        assert!(result.is_ok())
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_async_logs_when_tailing_into_full_buffer_should_keep_most_recent_lines() {
+        let log_stream = log_stream_async(&["one\ntwo\nthr", "ee\nfour\n", "five"]);
+        let buffer = LogRingBuffer::new(3);
+
+        log_stream.tail_into(&buffer).await.unwrap();
+
+        assert_eq!(buffer.contents(), "three\nfour\nfive\n");
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn given_async_logs_when_chunk_contains_several_lines_should_report_each_line() {
+        let log_stream = log_stream_async(&["Message one\r\nMessage two\n", "Message three"]);
+
+        let result = log_stream.wait_for_message_after("Message four", 0).await;
+
+        match result {
+            Err(WaitError::EndOfStream(lines)) => {
+                assert_eq!(lines, vec!["Message one", "Message two", "Message three"])
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
 }
@@ -1,5 +1,5 @@
 use bollard_stubs::models::{PortBinding, PortMap};
-use std::{collections::HashMap, net::IpAddr};
+use std::{collections::HashMap, fmt, net::IpAddr};
 
 /// The exposed ports of a running container.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -40,8 +40,69 @@ impl Ports {
     pub fn map_to_host_port_ipv6(&self, internal_port: u16) -> Option<u16> {
         self.ipv6_mapping.get(&internal_port).cloned()
     }
+
+    /// Returns the internal ports along with the host ports they are mapped to, on the host's IPv4
+    /// interfaces.
+    pub fn ipv4_mappings(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.ipv4_mapping
+            .iter()
+            .map(|(internal, host)| (*internal, *host))
+    }
+
+    /// Returns the number of container ports that are mapped to a host port, on the host's IPv4
+    /// or IPv6 interfaces.
+    pub fn published_count(&self) -> usize {
+        self.ipv4_mapping
+            .keys()
+            .chain(
+                self.ipv6_mapping
+                    .keys()
+                    .filter(|port| !self.ipv4_mapping.contains_key(port)),
+            )
+            .count()
+    }
+
+    /// Returns the host port for the given internal port, on the host's IPv6 interfaces.
+    ///
+    /// In contrast to [`Ports::map_to_host_port_ipv6`], the error tells apart a port that is not
+    /// mapped from a Docker host that does not publish any ports on IPv6 interfaces at all.
+    pub fn try_map_to_host_port_ipv6(&self, internal_port: u16) -> Result<u16, PortMappingError> {
+        if let Some(port) = self.map_to_host_port_ipv6(internal_port) {
+            return Ok(port);
+        }
+
+        if self.ipv6_mapping.is_empty() && !self.ipv4_mapping.is_empty() {
+            return Err(PortMappingError::Ipv6NotEnabled(internal_port));
+        }
+
+        Err(PortMappingError::NotMapped(internal_port))
+    }
+}
+
+/// Defines error cases when looking up the host port of a container port.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PortMappingError {
+    /// The container port is not mapped to any host port.
+    NotMapped(u16),
+    /// No port of the container is published on IPv6 interfaces, which usually means that IPv6
+    /// is not enabled on the Docker host.
+    Ipv6NotEnabled(u16),
+}
+
+impl fmt::Display for PortMappingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortMappingError::NotMapped(port) => write!(f, "port {port} is not mapped"),
+            PortMappingError::Ipv6NotEnabled(port) => write!(
+                f,
+                "port {port} is not mapped on IPv6, the container has no IPv6 port mappings at all; is IPv6 enabled on the Docker host?"
+            ),
+        }
+    }
 }
 
+impl std::error::Error for PortMappingError {}
+
 impl From<PortMap> for Ports {
     fn from(ports: PortMap) -> Self {
         let mut ipv4_mapping = HashMap::new();
@@ -352,4 +413,39 @@ mod tests {
 
         assert_eq!(parsed_ports, expected_ports)
     }
+
+    #[test]
+    fn ipv6_lookup_should_report_disabled_ipv6() {
+        let mut ports = Ports::default();
+        ports.ipv4_mapping.insert(8080, 33076);
+
+        assert_eq!(
+            ports.try_map_to_host_port_ipv6(8080),
+            Err(PortMappingError::Ipv6NotEnabled(8080))
+        );
+    }
+
+    #[test]
+    fn ipv6_lookup_should_report_unmapped_port() {
+        let mut ports = Ports::default();
+        ports.ipv4_mapping.insert(8080, 33076);
+        ports.ipv6_mapping.insert(8080, 49718);
+
+        assert_eq!(ports.try_map_to_host_port_ipv6(8080), Ok(49718));
+        assert_eq!(
+            ports.try_map_to_host_port_ipv6(9090),
+            Err(PortMappingError::NotMapped(9090))
+        );
+    }
+
+    #[test]
+    fn published_count_should_count_each_container_port_once() {
+        let mut ports = Ports::default();
+        ports.ipv4_mapping.insert(8080, 33076);
+        ports.ipv4_mapping.insert(9090, 33077);
+        ports.ipv6_mapping.insert(8080, 49718);
+        ports.ipv6_mapping.insert(7070, 49719);
+
+        assert_eq!(ports.published_count(), 3);
+    }
 }
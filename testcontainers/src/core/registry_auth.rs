@@ -0,0 +1,139 @@
+//! Reuses the registry credentials the Docker CLI stores in its `config.json`.
+
+use crate::core::env::GetEnvValue;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bollard::auth::DockerCredentials;
+use serde::Deserialize;
+use std::{collections::HashMap, path::PathBuf};
+
+/// The key under which the Docker CLI stores the credentials of Docker Hub.
+const DOCKER_HUB_KEY: &str = "https://index.docker.io/v1/";
+
+#[derive(Debug, Default, Deserialize)]
+struct DockerConfig {
+    #[serde(default)]
+    auths: HashMap<String, AuthEntry>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AuthEntry {
+    auth: Option<String>,
+    identitytoken: Option<String>,
+}
+
+/// Looks up the credentials for the registry of `image` in the `config.json` of the Docker CLI.
+///
+/// The file is read from `$DOCKER_CONFIG` if set, and from `$HOME/.docker` otherwise.
+/// Credentials kept in credential helpers are not supported.
+pub(crate) fn credentials_for<E>(image: &str) -> Option<DockerCredentials>
+where
+    E: GetEnvValue,
+{
+    let directory = E::get_env_value("DOCKER_CONFIG")
+        .map(PathBuf::from)
+        .or_else(|| E::get_env_value("HOME").map(|home| PathBuf::from(home).join(".docker")))?;
+    let config = std::fs::read_to_string(directory.join("config.json")).ok()?;
+
+    parse_credentials(&config, registry_of(image))
+}
+
+/// Returns the registry an image is pulled from, as it appears in the keys of `config.json`.
+fn registry_of(image: &str) -> &str {
+    match image.split_once('/') {
+        Some((first, _)) if first.contains(['.', ':']) || first == "localhost" => first,
+        _ => DOCKER_HUB_KEY,
+    }
+}
+
+fn parse_credentials(config: &str, registry: &str) -> Option<DockerCredentials> {
+    let config: DockerConfig = match serde_json::from_str(config) {
+        Ok(config) => config,
+        Err(e) => {
+            log::warn!("Failed to parse docker config, pulling without credentials: {e}");
+            return None;
+        }
+    };
+
+    let (server, entry) = config.auths.iter().find(|(server, _)| {
+        let server = server
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_end_matches('/');
+        let registry = registry
+            .trim_start_matches("https://")
+            .trim_end_matches('/');
+
+        server == registry || server.split_once('/').map(|(host, _)| host) == Some(registry)
+    })?;
+
+    let (username, password) = match &entry.auth {
+        Some(auth) => {
+            let decoded = STANDARD.decode(auth).ok()?;
+            let decoded = String::from_utf8(decoded).ok()?;
+            let (username, password) = decoded.split_once(':')?;
+
+            (Some(username.to_owned()), Some(password.to_owned()))
+        }
+        None => (None, None),
+    };
+
+    Some(DockerCredentials {
+        username,
+        password,
+        identitytoken: entry.identitytoken.clone(),
+        serveraddress: Some(server.clone()),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "auths": {
+            "https://index.docker.io/v1/": { "auth": "aHViLXVzZXI6aHViLXBhc3N3b3Jk" },
+            "ghcr.io": { "auth": "Z2hjci11c2VyOmdoY3ItcGFzc3dvcmQ=" },
+            "registry.example.com:5000": { "identitytoken": "token" }
+        },
+        "credsStore": "desktop"
+    }"#;
+
+    #[test]
+    fn registry_should_be_docker_hub_for_images_without_registry() {
+        assert_eq!(registry_of("redis:7-alpine"), DOCKER_HUB_KEY);
+        assert_eq!(registry_of("bitnami/redis:7"), DOCKER_HUB_KEY);
+        assert_eq!(registry_of("ghcr.io/org/image:1.0"), "ghcr.io");
+        assert_eq!(registry_of("localhost:5000/image:1.0"), "localhost:5000");
+    }
+
+    #[test]
+    fn credentials_should_be_decoded_from_auth() {
+        let credentials = parse_credentials(CONFIG, "ghcr.io").unwrap();
+
+        assert_eq!(credentials.username.as_deref(), Some("ghcr-user"));
+        assert_eq!(credentials.password.as_deref(), Some("ghcr-password"));
+        assert_eq!(credentials.serveraddress.as_deref(), Some("ghcr.io"));
+    }
+
+    #[test]
+    fn credentials_should_be_found_for_docker_hub() {
+        let credentials = parse_credentials(CONFIG, registry_of("redis:7-alpine")).unwrap();
+
+        assert_eq!(credentials.username.as_deref(), Some("hub-user"));
+        assert_eq!(credentials.password.as_deref(), Some("hub-password"));
+    }
+
+    #[test]
+    fn credentials_should_include_identity_token() {
+        let credentials = parse_credentials(CONFIG, "registry.example.com:5000").unwrap();
+
+        assert_eq!(credentials.identitytoken.as_deref(), Some("token"));
+        assert_eq!(credentials.username, None);
+    }
+
+    #[test]
+    fn no_credentials_should_be_found_for_unknown_registry() {
+        assert!(parse_credentials(CONFIG, "quay.io").is_none());
+    }
+}
@@ -0,0 +1,132 @@
+use bollard_stubs::models::{
+    ContainerInspectResponse, ContainerStateStatusEnum, RestartPolicyNameEnum,
+};
+use std::time::{Duration, Instant};
+
+/// How long a container has to keep running without being restarted to be considered stable.
+const STABILITY_WINDOW: Duration = Duration::from_secs(1);
+
+/// Tracks the restarts of a container while waiting for it to become stable.
+///
+/// Used to evaluate [`WaitFor::StableAfterRestarts`](crate::core::WaitFor::StableAfterRestarts).
+#[derive(Debug)]
+pub(crate) struct RestartTracker {
+    max_restarts: u32,
+    restart_count: Option<i64>,
+    stable_since: Instant,
+}
+
+impl RestartTracker {
+    pub(crate) fn new(max_restarts: u32) -> Self {
+        Self {
+            max_restarts,
+            restart_count: None,
+            stable_since: Instant::now(),
+        }
+    }
+
+    /// Records the latest state of the container and returns whether it is considered stable.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the container restarted more often than allowed or if it exited
+    /// without being restarted.
+    pub(crate) fn observe(&mut self, details: &ContainerInspectResponse) -> bool {
+        let restart_count = details.restart_count.unwrap_or_default();
+        if restart_count > i64::from(self.max_restarts) {
+            panic!(
+                "container restarted {restart_count} times, exceeding the maximum of {} restarts",
+                self.max_restarts
+            )
+        }
+
+        let state = details.state.clone().unwrap_or_default();
+        let exited = matches!(
+            state.status,
+            Some(ContainerStateStatusEnum::EXITED) | Some(ContainerStateStatusEnum::DEAD)
+        );
+        if exited && !will_be_restarted(details) {
+            panic!(
+                "container exited with code {} and is not restarted",
+                state.exit_code.unwrap_or_default()
+            )
+        }
+
+        if state.running != Some(true) || self.restart_count != Some(restart_count) {
+            self.restart_count = Some(restart_count);
+            self.stable_since = Instant::now();
+
+            return false;
+        }
+
+        self.stable_since.elapsed() >= STABILITY_WINDOW
+    }
+}
+
+/// Whether the restart policy of an exited container restarts it.
+pub(crate) fn will_be_restarted(details: &ContainerInspectResponse) -> bool {
+    let restart_policy = details
+        .host_config
+        .as_ref()
+        .and_then(|host_config| host_config.restart_policy.as_ref())
+        .and_then(|restart_policy| restart_policy.name.as_ref());
+    let exit_code = details
+        .state
+        .as_ref()
+        .and_then(|state| state.exit_code)
+        .unwrap_or_default();
+
+    match restart_policy {
+        Some(RestartPolicyNameEnum::ALWAYS) | Some(RestartPolicyNameEnum::UNLESS_STOPPED) => true,
+        Some(RestartPolicyNameEnum::ON_FAILURE) => exit_code != 0,
+        Some(RestartPolicyNameEnum::NO) | Some(RestartPolicyNameEnum::EMPTY) | None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard_stubs::models::ContainerState;
+
+    fn details(status: ContainerStateStatusEnum, restart_count: i64) -> ContainerInspectResponse {
+        ContainerInspectResponse {
+            restart_count: Some(restart_count),
+            state: Some(ContainerState {
+                running: Some(status == ContainerStateStatusEnum::RUNNING),
+                status: Some(status),
+                exit_code: Some(1),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn container_is_stable_after_running_for_stability_window() {
+        let mut tracker = RestartTracker::new(1);
+
+        assert!(!tracker.observe(&details(ContainerStateStatusEnum::RUNNING, 0)));
+        assert!(!tracker.observe(&details(ContainerStateStatusEnum::RESTARTING, 1)));
+        assert!(!tracker.observe(&details(ContainerStateStatusEnum::RUNNING, 1)));
+
+        tracker.stable_since -= STABILITY_WINDOW;
+
+        assert!(tracker.observe(&details(ContainerStateStatusEnum::RUNNING, 1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "container restarted 3 times, exceeding the maximum of 2 restarts")]
+    fn container_restarting_too_often_should_panic() {
+        let mut tracker = RestartTracker::new(2);
+
+        tracker.observe(&details(ContainerStateStatusEnum::RESTARTING, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "container exited with code 1 and is not restarted")]
+    fn container_exiting_without_restart_should_panic() {
+        let mut tracker = RestartTracker::new(2);
+
+        tracker.observe(&details(ContainerStateStatusEnum::EXITED, 0));
+    }
+}
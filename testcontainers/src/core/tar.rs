@@ -0,0 +1,209 @@
+//! Tar archives as exchanged with Docker when copying files into and out of containers.
+//!
+//! Archives copied into containers are written by hand, so that files can be streamed into them.
+//! Archives copied out of containers are read with the `tar` crate, which understands the
+//! extended headers Docker writes, e.g. for long file names.
+
+use std::{
+    io::Read,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const BLOCK_SIZE: usize = 512;
+
+/// Builds a tar archive that contains a single regular file with the given permission bits.
+///
+/// # Panics
+///
+/// This function panics if `name` does not fit into the 100 bytes of a tar header.
+pub(crate) fn single_file(name: &str, contents: &[u8], mode: u32) -> Vec<u8> {
+    let size = contents.len() as u64;
+    let mut archive = Vec::with_capacity(BLOCK_SIZE + contents.len() + 3 * BLOCK_SIZE);
+    archive.extend_from_slice(&header(name, size, mode));
+    archive.extend_from_slice(contents);
+    archive.extend_from_slice(&trailer(size));
+
+    archive
+}
+
+/// Builds the header of a regular file of `size` bytes with the given permission bits.
+///
+/// The header has to be followed by the contents of the file and the [`trailer`].
+///
+/// # Panics
+///
+/// This function panics if `name` does not fit into the 100 bytes of a tar header.
+pub(crate) fn header(name: &str, size: u64, mode: u32) -> [u8; BLOCK_SIZE] {
+    assert!(
+        name.len() < 100,
+        "file name `{name}` is too long to be copied into a container"
+    );
+
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or_default();
+
+    let mut header = [0u8; BLOCK_SIZE];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], u64::from(mode & 0o7777));
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size);
+    write_octal(&mut header[136..148], mtime);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // the checksum is computed with the checksum field itself filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|byte| u32::from(*byte)).sum();
+    header[148..155].copy_from_slice(format!("{checksum:06o}\0").as_bytes());
+
+    header
+}
+
+/// Builds the padding of a file of `size` bytes to a full block followed by the end-of-archive
+/// marker.
+pub(crate) fn trailer(size: u64) -> Vec<u8> {
+    let padding = (BLOCK_SIZE - (size % BLOCK_SIZE as u64) as usize) % BLOCK_SIZE;
+
+    vec![0; padding + 2 * BLOCK_SIZE]
+}
+
+/// Writes `value` as a zero-padded, NUL-terminated octal number filling `field`.
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    let octal = format!("{value:0digits$o}");
+    assert!(
+        octal.len() == digits,
+        "{value} does not fit into tar header"
+    );
+
+    field[..digits].copy_from_slice(octal.as_bytes());
+    field[digits] = 0;
+}
+
+/// Returns the contents of the first entry of `archive`, or `None` if it is not a regular file.
+///
+/// Extended headers in front of the entry, e.g. for long file names or large files, are applied
+/// to it.
+pub(crate) fn first_file(archive: &[u8]) -> Option<Vec<u8>> {
+    let mut archive = ::tar::Archive::new(archive);
+    let mut entry = archive.entries().ok()?.next()?.ok()?;
+    if !entry.header().entry_type().is_file() {
+        return None;
+    }
+
+    let mut contents = Vec::new();
+    entry.read_to_end(&mut contents).ok()?;
+
+    Some(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads the path, permission bits and contents of all entries of `archive`.
+    fn entries(archive: &[u8]) -> Vec<(String, u32, Vec<u8>)> {
+        ::tar::Archive::new(archive)
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let path = entry.path().unwrap().to_string_lossy().into_owned();
+                let mode = entry.header().mode().unwrap();
+                let mut contents = Vec::new();
+                entry.read_to_end(&mut contents).unwrap();
+
+                (path, mode, contents)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn single_file_archive_should_contain_file_with_mode() {
+        let archive = single_file("script.sh", b"#!/bin/sh\necho hello\n", 0o755);
+
+        assert_eq!(
+            entries(&archive),
+            vec![(
+                "script.sh".to_owned(),
+                0o755,
+                b"#!/bin/sh\necho hello\n".to_vec()
+            )]
+        );
+    }
+
+    #[test]
+    fn single_file_archive_should_be_padded_to_full_blocks() {
+        let archive = single_file("script.sh", b"#!/bin/sh\necho hello\n", 0o755);
+
+        assert_eq!(archive.len(), 4 * BLOCK_SIZE);
+    }
+
+    #[test]
+    fn trailer_should_pad_contents_to_full_blocks() {
+        assert_eq!(trailer(0).len(), 2 * BLOCK_SIZE);
+        assert_eq!(trailer(1).len(), 3 * BLOCK_SIZE - 1);
+        assert_eq!(trailer(BLOCK_SIZE as u64).len(), 2 * BLOCK_SIZE);
+    }
+
+    #[test]
+    fn header_should_describe_file_followed_by_trailer() {
+        let mut archive = header("config.toml", 7, 0o644).to_vec();
+        archive.extend_from_slice(b"key = 1");
+        archive.extend_from_slice(&trailer(7));
+
+        assert_eq!(
+            entries(&archive),
+            vec![("config.toml".to_owned(), 0o644, b"key = 1".to_vec())]
+        );
+    }
+
+    #[test]
+    fn first_file_should_return_contents_of_single_file_archive() {
+        let archive = single_file("app.log", b"started", 0o644);
+
+        assert_eq!(first_file(&archive), Some(b"started".to_vec()));
+    }
+
+    #[test]
+    fn first_file_should_apply_extended_headers() {
+        let mut builder = ::tar::Builder::new(Vec::new());
+        builder
+            .append_pax_extensions([("size", &b"7"[..]), ("path", &b"var/log/app.log"[..])])
+            .unwrap();
+        // the size of the header is overridden by the extended header in front of it
+        let mut header = ::tar::Header::new_ustar();
+        header.set_path("app.log").unwrap();
+        header.set_mode(0o644);
+        header.set_size(0);
+        header.set_cksum();
+        builder.append(&header, &b"started"[..]).unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        assert_eq!(
+            entries(&archive),
+            vec![("var/log/app.log".to_owned(), 0o644, b"started".to_vec())]
+        );
+        assert_eq!(first_file(&archive), Some(b"started".to_vec()));
+    }
+
+    #[test]
+    fn first_file_should_reject_directories_and_empty_archives() {
+        let mut builder = ::tar::Builder::new(Vec::new());
+        let mut directory = ::tar::Header::new_ustar();
+        directory.set_path("logs/").unwrap();
+        directory.set_entry_type(::tar::EntryType::Directory);
+        directory.set_size(0);
+        directory.set_cksum();
+        builder.append(&directory, std::io::empty()).unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        assert_eq!(first_file(&archive), None);
+        assert_eq!(first_file(&trailer(0)), None);
+        assert_eq!(first_file(&[]), None);
+    }
+}
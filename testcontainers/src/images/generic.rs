@@ -1,5 +1,5 @@
 use crate::{core::WaitFor, Image, ImageArgs};
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, time::Duration};
 
 impl ImageArgs for Vec<String> {
     fn into_iterator(self) -> Box<dyn Iterator<Item = String>> {
@@ -57,6 +57,13 @@ impl GenericImage {
         self
     }
 
+    /// Waits for the given message on stdout and afterwards for the given port to accept connections,
+    /// giving up on the port after `timeout`.
+    pub fn with_wait_for_log_and_port(self, message: &str, port: u16, timeout: Duration) -> Self {
+        self.with_wait_for(WaitFor::message_on_stdout(message))
+            .with_wait_for(WaitFor::tcp_port(port, timeout))
+    }
+
     pub fn with_entrypoint(mut self, entrypoint: &str) -> Self {
         self.entrypoint = Some(entrypoint.to_string());
         self
@@ -119,4 +126,21 @@ mod tests {
         assert_eq!(second_key, "two-key");
         assert_eq!(second_value, "two-value");
     }
+
+    #[test]
+    fn should_wait_for_log_and_port() {
+        let image = GenericImage::new("simple_web_server", "latest").with_wait_for_log_and_port(
+            "server is ready",
+            80,
+            Duration::from_secs(30),
+        );
+
+        assert_eq!(
+            image.ready_conditions(),
+            vec![
+                WaitFor::message_on_stdout("server is ready"),
+                WaitFor::tcp_port(80, Duration::from_secs(30))
+            ]
+        );
+    }
 }
@@ -42,13 +42,43 @@ pub use crate::core::{Container, Image, ImageArgs, RunnableImage};
 #[cfg(feature = "experimental")]
 pub use crate::core::ContainerAsync;
 
+#[cfg(feature = "experimental")]
+pub use crate::context::TestContext;
+
 #[cfg(feature = "watchdog")]
 pub(crate) mod watchdog;
 
 /// All available Docker clients.
 pub mod clients;
+#[cfg(feature = "experimental")]
+mod context;
 pub mod core;
 /// All available Docker images.
 mod images;
+#[cfg(feature = "presets")]
+pub mod presets;
 
 pub use images::generic::GenericImage;
+
+/// Runs an image like [`Cli::run`](clients::Cli::run) or [`Http::run`](clients::Http::run) but labels
+/// the container with the module, file and line of the call site.
+///
+/// The label is stored under [`SOURCE_LABEL`](core::SOURCE_LABEL), so a container that was leaked
+/// by a test can be traced back to it with `docker inspect` or
+/// `docker ps --format '{{.Label "org.testcontainers.source"}}'`.
+///
+/// ```no_run
+/// use testcontainers::{clients, run, GenericImage};
+///
+/// let docker = clients::Cli::default();
+/// let container = run!(docker, GenericImage::new("hello-world", "latest"));
+/// ```
+#[macro_export]
+macro_rules! run {
+    ($docker:expr, $image:expr $(,)?) => {
+        $docker.run($crate::RunnableImage::from($image).with_label(
+            $crate::core::SOURCE_LABEL,
+            concat!(module_path!(), " (", file!(), ":", line!(), ")"),
+        ))
+    };
+}
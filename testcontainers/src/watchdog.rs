@@ -4,23 +4,21 @@ use signal_hook::{
     consts::{SIGINT, SIGQUIT, SIGTERM},
     iterator::Signals,
 };
-use std::{collections::BTreeSet, sync::Mutex, thread};
+use std::{collections::BTreeMap, sync::Mutex, thread};
 
 static WATCHDOG: Lazy<Mutex<Watchdog>> = Lazy::new(|| {
     thread::spawn(move || {
-        let signal_docker = Cli::default();
         let mut signals =
             Signals::new([SIGTERM, SIGINT, SIGQUIT]).expect("failed to register signal handler");
 
         for signal in &mut signals {
-            for container_id in WATCHDOG
-                .lock()
-                .map(|s| s.containers.clone())
-                .unwrap_or_default()
-            {
-                signal_docker.stop(&container_id);
-                signal_docker.rm(&container_id);
-            }
+            reap(
+                WATCHDOG
+                    .lock()
+                    .map(|s| s.containers.clone())
+                    .unwrap_or_default(),
+                Cli::for_docker_host,
+            );
 
             let _ = signal_hook::low_level::emulate_default_handler(signal);
         }
@@ -31,22 +29,104 @@ static WATCHDOG: Lazy<Mutex<Watchdog>> = Lazy::new(|| {
 
 #[derive(Default)]
 pub(crate) struct Watchdog {
-    containers: BTreeSet<String>,
+    /// The observed containers along with the address of the Docker daemon they run on, if it
+    /// differs from the one configured in the environment.
+    containers: BTreeMap<String, Option<String>>,
 }
 
-/// Register a container for observation
-pub(crate) fn register(container_id: String) {
+impl Watchdog {
+    fn register(&mut self, container_id: String, docker_host: Option<String>) {
+        self.containers.insert(container_id, docker_host);
+    }
+
+    fn unregister(&mut self, container_id: &str) {
+        self.containers.remove(container_id);
+    }
+}
+
+/// Register a container running on the given Docker daemon for observation
+pub(crate) fn register(container_id: String, docker_host: Option<String>) {
     WATCHDOG
         .lock()
         .expect("failed to access watchdog")
-        .containers
-        .insert(container_id);
+        .register(container_id, docker_host);
 }
 /// Unregisters a container for observation
 pub(crate) fn unregister(container_id: &str) {
     WATCHDOG
         .lock()
         .expect("failed to access watchdog")
-        .containers
-        .remove(container_id);
+        .unregister(container_id);
+}
+
+/// Stops and removes the observed containers, each through the client `cli_for` its Docker daemon.
+fn reap(containers: BTreeMap<String, Option<String>>, cli_for: impl Fn(Option<String>) -> Cli) {
+    for (container_id, docker_host) in containers {
+        let signal_docker = cli_for(docker_host);
+        signal_docker.stop(&container_id);
+        signal_docker.rm(&container_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_container_should_remember_its_docker_host() {
+        let mut watchdog = Watchdog::default();
+
+        watchdog.register("local".to_owned(), None);
+        watchdog.register("remote".to_owned(), Some("tcp://remote:2375".to_owned()));
+        watchdog.unregister("local");
+
+        assert_eq!(
+            watchdog.containers,
+            BTreeMap::from([("remote".to_owned(), Some("tcp://remote:2375".to_owned()))])
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reaping_should_stop_and_remove_containers_on_their_docker_host() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("watchdog-reap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let calls = dir.join("calls");
+        let docker = dir.join("docker");
+        // records each call and echoes the container id like `docker rm` does
+        std::fs::write(
+            &docker,
+            format!(
+                "#!/bin/sh\necho \"${{DOCKER_HOST:-local}} $*\" >> {}\necho \"$*\"\n",
+                calls.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&docker, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        reap(
+            BTreeMap::from([
+                ("local-container".to_owned(), None),
+                (
+                    "remote-container".to_owned(),
+                    Some("tcp://remote:2375".to_owned()),
+                ),
+            ]),
+            |docker_host| Cli::with_binary(&docker, docker_host),
+        );
+
+        let calls = std::fs::read_to_string(calls).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            calls.lines().collect::<Vec<_>>(),
+            vec![
+                "local stop local-container",
+                "local rm -f -v local-container",
+                "tcp://remote:2375 stop remote-container",
+                "tcp://remote:2375 rm -f -v remote-container",
+            ]
+        );
+    }
 }
@@ -1,3 +1,4 @@
+use std::time::Duration;
 use testcontainers::{
     core::{Host, WaitFor},
     *,
@@ -122,3 +123,77 @@ fn generic_image_port_not_exposed() {
     // Without exposing the port with `with_exposed_port()`, we cannot get a mapping to it.
     node.get_host_port_ipv4(target_port);
 }
+
+#[test]
+fn generic_image_waits_for_log_and_port() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Cli::default();
+
+    let generic = GenericImage::new("simple_web_server", "latest").with_wait_for_log_and_port(
+        "server is ready",
+        80,
+        Duration::from_secs(30),
+    );
+
+    let node = docker.run(generic);
+    let port = node.get_host_port_ipv4(80);
+    assert!(reqwest::blocking::get(format!("http://127.0.0.1:{port}"))
+        .unwrap()
+        .status()
+        .is_success());
+}
+
+#[test]
+fn generic_image_becomes_ready_after_tolerated_restart() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Cli::default();
+
+    // fails on its first start only, the marker file survives the restart of the container
+    let generic = GenericImage::new("redis", "7-alpine")
+        .with_entrypoint("sh")
+        .with_wait_for(WaitFor::stable_after_restarts(1));
+    let generic = RunnableImage::from((
+        generic,
+        vec![
+            "-c".to_owned(),
+            "if [ -f /restarted ]; then sleep 60; else touch /restarted; exit 1; fi".to_owned(),
+        ],
+    ))
+    .with_run_option(("--restart", "on-failure"));
+
+    docker.run(generic);
+}
+
+#[test]
+#[should_panic(expected = "exceeding the maximum of 2 restarts")]
+fn generic_image_restarting_in_a_loop_is_not_ready() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Cli::default();
+
+    let generic = GenericImage::new("redis", "7-alpine")
+        .with_entrypoint("sh")
+        .with_wait_for(WaitFor::stable_after_restarts(2));
+    let generic = RunnableImage::from((generic, vec!["-c".to_owned(), "exit 1".to_owned()]))
+        .with_run_option(("--restart", "always"));
+
+    docker.run(generic);
+}
+
+#[test]
+fn generic_image_waits_for_message_on_any_stream() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Cli::default();
+
+    let generic = GenericImage::new("redis", "7-alpine")
+        .with_entrypoint("sh")
+        .with_wait_for(WaitFor::message_on_any_stream("ready on stderr"));
+    let generic = RunnableImage::from((
+        generic,
+        vec![
+            "-c".to_owned(),
+            "echo starting; echo ready on stderr >&2; sleep 60".to_owned(),
+        ],
+    ));
+
+    docker.run(generic);
+}
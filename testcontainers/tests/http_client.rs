@@ -78,3 +78,329 @@ async fn start_containers_in_parallel() {
     let timeout = Duration::from_secs(5);
     let _containers = tokio::time::timeout(timeout, run_all).await.unwrap();
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn ensure_stable_for_passes_for_long_running_container() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let image = GenericImage::new("simple_web_server", "latest")
+        .with_wait_for(WaitFor::message_on_stdout("server is ready"));
+    let container = docker.run(image).await;
+
+    container.ensure_stable_for(Duration::from_secs(1)).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[should_panic(expected = "exited with code 0")]
+async fn ensure_stable_for_panics_if_container_exits() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    // hello-world exits right after printing its greeting
+    let container = docker.run(HelloWorld).await;
+
+    container.ensure_stable_for(Duration::from_secs(5)).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[should_panic(expected = "exited with code 1 before it was running")]
+async fn run_panics_if_entrypoint_exits_immediately() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let image = GenericImage::new("redis", "7-alpine")
+        .with_entrypoint("false")
+        .with_wait_for(WaitFor::message_on_stdout("Ready to accept connections"));
+
+    let _container = docker.run(image).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn changes_should_report_created_files() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let image = GenericImage::new("redis", "7-alpine")
+        .with_entrypoint("sh")
+        .with_wait_for(WaitFor::message_on_stdout("file created"));
+    let image = RunnableImage::from((
+        image,
+        vec![
+            "-c".to_owned(),
+            "touch /created.txt && echo file created && sleep 60".to_owned(),
+        ],
+    ));
+    let container = docker.run(image).await;
+
+    let changes = container.changes().await;
+
+    assert!(
+        changes.contains(&core::FilesystemChange {
+            path: "/created.txt".to_owned(),
+            kind: core::ChangeKind::Added,
+        }),
+        "changes are {changes:?}"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn restart_and_wait_waits_until_container_is_ready_again() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let image = GenericImage::new("simple_web_server", "latest")
+        .with_wait_for_log_and_port("server is ready", 80, Duration::from_secs(30));
+    let container = docker.run(image).await;
+
+    container.restart_and_wait().await;
+
+    let port = container.get_host_port_ipv4(80).await;
+    assert_eq!(
+        "foo",
+        reqwest::get(format!("http://127.0.0.1:{port}"))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn inspect_returns_details_of_running_container() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let image = GenericImage::new("simple_web_server", "latest")
+        .with_wait_for(WaitFor::message_on_stdout("server is ready"));
+    let container = docker.run(image).await;
+
+    let details = container.inspect().await;
+
+    assert_eq!(details.id.as_deref(), Some(container.id()));
+    assert_eq!(details.state.unwrap().running, Some(true));
+    assert_eq!(
+        details.config.unwrap().image.as_deref(),
+        Some("simple_web_server:latest")
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn run_all_starts_all_containers() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let images = (0..6)
+        .map(|_| {
+            GenericImage::new("simple_web_server", "latest")
+                .with_wait_for(WaitFor::message_on_stdout("server is ready"))
+        })
+        .collect();
+    let containers = docker.run_all_with_concurrency(images, 3).await;
+
+    assert_eq!(containers.len(), 6);
+    for container in &containers {
+        let details = container.inspect().await;
+        assert_eq!(details.state.unwrap().running, Some(true));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[should_panic(expected = "failed to pull image `does-not-exist/simple_web_server:latest`")]
+async fn run_all_panics_if_one_container_fails_to_start() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let images = vec![
+        GenericImage::new("simple_web_server", "latest")
+            .with_wait_for(WaitFor::message_on_stdout("server is ready")),
+        GenericImage::new("does-not-exist/simple_web_server", "latest"),
+    ];
+    docker.run_all(images).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn docker_socket_can_be_used_from_within_container() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let image = GenericImage::new("docker", "cli")
+        .with_wait_for(WaitFor::message_on_stdout("CONTAINER ID"));
+    let image = RunnableImage::from((image, vec!["docker".to_owned(), "ps".to_owned()]))
+        .with_docker_socket();
+    let container = docker.run(image).await;
+
+    let mounts = container.inspect().await.mounts.unwrap();
+    assert!(
+        mounts
+            .iter()
+            .any(|mount| mount.destination.as_deref() == Some("/var/run/docker.sock")),
+        "mounts are {mounts:?}"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn copy_to_with_mode_should_preserve_executable_bit() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    // the container runs the script as soon as it has been copied, exiting with its exit code
+    let image = GenericImage::new("redis", "7-alpine")
+        .with_entrypoint("sh")
+        .with_wait_for(WaitFor::message_on_stdout("waiting for script"));
+    let image = RunnableImage::from((
+        image,
+        vec![
+            "-c".to_owned(),
+            "echo waiting for script; while [ ! -f /script.sh ]; do sleep 0.1; done; exec /script.sh"
+                .to_owned(),
+        ],
+    ));
+    let container = docker.run(image).await;
+
+    container
+        .copy_to_with_mode("#!/bin/sh\nexit 7\n", "/script.sh", 0o755)
+        .await;
+
+    let exit_code = loop {
+        let state = container.inspect().await.state.unwrap();
+        if state.running != Some(true) {
+            break state.exit_code;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+    assert_eq!(exit_code, Some(7));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn copy_file_to_should_stream_large_file_into_container() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let src = std::env::temp_dir().join("testcontainers-copy-file-to.bin");
+    std::fs::write(&src, vec![42u8; 8 * 1024 * 1024]).unwrap();
+
+    // the container exits successfully once the file has the expected size
+    let image = GenericImage::new("redis", "7-alpine")
+        .with_entrypoint("sh")
+        .with_wait_for(WaitFor::message_on_stdout("waiting for file"));
+    let image = RunnableImage::from((
+        image,
+        vec![
+            "-c".to_owned(),
+            "echo waiting for file; while [ ! -f /done ]; do sleep 0.1; done; [ \"$(stat -c %s /data.bin)\" = 8388608 ]"
+                .to_owned(),
+        ],
+    ));
+    let container = docker.run(image).await;
+
+    container.copy_file_to(&src, "/data.bin").await;
+    container.copy_to("", "/done").await;
+    std::fs::remove_file(&src).unwrap();
+
+    let exit_code = loop {
+        let state = container.inspect().await.state.unwrap();
+        if state.running != Some(true) {
+            break state.exit_code;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+    assert_eq!(exit_code, Some(0));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn waits_for_message_on_any_stream() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let image = GenericImage::new("redis", "7-alpine")
+        .with_entrypoint("sh")
+        .with_wait_for(WaitFor::message_on_any_stream("ready on stderr"));
+    let image = RunnableImage::from((
+        image,
+        vec![
+            "-c".to_owned(),
+            "echo starting; echo ready on stderr >&2; sleep 60".to_owned(),
+        ],
+    ));
+
+    docker.run(image).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pids_limit_prevents_forking_too_many_processes() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let image = GenericImage::new("redis", "7-alpine")
+        .with_entrypoint("sh")
+        .with_wait_for(WaitFor::message_on_stderr("can't fork"));
+    let image = RunnableImage::from((
+        image,
+        vec![
+            "-c".to_owned(),
+            // forking happens in a subshell, which exits as soon as a fork fails
+            "(for i in 1 2 3 4 5 6 7 8 9 10; do sleep 60 & done); sleep 60".to_owned(),
+        ],
+    ))
+    .with_pids_limit(5);
+
+    docker.run(image).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn env_reports_overridden_variables() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let image = GenericImage::new("simple_web_server", "latest")
+        .with_env_var("LEVEL", "image")
+        .with_env_var("IMAGE_ONLY", "yes")
+        .with_wait_for(WaitFor::message_on_stdout("server is ready"));
+    let image = RunnableImage::from(image).with_env_var(("LEVEL", "runnable"));
+    let container = docker.run(image).await;
+
+    let env = container.env().await;
+
+    assert_eq!(env.get("LEVEL").map(String::as_str), Some("runnable"));
+    assert_eq!(env.get("IMAGE_ONLY").map(String::as_str), Some("yes"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn log_line_count_counts_printed_lines() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let image = GenericImage::new("redis", "7-alpine")
+        .with_entrypoint("sh")
+        .with_wait_for(WaitFor::message_on_stdout("line 5"));
+    let image = RunnableImage::from((
+        image,
+        vec![
+            "-c".to_owned(),
+            "for i in 1 2 3 4 5; do echo line $i; done; sleep 60".to_owned(),
+        ],
+    ));
+    let container = docker.run(image).await;
+
+    assert_eq!(container.log_line_count().await, 5);
+    assert_eq!(container.log_byte_count().await, 35);
+}
+
+/// Requires credentials for a private registry in the `config.json` of the Docker CLI and the
+/// name of a private image in `TESTCONTAINERS_PRIVATE_IMAGE`, e.g. `ghcr.io/org/image:tag`.
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires credentials for a private registry"]
+async fn pulls_private_image_with_credentials_from_docker_config() {
+    let _ = pretty_env_logger::try_init();
+    let docker = clients::Http::default();
+
+    let private_image = std::env::var("TESTCONTAINERS_PRIVATE_IMAGE")
+        .expect("TESTCONTAINERS_PRIVATE_IMAGE is not set");
+    let (name, tag) = private_image
+        .rsplit_once(':')
+        .expect("TESTCONTAINERS_PRIVATE_IMAGE has no tag");
+
+    docker.run(GenericImage::new(name, tag)).await;
+}
@@ -38,6 +38,23 @@ fn main() -> Result<()> {
     }
     eprintln!("Built simple_web_server:latest");
 
+    let output = Command::new("docker")
+        .arg("build")
+        .arg("--file")
+        .arg(format!(
+            "{cwd}/src/dockerfiles/grpc_health_server.dockerfile"
+        ))
+        .arg("--force-rm")
+        .arg("--tag")
+        .arg("grpc_health_server:latest")
+        .arg(".")
+        .output()?;
+    if !output.status.success() {
+        eprintln!("stderr: {}", String::from_utf8(output.stderr)?);
+        bail!("unable to build grpc_health_server:latest");
+    }
+    eprintln!("Built grpc_health_server:latest");
+
     // trigger recompilation when dockerfiles are modified
     println!("cargo:rerun-if-changed=src/dockerfiles");
     println!("cargo:rerun-if-changed=.dockerignore");
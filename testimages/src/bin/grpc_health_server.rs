@@ -0,0 +1,37 @@
+//! A gRPC server implementing nothing but the health checking protocol, to test waiting for a
+//! container to report `SERVING`.
+//!
+//! The server reports `NOT_SERVING` for its first second and `SERVING` afterwards, for the
+//! overall health (the empty service name) as well as the `greeter` service. Other services are
+//! unknown.
+use std::{net::SocketAddr, time::Duration};
+
+use tonic::transport::Server;
+use tonic_health::ServingStatus;
+
+#[tokio::main]
+async fn main() {
+    let (mut reporter, service) = tonic_health::server::health_reporter();
+    for name in ["", "greeter"] {
+        reporter
+            .set_service_status(name, ServingStatus::NotServing)
+            .await;
+    }
+
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        for name in ["", "greeter"] {
+            reporter
+                .set_service_status(name, ServingStatus::Serving)
+                .await;
+        }
+    });
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], 50051));
+    println!("grpc health server is listening");
+    Server::builder()
+        .add_service(service)
+        .serve(addr)
+        .await
+        .unwrap();
+}